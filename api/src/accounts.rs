@@ -0,0 +1,708 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::invoices::deserialize_nullable;
+use crate::ledger::{AccountType, SystemAccount};
+
+/// Section of the cash flow statement an account's cash movements belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CashFlowActivity {
+    Operating,
+    Investing,
+    Financing,
+}
+
+impl CashFlowActivity {
+    /// Activity of an account without an explicit one: owner's money is
+    /// financing, everything else operating.
+    pub fn default_for(account_type: AccountType) -> Self {
+        match account_type {
+            AccountType::Equity => CashFlowActivity::Financing,
+            _ => CashFlowActivity::Operating,
+        }
+    }
+}
+
+/// An account of the standard chart every user starts with.
+#[derive(Debug)]
+pub(crate) struct DefaultAccount {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub account_type: AccountType,
+    pub parent_code: Option<&'static str>,
+    pub system_key: Option<SystemAccount>,
+    pub cash_flow_activity: Option<CashFlowActivity>,
+}
+
+const fn header(
+    code: &'static str,
+    name: &'static str,
+    account_type: AccountType,
+) -> DefaultAccount {
+    DefaultAccount {
+        code,
+        name,
+        account_type,
+        parent_code: None,
+        system_key: None,
+        cash_flow_activity: None,
+    }
+}
+
+const fn sub(
+    parent: &DefaultAccount,
+    code: &'static str,
+    name: &'static str,
+    system_key: Option<SystemAccount>,
+) -> DefaultAccount {
+    DefaultAccount {
+        code,
+        name,
+        account_type: parent.account_type,
+        parent_code: Some(parent.code),
+        system_key,
+        cash_flow_activity: None,
+    }
+}
+
+impl DefaultAccount {
+    const fn reported_as(self, activity: CashFlowActivity) -> Self {
+        DefaultAccount {
+            cash_flow_activity: Some(activity),
+            ..self
+        }
+    }
+}
+
+const ASSETS: DefaultAccount = header("1000", "Assets", AccountType::Asset);
+const LIABILITIES: DefaultAccount = header("2000", "Liabilities", AccountType::Liability);
+const EQUITY: DefaultAccount = header("3000", "Equity", AccountType::Equity);
+const INCOME: DefaultAccount = header("4000", "Income", AccountType::Income);
+const EXPENSES: DefaultAccount = header("5000", "Expenses", AccountType::Expense);
+
+/// Standard small-business chart of accounts. Parents come before their
+/// sub-accounts; expense accounts are named after common expense categories,
+/// which expenses are posted to by name.
+pub(crate) const DEFAULT_CHART: &[DefaultAccount] = &[
+    ASSETS,
+    sub(&ASSETS, "1010", "Cash", Some(SystemAccount::Cash)),
+    sub(
+        &ASSETS,
+        "1100",
+        "Accounts Receivable",
+        Some(SystemAccount::AccountsReceivable),
+    ),
+    sub(&ASSETS, "1200", "Prepaid Expenses", None),
+    sub(&ASSETS, "1500", "Equipment", None).reported_as(CashFlowActivity::Investing),
+    LIABILITIES,
+    sub(&LIABILITIES, "2100", "Accounts Payable", None),
+    sub(
+        &LIABILITIES,
+        "2200",
+        "Sales Tax Payable",
+        Some(SystemAccount::SalesTaxPayable),
+    ),
+    sub(
+        &LIABILITIES,
+        "2300",
+        "Customer Credits",
+        Some(SystemAccount::CustomerCredits),
+    ),
+    sub(&LIABILITIES, "2400", "Credit Card", None),
+    sub(&LIABILITIES, "2700", "Loans Payable", None).reported_as(CashFlowActivity::Financing),
+    EQUITY,
+    sub(&EQUITY, "3100", "Owner's Equity", None),
+    sub(&EQUITY, "3200", "Owner's Drawings", None),
+    sub(&EQUITY, "3900", "Retained Earnings", None),
+    INCOME,
+    sub(&INCOME, "4100", "Sales", Some(SystemAccount::Sales)),
+    sub(
+        &INCOME,
+        "4800",
+        "Foreign Exchange Gain/Loss",
+        Some(SystemAccount::ExchangeGainLoss),
+    ),
+    sub(&INCOME, "4900", "Other Income", None),
+    EXPENSES,
+    sub(&EXPENSES, "5100", "Advertising", None),
+    sub(&EXPENSES, "5200", "Bank Fees", None),
+    sub(&EXPENSES, "5300", "Meals", None),
+    sub(&EXPENSES, "5400", "Office Supplies", None),
+    sub(&EXPENSES, "5500", "Professional Fees", None),
+    sub(&EXPENSES, "5600", "Rent", None),
+    sub(&EXPENSES, "5700", "Software", None),
+    sub(&EXPENSES, "5800", "Travel", None),
+    sub(&EXPENSES, "5850", "Utilities", None),
+    sub(
+        &EXPENSES,
+        "5900",
+        "Other Expenses",
+        Some(SystemAccount::OtherExpenses),
+    ),
+];
+
+/// The default chart's entry for a system account.
+pub(crate) fn default_account(system_key: SystemAccount) -> &'static DefaultAccount {
+    DEFAULT_CHART
+        .iter()
+        .find(|account| account.system_key == Some(system_key))
+        .expect("every system account is in the default chart")
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Account {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub code: String,
+    pub name: String,
+    pub account_type: AccountType,
+    /// Role in automatic postings; system accounts cannot be deleted.
+    pub system_key: Option<SystemAccount>,
+    pub description: Option<String>,
+    /// `None` reports the account under [`CashFlowActivity::default_for`] its type.
+    pub cash_flow_activity: Option<CashFlowActivity>,
+    pub is_archived: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAccountRequest {
+    #[validate(length(min = 1, max = 20))]
+    pub code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub account_type: AccountType,
+    /// Account this is a sub-account of; must be of the same type.
+    pub parent_id: Option<Uuid>,
+    pub description: Option<String>,
+    pub cash_flow_activity: Option<CashFlowActivity>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateAccountRequest {
+    #[validate(length(min = 1, max = 20))]
+    pub code: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    /// Only accounts without postings or sub-accounts can change type.
+    pub account_type: Option<AccountType>,
+    /// `null` moves the account back to the top level.
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub parent_id: Option<Option<Uuid>>,
+    pub description: Option<String>,
+    pub cash_flow_activity: Option<CashFlowActivity>,
+    /// System accounts receive automatic postings and cannot be archived.
+    pub is_archived: Option<bool>,
+}
+
+/// Inserts an account of the default chart under its parent, unless the
+/// user already has an account with its code or role.
+pub(crate) async fn insert_default_account(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    account: &DefaultAccount,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO accounts (
+            user_id, code, name, account_type, system_key, cash_flow_activity, parent_id
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6,
+            (SELECT id FROM accounts WHERE user_id = $1 AND code = $7)
+        )
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(account.code)
+    .bind(account.name)
+    .bind(account.account_type)
+    .bind(account.system_key)
+    .bind(account.cash_flow_activity)
+    .bind(account.parent_code)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Seeds the default chart of accounts the first time a user is seen.
+pub(crate) async fn ensure_default_chart(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let seed_error = |e: sqlx::Error| {
+        tracing::error!("Failed to seed chart of accounts: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to set up chart of accounts" })),
+        )
+    };
+
+    let seeded = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM account_charts WHERE user_id = $1)",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(seed_error)?;
+    if seeded {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await.map_err(seed_error)?;
+
+    // Concurrent first requests race here; only the one that records the chart seeds it
+    let claimed =
+        sqlx::query("INSERT INTO account_charts (user_id) VALUES ($1) ON CONFLICT DO NOTHING")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(seed_error)?
+            .rows_affected()
+            == 1;
+
+    if claimed {
+        for account in DEFAULT_CHART {
+            insert_default_account(&mut tx, user_id, account)
+                .await
+                .map_err(seed_error)?;
+        }
+    }
+
+    tx.commit().await.map_err(seed_error)
+}
+
+async fn lock_account(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Account, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = $1 AND user_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch account: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch account" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Account not found" })),
+            )
+        })
+}
+
+/// Checks that `parent_id` is one of the user's accounts of `account_type`,
+/// and not `account_id` itself or one of its sub-accounts.
+async fn validate_parent(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    parent_id: Uuid,
+    account_type: AccountType,
+    account_id: Option<Uuid>,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let ancestors = sqlx::query_as::<_, (Uuid, AccountType)>(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id, account_type FROM accounts WHERE id = $1 AND user_id = $2
+            UNION
+            SELECT a.id, a.parent_id, a.account_type
+            FROM accounts a
+            JOIN ancestors ON a.id = ancestors.parent_id
+        )
+        SELECT id, account_type FROM ancestors
+        "#,
+    )
+    .bind(parent_id)
+    .bind(user_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch parent account: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch parent account" })),
+        )
+    })?;
+
+    let Some((_, parent_type)) = ancestors.first() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Parent account not found" })),
+        ));
+    };
+
+    if *parent_type != account_type {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Sub-accounts must have the same type as their parent" })),
+        ));
+    }
+
+    if account_id.is_some_and(|account_id| ancestors.iter().any(|(id, _)| *id == account_id)) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "An account cannot be nested under itself" })),
+        ));
+    }
+
+    Ok(())
+}
+
+fn code_conflict(e: &sqlx::Error) -> Option<(StatusCode, Json<Value>)> {
+    e.as_database_error()
+        .is_some_and(|db| db.is_unique_violation())
+        .then(|| {
+            (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "An account with this code already exists" })),
+            )
+        })
+}
+
+pub async fn list_accounts(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<Account>>, (StatusCode, Json<Value>)> {
+    let accounts =
+        sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE user_id = $1 ORDER BY code")
+            .bind(user_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch accounts: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch accounts" })),
+                )
+            })?;
+
+    Ok(Json(accounts))
+}
+
+pub async fn create_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateAccountRequest>,
+) -> Result<(StatusCode, Json<Account>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create account" })),
+        )
+    })?;
+
+    if let Some(parent_id) = req.parent_id {
+        validate_parent(&mut conn, user_id, parent_id, req.account_type, None).await?;
+    }
+
+    let account = sqlx::query_as::<_, Account>(
+        r#"
+        INSERT INTO accounts (
+            user_id, code, name, account_type, parent_id, description, cash_flow_activity
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.code.trim())
+    .bind(req.name)
+    .bind(req.account_type)
+    .bind(req.parent_id)
+    .bind(req.description)
+    .bind(req.cash_flow_activity)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        code_conflict(&e).unwrap_or_else(|| {
+            tracing::error!("Failed to create account: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to create account" })),
+            )
+        })
+    })?;
+
+    Ok((StatusCode::CREATED, Json(account)))
+}
+
+pub async fn get_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Account>, (StatusCode, Json<Value>)> {
+    let account =
+        sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch account: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch account" })),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": "Account not found" })),
+                )
+            })?;
+
+    Ok(Json(account))
+}
+
+pub async fn update_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateAccountRequest>,
+) -> Result<Json<Account>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update account" })),
+        )
+    })?;
+
+    let existing = lock_account(&mut tx, user_id, id).await?;
+
+    if req.is_archived == Some(true) && existing.system_key.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "System accounts cannot be archived" })),
+        ));
+    }
+
+    let account_type = req.account_type.unwrap_or(existing.account_type);
+
+    if account_type != existing.account_type {
+        if existing.system_key.is_some() {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "The type of a system account cannot be changed" })),
+            ));
+        }
+
+        let (has_postings, has_sub_accounts) = sqlx::query_as::<_, (bool, bool)>(
+            r#"
+            SELECT
+                EXISTS (SELECT 1 FROM journal_lines WHERE account_id = $1),
+                EXISTS (SELECT 1 FROM accounts WHERE parent_id = $1)
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check account usage: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to update account" })),
+            )
+        })?;
+
+        if has_postings || has_sub_accounts {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Accounts with postings or sub-accounts cannot change type"
+                })),
+            ));
+        }
+    }
+
+    let parent_id = req.parent_id.unwrap_or(existing.parent_id);
+    if let Some(parent_id) = parent_id {
+        validate_parent(&mut tx, user_id, parent_id, account_type, Some(id)).await?;
+    }
+
+    let account = sqlx::query_as::<_, Account>(
+        r#"
+        UPDATE accounts
+        SET
+            code = COALESCE($1, code),
+            name = COALESCE($2, name),
+            account_type = $3,
+            parent_id = $4,
+            description = COALESCE($5, description),
+            cash_flow_activity = COALESCE($6, cash_flow_activity),
+            is_archived = COALESCE($7, is_archived),
+            updated_at = NOW()
+        WHERE id = $8 AND user_id = $9
+        RETURNING *
+        "#,
+    )
+    .bind(req.code.as_deref().map(str::trim))
+    .bind(req.name)
+    .bind(account_type)
+    .bind(parent_id)
+    .bind(req.description)
+    .bind(req.cash_flow_activity)
+    .bind(req.is_archived)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        code_conflict(&e).unwrap_or_else(|| {
+            tracing::error!("Failed to update account: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to update account" })),
+            )
+        })
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update account" })),
+        )
+    })?;
+
+    Ok(Json(account))
+}
+
+pub async fn delete_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete account" })),
+        )
+    })?;
+
+    let account = lock_account(&mut tx, user_id, id).await?;
+
+    if account.system_key.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "System accounts cannot be deleted" })),
+        ));
+    }
+
+    // Journal lines and sub-accounts reference the account, so the database
+    // refuses the delete as well
+    sqlx::query("DELETE FROM accounts WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if e.as_database_error()
+                .is_some_and(|db| db.is_foreign_key_violation())
+            {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({
+                        "error": "Account has postings or sub-accounts and cannot be deleted; archive it instead"
+                    })),
+                );
+            }
+
+            tracing::error!("Failed to delete account: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete account" })),
+            )
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete account" })),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_chart_has_every_system_account_once() {
+        use SystemAccount::*;
+
+        for system_key in [
+            Cash,
+            AccountsReceivable,
+            SalesTaxPayable,
+            CustomerCredits,
+            Sales,
+            ExchangeGainLoss,
+            OtherExpenses,
+        ] {
+            let count = DEFAULT_CHART
+                .iter()
+                .filter(|account| account.system_key == Some(system_key))
+                .count();
+            assert_eq!(count, 1, "{:?}", system_key);
+        }
+    }
+
+    #[test]
+    fn test_default_chart_parents_come_first_with_the_same_type() {
+        for (index, account) in DEFAULT_CHART.iter().enumerate() {
+            assert!(
+                !DEFAULT_CHART[index + 1..]
+                    .iter()
+                    .any(|other| other.code == account.code)
+            );
+
+            if let Some(parent_code) = account.parent_code {
+                let parent = DEFAULT_CHART[..index]
+                    .iter()
+                    .find(|parent| parent.code == parent_code)
+                    .expect("parent precedes its sub-accounts");
+                assert_eq!(parent.account_type, account.account_type);
+            }
+        }
+    }
+}
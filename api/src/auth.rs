@@ -1,11 +1,15 @@
 use axum::{
-    extract::Json,
+    extract::{Json, Path},
     http::StatusCode,
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use std::env;
 
-use crate::supabase::auth::AuthError as SupabaseAuthError;
+use crate::middleware::{AccessToken, Claims};
+use crate::supabase::auth::{
+    AuthError as SupabaseAuthError, OAuthProvider, Session, SignOutScope, User, VerificationType,
+};
 use crate::supabase::{AuthResponse, SupabaseClient};
 
 #[derive(Debug, Deserialize)]
@@ -23,10 +27,64 @@ pub struct SignInRequest {
 #[derive(Debug, Deserialize)]
 pub struct VerifyEmailRequest {
     pub token: String,
-    pub type_: String,
+    #[serde(alias = "type")]
+    pub type_: VerificationType,
     pub email: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OtpRequest {
+    pub email: String,
+    /// Sign up unknown addresses instead of ignoring them
+    #[serde(default)]
+    pub create_user: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecoverRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePasswordRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignOutQuery {
+    #[serde(default)]
+    pub scope: SignOutScope,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OAuthStartRequest {
+    /// PKCE `S256` challenge of a verifier the browser keeps until the callback
+    pub code_challenge: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OAuthCallbackRequest {
+    pub code: String,
+    /// Verifier whose challenge started the flow, proving this browser started it
+    pub code_verifier: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OAuthStartResponse {
+    /// Where to send the browser to sign in with the provider
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageResponse {
+    pub message: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -37,6 +95,7 @@ pub enum AuthError {
     Unauthorized(String),
     InternalError(String),
     ConfirmationRequired(String),
+    TooManyRequests(String),
 }
 
 impl IntoResponse for AuthError {
@@ -46,6 +105,7 @@ impl IntoResponse for AuthError {
             AuthError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             AuthError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AuthError::ConfirmationRequired(msg) => (StatusCode::ACCEPTED, msg),
+            AuthError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
         };
 
         (status, Json(ErrorResponse { error: message })).into_response()
@@ -59,6 +119,10 @@ impl From<SupabaseAuthError> for AuthError {
             SupabaseAuthError::InvalidCredentials => AuthError::Unauthorized(err.to_string()),
             SupabaseAuthError::InvalidToken => AuthError::Unauthorized(err.to_string()),
             SupabaseAuthError::ExpiredToken => AuthError::Unauthorized(err.to_string()),
+            SupabaseAuthError::SessionExpired => AuthError::Unauthorized(err.to_string()),
+            SupabaseAuthError::InvalidAuthCode => AuthError::Unauthorized(err.to_string()),
+            SupabaseAuthError::WeakPassword(msg) => AuthError::BadRequest(msg),
+            SupabaseAuthError::RateLimited => AuthError::TooManyRequests(err.to_string()),
             SupabaseAuthError::MissingData(msg) => AuthError::InternalError(msg),
             SupabaseAuthError::NetworkError(msg) => AuthError::InternalError(msg),
             SupabaseAuthError::UnknownError(msg) => AuthError::BadRequest(msg),
@@ -92,8 +156,179 @@ pub async fn verify_email(
     let client = SupabaseClient::new().map_err(|e| AuthError::InternalError(e))?;
 
     let auth_response = client
-        .verify_otp(&payload.token, &payload.type_, payload.email.as_deref())
+        .verify_otp(&payload.token, payload.type_, payload.email.as_deref())
+        .await?;
+
+    Ok(Json(auth_response))
+}
+
+pub async fn refresh(Json(payload): Json<RefreshRequest>) -> Result<Json<AuthResponse>, AuthError> {
+    let client = SupabaseClient::new().map_err(AuthError::InternalError)?;
+
+    let auth_response = client.refresh_session(&payload.refresh_token).await?;
+
+    Ok(Json(auth_response))
+}
+
+pub async fn sign_out(
+    access_token: AccessToken,
+    scope: SignOutScope,
+) -> Result<StatusCode, AuthError> {
+    let client = SupabaseClient::new()
+        .map_err(AuthError::InternalError)?
+        .with_access_token(&access_token.0);
+
+    client.sign_out(scope).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_session(
+    access_token: AccessToken,
+    claims: Claims,
+) -> Result<Json<Session>, AuthError> {
+    let client = SupabaseClient::new()
+        .map_err(AuthError::InternalError)?
+        .with_access_token(&access_token.0);
+
+    let user = client.get_user().await?;
+
+    Ok(Json(Session {
+        user,
+        expires_at: claims.exp,
+    }))
+}
+
+pub async fn send_otp(Json(payload): Json<OtpRequest>) -> Result<Json<MessageResponse>, AuthError> {
+    let client = SupabaseClient::new().map_err(AuthError::InternalError)?;
+
+    client.send_otp(&payload.email, payload.create_user).await?;
+
+    Ok(Json(MessageResponse {
+        message: "Check your email for a sign-in link or code.".to_string(),
+    }))
+}
+
+pub async fn recover(
+    Json(payload): Json<RecoverRequest>,
+) -> Result<Json<MessageResponse>, AuthError> {
+    let client = SupabaseClient::new().map_err(AuthError::InternalError)?;
+
+    client.recover(&payload.email).await?;
+
+    Ok(Json(MessageResponse {
+        message: "If an account exists for this email, a password reset link has been sent."
+            .to_string(),
+    }))
+}
+
+pub async fn update_password(
+    access_token: AccessToken,
+    Json(payload): Json<UpdatePasswordRequest>,
+) -> Result<Json<User>, AuthError> {
+    let client = SupabaseClient::new()
+        .map_err(AuthError::InternalError)?
+        .with_access_token(&access_token.0);
+
+    let user = client.update_password(&payload.password).await?;
+
+    Ok(Json(user))
+}
+
+/// A PKCE `S256` challenge: the unpadded base64url SHA-256 of a verifier.
+fn is_code_challenge(value: &str) -> bool {
+    value.len() == 43
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// A PKCE code verifier: 43 to 128 unreserved characters (RFC 7636).
+fn is_code_verifier(value: &str) -> bool {
+    (43..=128).contains(&value.len())
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-._~".contains(&b))
+}
+
+/// Starts a social login. The browser generates the PKCE verifier and sends
+/// only its challenge, so a code can only be exchanged by the browser that
+/// started the flow; a callback link from someone else's login fails.
+pub async fn start_oauth(
+    Path(provider): Path<OAuthProvider>,
+    Json(payload): Json<OAuthStartRequest>,
+) -> Result<Json<OAuthStartResponse>, AuthError> {
+    if !is_code_challenge(&payload.code_challenge) {
+        return Err(AuthError::BadRequest(
+            "code_challenge must be an S256 PKCE challenge".to_string(),
+        ));
+    }
+
+    let client = SupabaseClient::new().map_err(AuthError::InternalError)?;
+
+    let site_url = env::var("SITE_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
+    let redirect_to = format!("{}/auth/callback", site_url);
+
+    let url = client.authorize_url(provider, &redirect_to, &payload.code_challenge)?;
+
+    Ok(Json(OAuthStartResponse { url }))
+}
+
+pub async fn oauth_callback(
+    Json(payload): Json<OAuthCallbackRequest>,
+) -> Result<Json<AuthResponse>, AuthError> {
+    if !is_code_verifier(&payload.code_verifier) {
+        return Err(AuthError::BadRequest(
+            "code_verifier must be a PKCE code verifier".to_string(),
+        ));
+    }
+
+    let client = SupabaseClient::new().map_err(AuthError::InternalError)?;
+
+    let auth_response = client
+        .exchange_code(&payload.code, &payload.code_verifier)
         .await?;
 
     Ok(Json(auth_response))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_email_request_accepts_type_field_names() {
+        let legacy: VerifyEmailRequest =
+            serde_json::from_str(r#"{"token": "abc", "type_": "magiclink"}"#).unwrap();
+        assert_eq!(legacy.type_, VerificationType::Magiclink);
+
+        let request: VerifyEmailRequest = serde_json::from_str(
+            r#"{"token": "123456", "type": "email", "email": "user@example.com"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.type_, VerificationType::Email);
+
+        assert!(
+            serde_json::from_str::<VerifyEmailRequest>(r#"{"token": "abc", "type_": "sms"}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pkce_values_are_checked() {
+        // RFC 7636, appendix B
+        assert!(is_code_verifier(
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"
+        ));
+        assert!(is_code_challenge(
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        ));
+
+        assert!(!is_code_verifier("too-short"));
+        assert!(!is_code_verifier(&"a".repeat(129)));
+        assert!(!is_code_challenge(
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-c="
+        ));
+        assert!(!is_code_challenge("plain-verifier-sent-as-challenge"));
+    }
+}
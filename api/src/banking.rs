@@ -0,0 +1,2481 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::NaiveDate;
+use roxmltree::Node;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::currencies::{self, validate_currency};
+use crate::expenses::{self, CreateExpenseRequest, Expense};
+use crate::invoices::round_money;
+use crate::reports;
+
+/// Largest statement file accepted for import.
+pub const MAX_STATEMENT_SIZE: usize = 5 * 1024 * 1024;
+
+/// How many match suggestions are returned per transaction.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// File format a bank statement was imported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum StatementFormat {
+    Csv,
+    /// OFX 1.x (SGML) or 2.x (XML), including Quicken's QFX.
+    Ofx,
+    /// ISO 20022 bank to customer statement.
+    Camt053,
+}
+
+impl StatementFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "csv" => Some(StatementFormat::Csv),
+            "ofx" | "qfx" => Some(StatementFormat::Ofx),
+            "camt053" | "camt.053" => Some(StatementFormat::Camt053),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the file contents; anything that is neither
+    /// OFX nor CAMT.053 is taken to be CSV.
+    pub fn detect(text: &str) -> Self {
+        if text.contains("OFXHEADER") || text.contains("<OFX") || text.contains("<?OFX") {
+            StatementFormat::Ofx
+        } else if text.contains("BkToCstmrStmt") {
+            StatementFormat::Camt053
+        } else {
+            StatementFormat::Csv
+        }
+    }
+}
+
+/// How a bank transaction was recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSource {
+    Manual,
+    Csv,
+    Ofx,
+    Camt053,
+}
+
+impl From<StatementFormat> for TransactionSource {
+    fn from(format: StatementFormat) -> Self {
+        match format {
+            StatementFormat::Csv => TransactionSource::Csv,
+            StatementFormat::Ofx => TransactionSource::Ofx,
+            StatementFormat::Camt053 => TransactionSource::Camt053,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationStatus {
+    InProgress,
+    Completed,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BankAccount {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub account_number: Option<String>,
+    pub currency: String,
+    /// Balance before the first recorded transaction.
+    pub opening_balance: Decimal,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BankTransaction {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub bank_account_id: Uuid,
+    pub transaction_date: NaiveDate,
+    /// Positive for money in, negative for money out.
+    pub amount: Decimal,
+    pub description: String,
+    pub reference: Option<String>,
+    pub counterparty: Option<String>,
+    pub external_id: Option<String>,
+    pub fingerprint: String,
+    pub source: TransactionSource,
+    /// Reconciliation the transaction was ticked off in.
+    pub reconciliation_id: Option<Uuid>,
+    /// Expense recorded for the transaction.
+    pub expense_id: Option<Uuid>,
+    /// Rule that recorded the expense.
+    pub bank_rule_id: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BankReconciliation {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub bank_account_id: Uuid,
+    pub statement_date: NaiveDate,
+    pub statement_ending_balance: Decimal,
+    pub status: ReconciliationStatus,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A reconciliation with the transactions that can be ticked off in it.
+#[derive(Debug, Serialize)]
+pub struct ReconciliationDetails {
+    #[serde(flatten)]
+    pub reconciliation: BankReconciliation,
+    /// Ending balance of the previous reconciliation, or the account's
+    /// opening balance for the first.
+    pub opening_balance: Decimal,
+    /// Opening balance plus the transactions ticked off so far.
+    pub cleared_balance: Decimal,
+    /// Statement ending balance less the cleared balance; the
+    /// reconciliation can be completed once this is zero.
+    pub difference: Decimal,
+    /// Transactions up to the statement date not reconciled before.
+    pub transactions: Vec<BankTransaction>,
+}
+
+/// Records withdrawals whose description or counterparty contains `pattern`
+/// as expenses.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BankRule {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub bank_account_id: Option<Uuid>,
+    pub name: String,
+    pub pattern: String,
+    pub vendor: Option<String>,
+    pub category: String,
+    pub tax_rate_id: Option<Uuid>,
+    pub priority: i32,
+    pub is_active: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A transaction read from a statement file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedTransaction {
+    pub transaction_date: NaiveDate,
+    pub amount: Decimal,
+    pub description: String,
+    pub reference: Option<String>,
+    pub counterparty: Option<String>,
+    pub external_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedStatement {
+    /// Currency stated in the file, if it names one.
+    pub currency: Option<String>,
+    pub transactions: Vec<ParsedTransaction>,
+}
+
+/// Which CSV columns hold what, by header name. Amounts come from a signed
+/// `amount` column or from separate `debit` and `credit` columns. The
+/// default expects `date`, `description` and `amount` columns.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CsvMapping {
+    pub date: String,
+    /// chrono format of the dates, e.g. `%d/%m/%Y`.
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    pub amount: Option<String>,
+    pub debit: Option<String>,
+    pub credit: Option<String>,
+    pub description: Option<String>,
+    pub reference: Option<String>,
+    pub counterparty: Option<String>,
+    pub external_id: Option<String>,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Amounts are written like `1.234,56`.
+    #[serde(default)]
+    pub decimal_comma: bool,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        Self {
+            date: "date".to_string(),
+            date_format: default_date_format(),
+            amount: Some("amount".to_string()),
+            debit: None,
+            credit: None,
+            description: Some("description".to_string()),
+            reference: None,
+            counterparty: None,
+            external_id: None,
+            delimiter: default_delimiter(),
+            decimal_comma: false,
+        }
+    }
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateBankAccountRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1, max = 50))]
+    pub account_number: Option<String>,
+    /// Defaults to the home currency.
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub opening_balance: Decimal,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateBankAccountRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    pub account_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateBankTransactionRequest {
+    pub transaction_date: NaiveDate,
+    /// Positive for money in, negative for money out.
+    #[validate(custom(function = "validate_non_zero"))]
+    pub amount: Decimal,
+    #[serde(default)]
+    pub description: String,
+    #[validate(length(min = 1, max = 255))]
+    pub reference: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub counterparty: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReconciliationRequest {
+    pub statement_date: NaiveDate,
+    pub statement_ending_balance: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconcileTransactionsRequest {
+    pub transaction_ids: Vec<Uuid>,
+    /// Ticks the transactions off, or clears them again when `false`.
+    #[serde(default = "default_reconciled")]
+    pub reconciled: bool,
+}
+
+fn default_reconciled() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateBankRuleRequest {
+    pub bank_account_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1, max = 255))]
+    pub pattern: String,
+    #[validate(length(min = 1, max = 255))]
+    pub vendor: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub category: String,
+    pub tax_rate_id: Option<Uuid>,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateBankRuleRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub pattern: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub vendor: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub category: Option<String>,
+    pub tax_rate_id: Option<Uuid>,
+    pub priority: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+fn validate_non_zero(amount: &Decimal) -> Result<(), ValidationError> {
+    if amount.is_zero() {
+        return Err(ValidationError::new("must_not_be_zero"));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BankTransactionsQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementImportSummary {
+    pub format: StatementFormat,
+    /// Transactions added by this import.
+    pub imported: Vec<BankTransaction>,
+    /// Transactions skipped because an earlier import already added them or
+    /// they were recorded by hand.
+    pub duplicates: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Invoice,
+    Expense,
+}
+
+/// An open invoice or an expense a bank transaction may settle.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MatchSuggestion {
+    pub kind: MatchKind,
+    pub id: Uuid,
+    /// Invoice number; `None` for expenses.
+    pub number: Option<String>,
+    /// Client or vendor name.
+    pub name: String,
+    pub date: NaiveDate,
+    /// Balance due on invoices, total of expenses.
+    pub amount: Decimal,
+    /// How well the transaction fits, from 0 to 100.
+    #[sqlx(skip)]
+    pub score: u32,
+}
+
+/// Reads an amount as banks write them: with currency symbols, thousands
+/// separators, a trailing minus or parentheses for negatives.
+fn parse_amount(text: &str, decimal_comma: bool) -> Option<Decimal> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(inner) => (true, inner),
+        None => (false, text),
+    };
+    let (decimal_separator, group_separator) = if decimal_comma {
+        (',', '.')
+    } else {
+        ('.', ',')
+    };
+
+    let mut number: String = text
+        .chars()
+        .filter(|c| *c != group_separator)
+        .map(|c| if c == decimal_separator { '.' } else { c })
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))
+        .collect();
+    if let Some(unsigned) = number.strip_suffix('-') {
+        number = format!("-{}", unsigned);
+    }
+
+    let amount = Decimal::from_str(number.trim_start_matches('+')).ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Reads a CSV statement using `mapping` to find the columns. Rows with a
+/// zero amount (balance lines some banks add) are skipped.
+pub fn parse_csv_statement(text: &str, mapping: &CsvMapping) -> Result<ParsedStatement, String> {
+    let mut records = reports::parse_csv(text, mapping.delimiter).into_iter();
+    let header = records.next().ok_or("File is empty")?;
+
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|field| field.trim().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("Missing column \"{}\"", name))
+    };
+    let optional_column = |name: &Option<String>| name.as_deref().map(column).transpose();
+
+    let date_column = column(&mapping.date)?;
+    let amount_column = optional_column(&mapping.amount)?;
+    let debit_column = optional_column(&mapping.debit)?;
+    let credit_column = optional_column(&mapping.credit)?;
+    if amount_column.is_none() && debit_column.is_none() && credit_column.is_none() {
+        return Err("Map an amount column, or debit and credit columns".to_string());
+    }
+    let description_column = optional_column(&mapping.description)?;
+    let reference_column = optional_column(&mapping.reference)?;
+    let counterparty_column = optional_column(&mapping.counterparty)?;
+    let external_id_column = optional_column(&mapping.external_id)?;
+
+    let mut transactions = Vec::new();
+    for (index, record) in records.enumerate() {
+        let line = index + 2;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(String::as_str)
+                .unwrap_or_default()
+        };
+        let amount_in = |column: Option<usize>| {
+            let value = field(column);
+            if value.trim().is_empty() {
+                return Ok(Decimal::ZERO);
+            }
+            parse_amount(value, mapping.decimal_comma)
+                .ok_or_else(|| format!("Line {}: invalid amount \"{}\"", line, value))
+        };
+
+        let date = field(Some(date_column));
+        let transaction_date = NaiveDate::parse_from_str(date.trim(), &mapping.date_format)
+            .map_err(|_| format!("Line {}: invalid date \"{}\"", line, date))?;
+        let amount = match amount_column {
+            Some(_) => amount_in(amount_column)?,
+            // Some banks write debits as negative numbers, others as positive
+            None => amount_in(credit_column)?.abs() - amount_in(debit_column)?.abs(),
+        };
+        if amount.is_zero() {
+            continue;
+        }
+
+        transactions.push(ParsedTransaction {
+            transaction_date,
+            amount,
+            description: field(description_column).trim().to_string(),
+            reference: non_empty(field(reference_column)),
+            counterparty: non_empty(field(counterparty_column)),
+            external_id: non_empty(field(external_id_column)),
+        });
+    }
+
+    Ok(ParsedStatement {
+        currency: None,
+        transactions,
+    })
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Value of an OFX element. OFX 1.x leaves elements unclosed, so the value
+/// runs to the next tag or line break.
+fn ofx_value(block: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = block.find(&open)? + open.len();
+    let rest = &block[start..];
+    let end = rest.find(['<', '\r', '\n']).unwrap_or(rest.len());
+
+    non_empty(&decode_entities(&rest[..end]))
+}
+
+/// Reads the `<STMTTRN>` records of an OFX or QFX file.
+pub fn parse_ofx(text: &str) -> Result<ParsedStatement, String> {
+    let mut transactions = Vec::new();
+
+    for (index, block) in text.split("<STMTTRN>").skip(1).enumerate() {
+        let block = block.split("</STMTTRN>").next().unwrap_or(block);
+        let record = index + 1;
+
+        let posted = ofx_value(block, "DTPOSTED")
+            .ok_or_else(|| format!("Transaction {}: missing DTPOSTED", record))?;
+        let transaction_date = posted
+            .get(..8)
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+            .ok_or_else(|| format!("Transaction {}: invalid date \"{}\"", record, posted))?;
+        let amount = ofx_value(block, "TRNAMT")
+            .and_then(|amount| parse_amount(&amount, amount.contains(',') && !amount.contains('.')))
+            .ok_or_else(|| format!("Transaction {}: missing or invalid TRNAMT", record))?;
+        if amount.is_zero() {
+            continue;
+        }
+
+        let name = ofx_value(block, "NAME");
+        let memo = ofx_value(block, "MEMO");
+        transactions.push(ParsedTransaction {
+            transaction_date,
+            amount,
+            description: memo.or_else(|| name.clone()).unwrap_or_default(),
+            reference: ofx_value(block, "CHECKNUM").or_else(|| ofx_value(block, "REFNUM")),
+            counterparty: name,
+            external_id: ofx_value(block, "FITID"),
+        });
+    }
+
+    if transactions.is_empty() && !text.contains("<OFX>") {
+        return Err("Not an OFX file".to_string());
+    }
+
+    Ok(ParsedStatement {
+        currency: ofx_value(text, "CURDEF"),
+        transactions,
+    })
+}
+
+/// First descendant reached by following child elements named `path`,
+/// ignoring namespaces.
+fn camt_node<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |node, name| {
+        node.children().find(|child| child.has_tag_name(*name))
+    })
+}
+
+fn camt_text(node: Node, path: &[&str]) -> Option<String> {
+    camt_node(node, path)
+        .and_then(|node| node.text())
+        .and_then(non_empty)
+}
+
+/// Date of an ISO date or date-time element.
+fn camt_date(node: Node, path: &[&str]) -> Option<NaiveDate> {
+    let text = camt_text(node, path)?;
+
+    NaiveDate::parse_from_str(text.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Reads the booked `<Ntry>` entries of an ISO 20022 CAMT.053 statement.
+/// Batched entries are kept as one transaction with the first transaction's
+/// details.
+pub fn parse_camt053(text: &str) -> Result<ParsedStatement, String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| format!("Invalid XML: {}", e))?;
+    let statements: Vec<Node> = document
+        .descendants()
+        .filter(|node| node.has_tag_name("Stmt"))
+        .collect();
+    if statements.is_empty() {
+        return Err("No statement (Stmt) found in CAMT.053 file".to_string());
+    }
+
+    let mut currency = None;
+    let mut transactions = Vec::new();
+    for statement in statements {
+        currency = currency.or_else(|| camt_text(statement, &["Acct", "Ccy"]));
+
+        for (index, entry) in statement
+            .children()
+            .filter(|node| node.has_tag_name("Ntry"))
+            .enumerate()
+        {
+            let record = index + 1;
+            let status = camt_text(entry, &["Sts", "Cd"]).or_else(|| camt_text(entry, &["Sts"]));
+            if status.is_some_and(|status| status != "BOOK") {
+                continue;
+            }
+
+            let amount_node = camt_node(entry, &["Amt"])
+                .ok_or_else(|| format!("Entry {}: missing Amt", record))?;
+            currency = currency.or_else(|| amount_node.attribute("Ccy").map(str::to_string));
+            let amount = amount_node
+                .text()
+                .and_then(|amount| parse_amount(amount, false))
+                .ok_or_else(|| format!("Entry {}: invalid Amt", record))?;
+            let credit = camt_text(entry, &["CdtDbtInd"]).as_deref() != Some("DBIT");
+            let amount = if credit { amount } else { -amount };
+
+            let transaction_date = ["BookgDt", "ValDt"]
+                .iter()
+                .find_map(|&date| {
+                    camt_date(entry, &[date, "Dt"]).or_else(|| camt_date(entry, &[date, "DtTm"]))
+                })
+                .ok_or_else(|| format!("Entry {}: missing booking date", record))?;
+
+            let details = camt_node(entry, &["NtryDtls", "TxDtls"]);
+            let detail = |path: &[&str]| details.and_then(|details| camt_text(details, path));
+
+            let remittance: Vec<String> = details
+                .and_then(|details| camt_node(details, &["RmtInf"]))
+                .map(|info| {
+                    info.children()
+                        .filter(|node| node.has_tag_name("Ustrd"))
+                        .filter_map(|node| node.text().and_then(non_empty))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let description = if remittance.is_empty() {
+                camt_text(entry, &["AddtlNtryInf"])
+                    .or_else(|| detail(&["AddtlTxInf"]))
+                    .unwrap_or_default()
+            } else {
+                remittance.join(" ")
+            };
+
+            let party = if credit { "Dbtr" } else { "Cdtr" };
+            transactions.push(ParsedTransaction {
+                transaction_date,
+                amount,
+                description,
+                reference: detail(&["RmtInf", "Strd", "CdtrRefInf", "Ref"])
+                    .or_else(|| detail(&["Refs", "EndToEndId"]).filter(|id| id != "NOTPROVIDED")),
+                counterparty: detail(&["RltdPties", party, "Nm"])
+                    .or_else(|| detail(&["RltdPties", party, "Pty", "Nm"])),
+                external_id: camt_text(entry, &["AcctSvcrRef"])
+                    .or_else(|| detail(&["Refs", "AcctSvcrRef"])),
+            });
+        }
+    }
+
+    Ok(ParsedStatement {
+        currency,
+        transactions,
+    })
+}
+
+/// Lower case letters and digits only, so references compare the same
+/// however they are punctuated.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Identifies a transaction across imports: by the bank's id when the file
+/// has one, otherwise by its contents and `occurrence`, the count of
+/// identical transactions up to and including it.
+pub fn fingerprint(transaction: &ParsedTransaction, occurrence: usize) -> String {
+    let key = match &transaction.external_id {
+        Some(id) => format!("id|{}", id),
+        None => format!(
+            "{}|{}|{}|{}",
+            transaction.transaction_date,
+            transaction.amount.normalize(),
+            normalize(&transaction.description),
+            normalize(transaction.reference.as_deref().unwrap_or_default()),
+        ),
+    };
+
+    hex::encode(Sha256::digest(format!("{}|{}", key, occurrence)))
+}
+
+/// Fingerprints of the transactions of one file. Identical lines are told
+/// apart by their order, so two equal card payments on the same day are both
+/// kept, and are both recognised when an overlapping statement repeats them.
+pub fn fingerprints(transactions: &[ParsedTransaction]) -> Vec<String> {
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    transactions
+        .iter()
+        .map(|transaction| {
+            let first = fingerprint(transaction, 1);
+            let occurrence = occurrences.entry(first.clone()).or_default();
+            *occurrence += 1;
+
+            match *occurrence {
+                1 => first,
+                occurrence => fingerprint(transaction, occurrence),
+            }
+        })
+        .collect()
+}
+
+/// Scores how likely `transaction` settles `candidate`. A candidate must
+/// match on amount or, for invoices, have its number quoted; the client or
+/// vendor name and a close date add to the score.
+fn match_score(transaction: &BankTransaction, candidate: &MatchSuggestion) -> u32 {
+    let text = normalize(&format!(
+        "{} {} {}",
+        transaction.description,
+        transaction.reference.as_deref().unwrap_or_default(),
+        transaction.counterparty.as_deref().unwrap_or_default(),
+    ));
+    let mentions = |value: &str| {
+        let value = normalize(value);
+        value.len() >= 3 && text.contains(&value)
+    };
+
+    let amount_matches = transaction.amount.abs() == candidate.amount;
+    let number_quoted = candidate.number.as_deref().is_some_and(mentions);
+    if !amount_matches && !number_quoted {
+        return 0;
+    }
+
+    let days_apart = (transaction.transaction_date - candidate.date)
+        .num_days()
+        .abs();
+    let mut score = 0;
+    if amount_matches {
+        score += 50;
+    }
+    if number_quoted {
+        score += 30;
+    }
+    if mentions(&candidate.name) {
+        score += 10;
+    }
+    score += match days_apart {
+        0..=3 => 10,
+        4..=14 => 5,
+        _ => 0,
+    };
+
+    score
+}
+
+/// First active rule, in priority order, whose pattern appears in the
+/// description or counterparty of a withdrawal. Deposits are left alone.
+fn matching_rule<'a>(rules: &'a [BankRule], transaction: &BankTransaction) -> Option<&'a BankRule> {
+    if transaction.amount >= Decimal::ZERO {
+        return None;
+    }
+    let text = format!(
+        "{} {}",
+        transaction.description,
+        transaction.counterparty.as_deref().unwrap_or_default()
+    )
+    .to_lowercase();
+
+    rules.iter().find(|rule| {
+        rule.is_active
+            && rule
+                .bank_account_id
+                .is_none_or(|id| id == transaction.bank_account_id)
+            && text.contains(&rule.pattern.to_lowercase())
+    })
+}
+
+/// Splits an amount that includes tax at `rate` percent into net and tax.
+fn split_gross(gross: Decimal, rate: Option<Decimal>) -> (Decimal, Decimal) {
+    let net = match rate {
+        Some(rate) => round_money(gross * Decimal::ONE_HUNDRED / (Decimal::ONE_HUNDRED + rate)),
+        None => gross,
+    };
+
+    (net, gross - net)
+}
+
+/// Reconciliation totals over the transactions listed for it.
+fn reconciliation_details(
+    reconciliation: BankReconciliation,
+    opening_balance: Decimal,
+    transactions: Vec<BankTransaction>,
+) -> ReconciliationDetails {
+    let cleared_balance = opening_balance
+        + transactions
+            .iter()
+            .filter(|transaction| transaction.reconciliation_id == Some(reconciliation.id))
+            .map(|transaction| transaction.amount)
+            .sum::<Decimal>();
+
+    ReconciliationDetails {
+        difference: reconciliation.statement_ending_balance - cleared_balance,
+        opening_balance,
+        cleared_balance,
+        reconciliation,
+        transactions,
+    }
+}
+
+/// Best scoring candidates first; ties go to the closest date.
+fn rank_suggestions(
+    transaction: &BankTransaction,
+    candidates: Vec<MatchSuggestion>,
+) -> Vec<MatchSuggestion> {
+    let mut suggestions: Vec<MatchSuggestion> = candidates
+        .into_iter()
+        .map(|mut candidate| {
+            candidate.score = match_score(transaction, &candidate);
+            candidate
+        })
+        .filter(|candidate| candidate.score > 0)
+        .collect();
+
+    suggestions.sort_by_key(|candidate| {
+        (
+            std::cmp::Reverse(candidate.score),
+            (transaction.transaction_date - candidate.date)
+                .num_days()
+                .abs(),
+        )
+    });
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+async fn fetch_bank_account(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<BankAccount, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, BankAccount>("SELECT * FROM bank_accounts WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch bank account: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch bank account" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Bank account not found" })),
+            )
+        })
+}
+
+async fn insert_transaction(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    bank_account_id: Uuid,
+    transaction: &ParsedTransaction,
+    fingerprint: &str,
+    source: TransactionSource,
+) -> Result<Option<BankTransaction>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, BankTransaction>(
+        r#"
+        INSERT INTO bank_transactions (
+            user_id, bank_account_id, transaction_date, amount, description,
+            reference, counterparty, external_id, fingerprint, source
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (bank_account_id, fingerprint) DO NOTHING
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(bank_account_id)
+    .bind(transaction.transaction_date)
+    .bind(transaction.amount)
+    .bind(&transaction.description)
+    .bind(&transaction.reference)
+    .bind(&transaction.counterparty)
+    .bind(&transaction.external_id)
+    .bind(fingerprint)
+    .bind(source)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save bank transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save bank transaction" })),
+        )
+    })
+}
+
+/// Links a statement line to a transaction recorded by hand with the same
+/// date, amount and reference, unless the line has been imported before.
+/// The manual transaction takes the line's bank id, fingerprint and source,
+/// so later imports recognise it and it is not linked twice.
+async fn claim_manual_transaction(
+    conn: &mut PgConnection,
+    bank_account_id: Uuid,
+    transaction: &ParsedTransaction,
+    fingerprint: &str,
+    source: TransactionSource,
+) -> Result<Option<BankTransaction>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, BankTransaction>(
+        r#"
+        UPDATE bank_transactions
+        SET
+            external_id = $1,
+            fingerprint = $2,
+            counterparty = COALESCE(counterparty, $3),
+            source = $8,
+            updated_at = NOW()
+        WHERE id = (
+            SELECT id FROM bank_transactions
+            WHERE bank_account_id = $4
+                AND source = 'manual'::varchar
+                AND transaction_date = $5
+                AND amount = $6
+                AND LOWER(REGEXP_REPLACE(COALESCE(reference, ''), '[^a-zA-Z0-9]', '', 'g')) = $7
+                AND NOT EXISTS (
+                    SELECT 1 FROM bank_transactions
+                    WHERE bank_account_id = $4 AND fingerprint = $2
+                )
+            ORDER BY created_at
+            LIMIT 1
+            FOR UPDATE
+        )
+        RETURNING *
+        "#,
+    )
+    .bind(&transaction.external_id)
+    .bind(fingerprint)
+    .bind(&transaction.counterparty)
+    .bind(bank_account_id)
+    .bind(transaction.transaction_date)
+    .bind(transaction.amount)
+    .bind(normalize(
+        transaction.reference.as_deref().unwrap_or_default(),
+    ))
+    .bind(source)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to match manual bank transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save bank transaction" })),
+        )
+    })
+}
+
+/// Records an expense for `transaction` as `rule` describes it, in the
+/// account's currency and dated like the withdrawal.
+async fn record_rule_expense(
+    conn: &mut PgConnection,
+    account: &BankAccount,
+    rule: &BankRule,
+    transaction: &BankTransaction,
+) -> Result<Expense, (StatusCode, Json<Value>)> {
+    let rate = match rule.tax_rate_id {
+        Some(tax_rate_id) => {
+            Some(expenses::fetch_tax_rate(&mut *conn, account.user_id, tax_rate_id).await?)
+        }
+        None => None,
+    };
+    let (amount, tax_amount) = split_gross(-transaction.amount, rate);
+
+    let vendor = rule
+        .vendor
+        .clone()
+        .or_else(|| transaction.counterparty.clone())
+        .or_else(|| non_empty(&transaction.description))
+        .unwrap_or_else(|| rule.name.clone());
+
+    expenses::insert_expense(
+        conn,
+        account.user_id,
+        CreateExpenseRequest {
+            client_id: None,
+            project_id: None,
+            vendor: vendor.chars().take(255).collect(),
+            category: rule.category.clone(),
+            description: non_empty(&transaction.description),
+            expense_date: Some(transaction.transaction_date),
+            amount,
+            tax_rate_id: rule.tax_rate_id,
+            tax_amount: Some(tax_amount),
+            notes: Some(format!("Recorded by bank rule \"{}\"", rule.name)),
+            billable: false,
+            currency: Some(account.currency.clone()),
+            exchange_rate: None,
+        },
+    )
+    .await
+}
+
+/// Records an expense for each of `transactions` matching a bank rule.
+async fn apply_rules(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    account: &BankAccount,
+    transactions: &mut [BankTransaction],
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let rules = sqlx::query_as::<_, BankRule>(
+        r#"
+        SELECT * FROM bank_rules
+        WHERE user_id = $1 AND is_active
+            AND (bank_account_id IS NULL OR bank_account_id = $2)
+        ORDER BY priority DESC, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(account.id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch bank rules: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch bank rules" })),
+        )
+    })?;
+
+    for transaction in transactions
+        .iter_mut()
+        .filter(|transaction| transaction.expense_id.is_none())
+    {
+        let Some(rule) = matching_rule(&rules, transaction) else {
+            continue;
+        };
+        let expense = record_rule_expense(&mut *conn, account, rule, transaction).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE bank_transactions
+            SET expense_id = $1, bank_rule_id = $2, updated_at = NOW()
+            WHERE id = $3
+            "#,
+        )
+        .bind(expense.id)
+        .bind(rule.id)
+        .bind(transaction.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to link expense to bank transaction: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to apply bank rules" })),
+            )
+        })?;
+
+        transaction.expense_id = Some(expense.id);
+        transaction.bank_rule_id = Some(rule.id);
+    }
+
+    Ok(())
+}
+
+async fn fetch_reconciliation(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<BankReconciliation, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, BankReconciliation>(
+        "SELECT * FROM bank_reconciliations WHERE id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch reconciliation: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch reconciliation" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Reconciliation not found" })),
+        )
+    })
+}
+
+/// Loads the opening balance and transactions of `reconciliation`. Once
+/// completed only the transactions ticked off in it are listed.
+async fn load_reconciliation(
+    conn: &mut PgConnection,
+    reconciliation: BankReconciliation,
+) -> Result<ReconciliationDetails, (StatusCode, Json<Value>)> {
+    let load_error = |e: sqlx::Error| {
+        tracing::error!("Failed to load reconciliation: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to load reconciliation" })),
+        )
+    };
+
+    let opening_balance = sqlx::query_scalar::<_, Decimal>(
+        r#"
+        SELECT COALESCE(
+            (
+                SELECT statement_ending_balance FROM bank_reconciliations
+                WHERE bank_account_id = $1
+                    AND status = 'completed'
+                    AND completed_at < COALESCE($2, 'infinity'::timestamptz)
+                ORDER BY completed_at DESC
+                LIMIT 1
+            ),
+            (SELECT opening_balance FROM bank_accounts WHERE id = $1)
+        )
+        "#,
+    )
+    .bind(reconciliation.bank_account_id)
+    .bind(reconciliation.completed_at)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(load_error)?;
+
+    let transactions = sqlx::query_as::<_, BankTransaction>(
+        r#"
+        SELECT * FROM bank_transactions
+        WHERE bank_account_id = $1
+            AND (
+                reconciliation_id = $2
+                OR ($3 AND reconciliation_id IS NULL AND transaction_date <= $4)
+            )
+        ORDER BY transaction_date, created_at
+        "#,
+    )
+    .bind(reconciliation.bank_account_id)
+    .bind(reconciliation.id)
+    .bind(reconciliation.status == ReconciliationStatus::InProgress)
+    .bind(reconciliation.statement_date)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(load_error)?;
+
+    Ok(reconciliation_details(
+        reconciliation,
+        opening_balance,
+        transactions,
+    ))
+}
+
+pub async fn list_bank_accounts(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<BankAccount>>, (StatusCode, Json<Value>)> {
+    let accounts = sqlx::query_as::<_, BankAccount>(
+        "SELECT * FROM bank_accounts WHERE user_id = $1 ORDER BY name",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch bank accounts: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch bank accounts" })),
+        )
+    })?;
+
+    Ok(Json(accounts))
+}
+
+pub async fn create_bank_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateBankAccountRequest>,
+) -> Result<(StatusCode, Json<BankAccount>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create bank account" })),
+        )
+    })?;
+    let currency = match req.currency {
+        Some(currency) => currency,
+        None => currencies::home_currency(&mut conn, user_id).await?,
+    };
+
+    let account = sqlx::query_as::<_, BankAccount>(
+        r#"
+        INSERT INTO bank_accounts (user_id, name, account_number, currency, opening_balance)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.name)
+    .bind(req.account_number)
+    .bind(currency)
+    .bind(req.opening_balance)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create bank account: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create bank account" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(account)))
+}
+
+pub async fn get_bank_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BankAccount>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch bank account" })),
+        )
+    })?;
+
+    Ok(Json(fetch_bank_account(&mut conn, user_id, id).await?))
+}
+
+/// Renames an account. The currency is fixed once the account exists, as
+/// its transactions are in it.
+pub async fn update_bank_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateBankAccountRequest>,
+) -> Result<Json<BankAccount>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let account = sqlx::query_as::<_, BankAccount>(
+        r#"
+        UPDATE bank_accounts
+        SET
+            name = COALESCE($1, name),
+            account_number = COALESCE($2, account_number),
+            updated_at = NOW()
+        WHERE id = $3 AND user_id = $4
+        RETURNING *
+        "#,
+    )
+    .bind(req.name)
+    .bind(req.account_number)
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update bank account: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update bank account" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Bank account not found" })),
+        )
+    })?;
+
+    Ok(Json(account))
+}
+
+/// Deletes an account together with its imported transactions.
+pub async fn delete_bank_account(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM bank_accounts WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete bank account: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete bank account" })),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Bank account not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_bank_transactions(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(bank_account_id): Path<Uuid>,
+    Query(query): Query<BankTransactionsQuery>,
+) -> Result<Json<Vec<BankTransaction>>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch bank transactions" })),
+        )
+    })?;
+    fetch_bank_account(&mut conn, user_id, bank_account_id).await?;
+
+    let transactions = sqlx::query_as::<_, BankTransaction>(
+        r#"
+        SELECT * FROM bank_transactions
+        WHERE bank_account_id = $1 AND user_id = $2
+            AND ($3::date IS NULL OR transaction_date >= $3)
+            AND ($4::date IS NULL OR transaction_date <= $4)
+        ORDER BY transaction_date DESC, created_at DESC
+        "#,
+    )
+    .bind(bank_account_id)
+    .bind(user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch bank transactions: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch bank transactions" })),
+        )
+    })?;
+
+    Ok(Json(transactions))
+}
+
+/// Imports a statement uploaded in the "file" field. The format is detected
+/// unless given in a "format" field (`csv`, `ofx`, `qfx` or `camt053`); CSV
+/// columns can be described by a JSON [`CsvMapping`] in a "mapping" field.
+/// Transactions already imported from an earlier, overlapping statement are
+/// skipped, as are lines matching a transaction recorded by hand; bank rules
+/// are applied to the new ones.
+pub async fn import_statement(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(bank_account_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<Json<StatementImportSummary>, (StatusCode, Json<Value>)> {
+    let multipart_error = |e: axum::extract::multipart::MultipartError| {
+        (e.status(), Json(json!({ "error": e.body_text() })))
+    };
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(json!({ "error": error })));
+
+    let mut text = None;
+    let mut format = None;
+    let mut mapping = None;
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name() {
+            Some("file") => text = Some(field.text().await.map_err(multipart_error)?),
+            Some("format") => {
+                let name = field.text().await.map_err(multipart_error)?;
+                format = Some(StatementFormat::from_name(&name).ok_or_else(|| {
+                    bad_request(format!("Unsupported statement format \"{}\"", name))
+                })?);
+            }
+            Some("mapping") => {
+                let json = field.text().await.map_err(multipart_error)?;
+                mapping = Some(
+                    serde_json::from_str::<CsvMapping>(&json)
+                        .map_err(|e| bad_request(format!("Invalid column mapping: {}", e)))?,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let text = text
+        .ok_or_else(|| bad_request("Missing statement file in the \"file\" field".to_string()))?;
+    let format = format.unwrap_or_else(|| StatementFormat::detect(&text));
+    let statement = match format {
+        StatementFormat::Csv => parse_csv_statement(&text, &mapping.unwrap_or_default()),
+        StatementFormat::Ofx => parse_ofx(&text),
+        StatementFormat::Camt053 => parse_camt053(&text),
+    }
+    .map_err(bad_request)?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to import statement" })),
+        )
+    })?;
+
+    let account = fetch_bank_account(&mut tx, user_id, bank_account_id).await?;
+    if let Some(currency) = &statement.currency
+        && *currency != account.currency
+    {
+        return Err(bad_request(format!(
+            "Statement is in {} but the bank account is in {}",
+            currency, account.currency
+        )));
+    }
+
+    let mut imported = Vec::new();
+    for (transaction, fingerprint) in statement
+        .transactions
+        .iter()
+        .zip(fingerprints(&statement.transactions))
+    {
+        let claimed = claim_manual_transaction(
+            &mut tx,
+            bank_account_id,
+            transaction,
+            &fingerprint,
+            format.into(),
+        )
+        .await?;
+        if claimed.is_some() {
+            continue;
+        }
+
+        let inserted = insert_transaction(
+            &mut tx,
+            user_id,
+            bank_account_id,
+            transaction,
+            &fingerprint,
+            format.into(),
+        )
+        .await?;
+        imported.extend(inserted);
+    }
+
+    apply_rules(&mut tx, user_id, &account, &mut imported).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit statement import: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to import statement" })),
+        )
+    })?;
+
+    Ok(Json(StatementImportSummary {
+        format,
+        duplicates: statement.transactions.len() - imported.len(),
+        imported,
+    }))
+}
+
+/// Open invoices in the account's currency a deposit may pay, or expenses
+/// within a month of a withdrawal it may be, best match first.
+pub async fn suggest_matches(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<MatchSuggestion>>, (StatusCode, Json<Value>)> {
+    let transaction = sqlx::query_as::<_, BankTransaction>(
+        "SELECT * FROM bank_transactions WHERE id = $1 AND user_id = $2",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch bank transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch bank transaction" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Bank transaction not found" })),
+        )
+    })?;
+
+    let candidates = if transaction.amount > Decimal::ZERO {
+        sqlx::query_as::<_, MatchSuggestion>(
+            r#"
+            SELECT
+                'invoice'::varchar AS kind,
+                i.id,
+                i.invoice_number AS number,
+                CASE
+                    WHEN c.client_type = 'company' THEN COALESCE(c.company_name, '')
+                    ELSE CONCAT_WS(' ', c.first_name, c.last_name)
+                END AS name,
+                COALESCE(i.due_date, i.issue_date) AS date,
+                i.balance_due AS amount
+            FROM invoices i
+            JOIN clients c ON c.id = i.client_id
+            JOIN bank_accounts b ON b.id = $2
+            WHERE i.user_id = $1
+                AND i.status IN ('sent', 'partially_paid')
+                AND i.balance_due > 0
+                AND i.currency = b.currency
+            "#,
+        )
+        .bind(user_id)
+        .bind(transaction.bank_account_id)
+        .fetch_all(&pool)
+        .await
+    } else {
+        sqlx::query_as::<_, MatchSuggestion>(
+            r#"
+            SELECT
+                'expense'::varchar AS kind,
+                e.id,
+                NULL::varchar AS number,
+                e.vendor AS name,
+                e.expense_date AS date,
+                e.total AS amount
+            FROM expenses e
+            JOIN bank_accounts b ON b.id = $2
+            WHERE e.user_id = $1
+                AND e.currency = b.currency
+                AND e.expense_date BETWEEN $3::date - 31 AND $3::date + 31
+            "#,
+        )
+        .bind(user_id)
+        .bind(transaction.bank_account_id)
+        .bind(transaction.transaction_date)
+        .fetch_all(&pool)
+        .await
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to fetch match candidates: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to suggest matches" })),
+        )
+    })?;
+
+    Ok(Json(rank_suggestions(&transaction, candidates)))
+}
+
+/// Records a transaction by hand. A statement line imported later with the
+/// same date, amount and reference is recognised as this one.
+pub async fn create_bank_transaction(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(bank_account_id): Path<Uuid>,
+    Json(req): Json<CreateBankTransactionRequest>,
+) -> Result<(StatusCode, Json<BankTransaction>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create bank transaction" })),
+        )
+    })?;
+
+    let account = fetch_bank_account(&mut tx, user_id, bank_account_id).await?;
+    let parsed = ParsedTransaction {
+        transaction_date: req.transaction_date,
+        amount: req.amount,
+        description: req.description.trim().to_string(),
+        reference: req.reference,
+        counterparty: req.counterparty,
+        external_id: None,
+    };
+
+    // Counts as the next of any identical transactions already recorded
+    let mut occurrence = 1;
+    let transaction = loop {
+        let fingerprint = fingerprint(&parsed, occurrence);
+        if let Some(transaction) = insert_transaction(
+            &mut tx,
+            user_id,
+            bank_account_id,
+            &parsed,
+            &fingerprint,
+            TransactionSource::Manual,
+        )
+        .await?
+        {
+            break transaction;
+        }
+        occurrence += 1;
+    };
+
+    let mut transactions = [transaction];
+    apply_rules(&mut tx, user_id, &account, &mut transactions).await?;
+    let [transaction] = transactions;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create bank transaction" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(transaction)))
+}
+
+/// Deletes a transaction that has not been reconciled. An expense recorded
+/// for it is kept.
+pub async fn delete_bank_transaction(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let reconciliation_id = sqlx::query_scalar::<_, Option<Uuid>>(
+        r#"
+        WITH deleted AS (
+            DELETE FROM bank_transactions
+            WHERE id = $1 AND user_id = $2 AND reconciliation_id IS NULL
+            RETURNING reconciliation_id
+        )
+        SELECT reconciliation_id FROM deleted
+        UNION ALL
+        SELECT reconciliation_id FROM bank_transactions
+        WHERE id = $1 AND user_id = $2 AND reconciliation_id IS NOT NULL
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete bank transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete bank transaction" })),
+        )
+    })?;
+
+    match reconciliation_id {
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Bank transaction not found" })),
+        )),
+        Some(Some(_)) => Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Reconciled transactions cannot be deleted" })),
+        )),
+        Some(None) => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+pub async fn list_reconciliations(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(bank_account_id): Path<Uuid>,
+) -> Result<Json<Vec<BankReconciliation>>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch reconciliations" })),
+        )
+    })?;
+    fetch_bank_account(&mut conn, user_id, bank_account_id).await?;
+
+    let reconciliations = sqlx::query_as::<_, BankReconciliation>(
+        r#"
+        SELECT * FROM bank_reconciliations
+        WHERE bank_account_id = $1 AND user_id = $2
+        ORDER BY statement_date DESC, created_at DESC
+        "#,
+    )
+    .bind(bank_account_id)
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch reconciliations: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch reconciliations" })),
+        )
+    })?;
+
+    Ok(Json(reconciliations))
+}
+
+/// Starts reconciling the account against a statement. Only one
+/// reconciliation per account can be in progress, and statements are
+/// reconciled in date order.
+pub async fn create_reconciliation(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(bank_account_id): Path<Uuid>,
+    Json(req): Json<CreateReconciliationRequest>,
+) -> Result<(StatusCode, Json<ReconciliationDetails>), (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create reconciliation" })),
+        )
+    })?;
+
+    fetch_bank_account(&mut tx, user_id, bank_account_id).await?;
+
+    let last_statement_date = sqlx::query_scalar::<_, Option<NaiveDate>>(
+        r#"
+        SELECT MAX(statement_date) FROM bank_reconciliations
+        WHERE bank_account_id = $1 AND status = 'completed'
+        "#,
+    )
+    .bind(bank_account_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch reconciliations: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create reconciliation" })),
+        )
+    })?;
+    if let Some(last) = last_statement_date
+        && req.statement_date <= last
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("Statement date must be after the last reconciled statement of {}", last)
+            })),
+        ));
+    }
+
+    let reconciliation = sqlx::query_as::<_, BankReconciliation>(
+        r#"
+        INSERT INTO bank_reconciliations (
+            user_id, bank_account_id, statement_date, statement_ending_balance
+        )
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(bank_account_id)
+    .bind(req.statement_date)
+    .bind(req.statement_ending_balance)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        if e.as_database_error()
+            .is_some_and(|db| db.is_unique_violation())
+        {
+            return (
+                StatusCode::CONFLICT,
+                Json(
+                    json!({ "error": "A reconciliation is already in progress for this account" }),
+                ),
+            );
+        }
+
+        tracing::error!("Failed to create reconciliation: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create reconciliation" })),
+        )
+    })?;
+
+    let details = load_reconciliation(&mut tx, reconciliation).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create reconciliation" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(details)))
+}
+
+pub async fn get_reconciliation(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReconciliationDetails>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch reconciliation" })),
+        )
+    })?;
+
+    let reconciliation = fetch_reconciliation(&mut conn, user_id, id).await?;
+    Ok(Json(load_reconciliation(&mut conn, reconciliation).await?))
+}
+
+/// Ticks transactions off in a reconciliation in progress, or clears them.
+/// Only transactions of the account dated up to the statement date that are
+/// not reconciled elsewhere can be ticked off.
+pub async fn reconcile_transactions(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReconcileTransactionsRequest>,
+) -> Result<Json<ReconciliationDetails>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update reconciliation" })),
+        )
+    })?;
+
+    let reconciliation = fetch_reconciliation(&mut tx, user_id, id).await?;
+    if reconciliation.status != ReconciliationStatus::InProgress {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Reconciliation is already completed" })),
+        ));
+    }
+
+    let mut transaction_ids = req.transaction_ids;
+    transaction_ids.sort();
+    transaction_ids.dedup();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE bank_transactions
+        SET reconciliation_id = CASE WHEN $1 THEN $2 END, updated_at = NOW()
+        WHERE id = ANY($3)
+            AND user_id = $4
+            AND bank_account_id = $5
+            AND transaction_date <= $6
+            AND (reconciliation_id IS NULL OR reconciliation_id = $2)
+        "#,
+    )
+    .bind(req.reconciled)
+    .bind(reconciliation.id)
+    .bind(&transaction_ids)
+    .bind(user_id)
+    .bind(reconciliation.bank_account_id)
+    .bind(reconciliation.statement_date)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to reconcile bank transactions: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update reconciliation" })),
+        )
+    })?;
+
+    if result.rows_affected() != transaction_ids.len() as u64 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Some transactions are not open for this reconciliation"
+            })),
+        ));
+    }
+
+    let details = load_reconciliation(&mut tx, reconciliation).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update reconciliation" })),
+        )
+    })?;
+
+    Ok(Json(details))
+}
+
+/// Completes a reconciliation once its cleared balance agrees with the
+/// statement.
+pub async fn complete_reconciliation(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReconciliationDetails>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to complete reconciliation" })),
+        )
+    })?;
+
+    let reconciliation = fetch_reconciliation(&mut tx, user_id, id).await?;
+    if reconciliation.status != ReconciliationStatus::InProgress {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Reconciliation is already completed" })),
+        ));
+    }
+
+    let details = load_reconciliation(&mut tx, reconciliation).await?;
+    if !details.difference.is_zero() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!(
+                    "Cleared balance {} differs from the statement ending balance by {}",
+                    details.cleared_balance, details.difference
+                )
+            })),
+        ));
+    }
+
+    let reconciliation = sqlx::query_as::<_, BankReconciliation>(
+        r#"
+        UPDATE bank_reconciliations
+        SET status = 'completed', completed_at = NOW(), updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to complete reconciliation: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to complete reconciliation" })),
+        )
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to complete reconciliation" })),
+        )
+    })?;
+
+    Ok(Json(ReconciliationDetails {
+        reconciliation,
+        ..details
+    }))
+}
+
+/// Abandons a reconciliation in progress, clearing its ticked transactions.
+pub async fn delete_reconciliation(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete reconciliation" })),
+        )
+    })?;
+
+    let reconciliation = fetch_reconciliation(&mut tx, user_id, id).await?;
+    if reconciliation.status != ReconciliationStatus::InProgress {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Completed reconciliations cannot be deleted" })),
+        ));
+    }
+
+    sqlx::query("DELETE FROM bank_reconciliations WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete reconciliation: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete reconciliation" })),
+            )
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete reconciliation" })),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_bank_rules(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<BankRule>>, (StatusCode, Json<Value>)> {
+    let rules = sqlx::query_as::<_, BankRule>(
+        "SELECT * FROM bank_rules WHERE user_id = $1 ORDER BY priority DESC, created_at",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch bank rules: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch bank rules" })),
+        )
+    })?;
+
+    Ok(Json(rules))
+}
+
+/// Rule errors from the database: unknown accounts and tax rates are the
+/// caller's mistake.
+fn rule_error(e: sqlx::Error, action: &str) -> (StatusCode, Json<Value>) {
+    if e.as_database_error()
+        .is_some_and(|db| db.is_foreign_key_violation())
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Bank account or tax rate not found" })),
+        );
+    }
+
+    tracing::error!("Failed to {} bank rule: {}", action, e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("Failed to {} bank rule", action) })),
+    )
+}
+
+pub async fn create_bank_rule(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateBankRuleRequest>,
+) -> Result<(StatusCode, Json<BankRule>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let rule = sqlx::query_as::<_, BankRule>(
+        r#"
+        INSERT INTO bank_rules (
+            user_id, bank_account_id, name, pattern, vendor, category, tax_rate_id, priority
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.bank_account_id)
+    .bind(req.name)
+    .bind(req.pattern)
+    .bind(req.vendor)
+    .bind(req.category)
+    .bind(req.tax_rate_id)
+    .bind(req.priority)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| rule_error(e, "create"))?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn update_bank_rule(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateBankRuleRequest>,
+) -> Result<Json<BankRule>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let rule = sqlx::query_as::<_, BankRule>(
+        r#"
+        UPDATE bank_rules
+        SET
+            name = COALESCE($1, name),
+            pattern = COALESCE($2, pattern),
+            vendor = COALESCE($3, vendor),
+            category = COALESCE($4, category),
+            tax_rate_id = COALESCE($5, tax_rate_id),
+            priority = COALESCE($6, priority),
+            is_active = COALESCE($7, is_active),
+            updated_at = NOW()
+        WHERE id = $8 AND user_id = $9
+        RETURNING *
+        "#,
+    )
+    .bind(req.name)
+    .bind(req.pattern)
+    .bind(req.vendor)
+    .bind(req.category)
+    .bind(req.tax_rate_id)
+    .bind(req.priority)
+    .bind(req.is_active)
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| rule_error(e, "update"))?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Bank rule not found" })),
+        )
+    })?;
+
+    Ok(Json(rule))
+}
+
+/// Deletes a rule. Expenses it recorded are kept.
+pub async fn delete_bank_rule(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM bank_rules WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete bank rule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete bank rule" })),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Bank rule not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Runs the rules over all withdrawals without an expense, e.g. after
+/// adding a rule, and returns the transactions an expense was recorded for.
+pub async fn apply_bank_rules(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<BankTransaction>>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply bank rules" })),
+        )
+    })?;
+
+    let accounts =
+        sqlx::query_as::<_, BankAccount>("SELECT * FROM bank_accounts WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch bank accounts: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to apply bank rules" })),
+                )
+            })?;
+
+    let mut categorised = Vec::new();
+    for account in &accounts {
+        let mut transactions = sqlx::query_as::<_, BankTransaction>(
+            r#"
+            SELECT * FROM bank_transactions
+            WHERE bank_account_id = $1 AND expense_id IS NULL AND amount < 0
+            ORDER BY transaction_date, created_at
+            "#,
+        )
+        .bind(account.id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch bank transactions: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to apply bank rules" })),
+            )
+        })?;
+
+        apply_rules(&mut tx, user_id, account, &mut transactions).await?;
+        categorised.extend(
+            transactions
+                .into_iter()
+                .filter(|transaction| transaction.expense_id.is_some()),
+        );
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply bank rules" })),
+        )
+    })?;
+
+    Ok(Json(categorised))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn bank_transaction(cents: i64, description: &str) -> BankTransaction {
+        BankTransaction {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            bank_account_id: Uuid::nil(),
+            transaction_date: date(2026, 10, 16),
+            amount: Decimal::new(cents, 2),
+            description: description.to_string(),
+            reference: None,
+            counterparty: None,
+            external_id: None,
+            fingerprint: String::new(),
+            source: TransactionSource::Manual,
+            reconciliation_id: None,
+            expense_id: None,
+            bank_rule_id: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_statement_with_mapping() {
+        let text = "Booked;Text;Ref;Debit;Credit\n\
+                    15/10/2026;Card payment ACME;;1.234,50;\n\
+                    16/10/2026;\"Transfer; INV-0042\";INV-0042;;980,00\n\
+                    17/10/2026;Closing balance;;;\n";
+        let mapping: CsvMapping = serde_json::from_value(json!({
+            "date": "Booked",
+            "date_format": "%d/%m/%Y",
+            "debit": "Debit",
+            "credit": "Credit",
+            "description": "text",
+            "reference": "Ref",
+            "delimiter": ";",
+            "decimal_comma": true
+        }))
+        .unwrap();
+
+        let statement = parse_csv_statement(text, &mapping).unwrap();
+        assert_eq!(
+            statement.transactions,
+            vec![
+                ParsedTransaction {
+                    transaction_date: date(2026, 10, 15),
+                    amount: Decimal::new(-123450, 2),
+                    description: "Card payment ACME".to_string(),
+                    reference: None,
+                    counterparty: None,
+                    external_id: None,
+                },
+                ParsedTransaction {
+                    transaction_date: date(2026, 10, 16),
+                    amount: Decimal::new(98000, 2),
+                    description: "Transfer; INV-0042".to_string(),
+                    reference: Some("INV-0042".to_string()),
+                    counterparty: None,
+                    external_id: None,
+                },
+            ]
+        );
+
+        let bad_date = "Booked;Text;Ref;Debit;Credit\n2026-10-15;Fee;;1,00;\n";
+        assert_eq!(
+            parse_csv_statement(bad_date, &mapping).unwrap_err(),
+            "Line 2: invalid date \"2026-10-15\""
+        );
+    }
+
+    #[test]
+    fn test_parse_ofx_and_camt053() {
+        let ofx = "OFXHEADER:100\nDATA:OFXSGML\n\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>\n\
+                   <CURDEF>EUR\n<BANKTRANLIST>\n\
+                   <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20261015120000[-5:EST]\n\
+                   <TRNAMT>-42.10\n<FITID>2026101501\n<NAME>Coffee &amp; Co\n</STMTTRN>\n\
+                   <STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20261016<TRNAMT>980.00\
+                   <FITID>2026101602<NAME>Globex<MEMO>INV-0042</STMTTRN>\n\
+                   </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+        let statement = parse_ofx(ofx).unwrap();
+        assert_eq!(statement.currency.as_deref(), Some("EUR"));
+        assert_eq!(
+            statement.transactions,
+            vec![
+                ParsedTransaction {
+                    transaction_date: date(2026, 10, 15),
+                    amount: Decimal::new(-4210, 2),
+                    description: "Coffee & Co".to_string(),
+                    reference: None,
+                    counterparty: Some("Coffee & Co".to_string()),
+                    external_id: Some("2026101501".to_string()),
+                },
+                ParsedTransaction {
+                    transaction_date: date(2026, 10, 16),
+                    amount: Decimal::new(98000, 2),
+                    description: "INV-0042".to_string(),
+                    reference: None,
+                    counterparty: Some("Globex".to_string()),
+                    external_id: Some("2026101602".to_string()),
+                },
+            ]
+        );
+
+        let camt = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+              <BkToCstmrStmt><Stmt>
+                <Acct><Id><IBAN>DE89370400440532013000</IBAN></Id><Ccy>EUR</Ccy></Acct>
+                <Ntry>
+                  <Amt Ccy="EUR">980.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+                  <Sts><Cd>BOOK</Cd></Sts>
+                  <BookgDt><Dt>2026-10-16</Dt></BookgDt>
+                  <AcctSvcrRef>REF-1</AcctSvcrRef>
+                  <NtryDtls><TxDtls>
+                    <Refs><EndToEndId>NOTPROVIDED</EndToEndId></Refs>
+                    <RltdPties><Dbtr><Pty><Nm>Globex</Nm></Pty></Dbtr></RltdPties>
+                    <RmtInf><Ustrd>Invoice</Ustrd><Ustrd>INV-0042</Ustrd></RmtInf>
+                  </TxDtls></NtryDtls>
+                </Ntry>
+                <Ntry>
+                  <Amt Ccy="EUR">12.00</Amt><CdtDbtInd>DBIT</CdtDbtInd>
+                  <Sts><Cd>PDNG</Cd></Sts>
+                  <BookgDt><Dt>2026-10-17</Dt></BookgDt>
+                </Ntry>
+              </Stmt></BkToCstmrStmt>
+            </Document>"#;
+        assert_eq!(StatementFormat::detect(camt), StatementFormat::Camt053);
+        assert_eq!(
+            parse_camt053(camt).unwrap(),
+            ParsedStatement {
+                currency: Some("EUR".to_string()),
+                transactions: vec![ParsedTransaction {
+                    transaction_date: date(2026, 10, 16),
+                    amount: Decimal::new(98000, 2),
+                    description: "Invoice INV-0042".to_string(),
+                    reference: None,
+                    counterparty: Some("Globex".to_string()),
+                    external_id: Some("REF-1".to_string()),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_fingerprints_and_match_ranking() {
+        let coffee = ParsedTransaction {
+            transaction_date: date(2026, 10, 15),
+            amount: Decimal::new(-450, 2),
+            description: "Coffee".to_string(),
+            reference: None,
+            counterparty: None,
+            external_id: None,
+        };
+        let first = fingerprints(&[coffee.clone(), coffee.clone()]);
+        // Re-importing an overlapping statement yields the same fingerprints
+        let again = fingerprints(&[coffee.clone(), coffee.clone(), coffee]);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(first[..], again[..2]);
+
+        let mut transaction = bank_transaction(98000, "Payment inv 0042");
+        transaction.counterparty = Some("GLOBEX LTD".to_string());
+        let invoice = |number: &str, name: &str, amount: i64| MatchSuggestion {
+            kind: MatchKind::Invoice,
+            id: Uuid::new_v4(),
+            number: Some(number.to_string()),
+            name: name.to_string(),
+            date: date(2026, 10, 10),
+            amount: Decimal::new(amount, 2),
+            score: 0,
+        };
+
+        let ranked = rank_suggestions(
+            &transaction,
+            vec![
+                invoice("INV-0051", "Initech", 98000),
+                invoice("INV-0042", "Globex", 98000),
+                invoice("INV-0063", "Umbrella", 12000),
+            ],
+        );
+        let numbers: Vec<_> = ranked
+            .iter()
+            .map(|suggestion| (suggestion.number.as_deref().unwrap(), suggestion.score))
+            .collect();
+        assert_eq!(numbers, vec![("INV-0042", 95), ("INV-0051", 55)]);
+    }
+
+    #[test]
+    fn test_rules_and_reconciliation_balance() {
+        let rule = |name: &str, pattern: &str, bank_account_id: Option<Uuid>| BankRule {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            bank_account_id,
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            vendor: None,
+            category: "Fees".to_string(),
+            tax_rate_id: None,
+            priority: 0,
+            is_active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let rules = vec![
+            rule("Other account", "stripe", Some(Uuid::new_v4())),
+            rule("Stripe", "STRIPE", None),
+        ];
+
+        let fee = bank_transaction(-290, "Stripe payout fee");
+        assert_eq!(
+            matching_rule(&rules, &fee).map(|rule| rule.name.as_str()),
+            Some("Stripe")
+        );
+        assert!(matching_rule(&rules, &bank_transaction(290, "Stripe refund")).is_none());
+        assert!(matching_rule(&rules, &bank_transaction(-290, "Coffee")).is_none());
+
+        assert_eq!(
+            split_gross(Decimal::new(11500, 2), Some(Decimal::from(15))),
+            (Decimal::new(10000, 2), Decimal::new(1500, 2))
+        );
+        assert_eq!(
+            split_gross(Decimal::new(1000, 2), Some(Decimal::from(7))),
+            (Decimal::new(935, 2), Decimal::new(65, 2))
+        );
+
+        let reconciliation = BankReconciliation {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            bank_account_id: Uuid::nil(),
+            statement_date: date(2026, 10, 31),
+            statement_ending_balance: Decimal::new(97710, 2),
+            status: ReconciliationStatus::InProgress,
+            completed_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let mut deposit = bank_transaction(98000, "Globex");
+        deposit.reconciliation_id = Some(reconciliation.id);
+        let mut ticked_fee = fee;
+        ticked_fee.reconciliation_id = Some(reconciliation.id);
+        let outstanding = bank_transaction(-5000, "Cheque 104");
+
+        let details = reconciliation_details(
+            reconciliation,
+            Decimal::new(1000, 2),
+            vec![deposit, ticked_fee, outstanding],
+        );
+        assert_eq!(details.cleared_balance, Decimal::new(98710, 2));
+        assert_eq!(details.difference, Decimal::new(-1000, 2));
+        assert_eq!(details.transactions.len(), 3);
+    }
+}
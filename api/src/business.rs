@@ -0,0 +1,196 @@
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::currencies::{self, validate_currency};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BusinessProfile {
+    pub user_id: Uuid,
+    pub business_name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub website: Option<String>,
+    pub tax_number: Option<String>,
+    pub country: Option<String>,
+    pub address_line1: Option<String>,
+    pub address_line2: Option<String>,
+    pub city: Option<String>,
+    pub province: Option<String>,
+    pub postal_code: Option<String>,
+    /// Currency the books are kept in.
+    pub home_currency: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpsertBusinessProfileRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub business_name: String,
+    #[validate(email)]
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub website: Option<String>,
+    pub tax_number: Option<String>,
+    pub country: Option<String>,
+    pub address_line1: Option<String>,
+    pub address_line2: Option<String>,
+    pub city: Option<String>,
+    pub province: Option<String>,
+    pub postal_code: Option<String>,
+    /// Kept as is when omitted; cannot change once anything has been posted.
+    #[validate(custom(function = "validate_currency"))]
+    pub home_currency: Option<String>,
+}
+
+pub(crate) async fn fetch_business_profile(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Option<BusinessProfile>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, BusinessProfile>("SELECT * FROM business_profiles WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch business profile: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch business profile" })),
+            )
+        })
+}
+
+pub async fn get_business_profile(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<BusinessProfile>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch business profile" })),
+        )
+    })?;
+
+    let profile = fetch_business_profile(&mut conn, user_id)
+        .await?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Business profile not found" })),
+            )
+        })?;
+
+    Ok(Json(profile))
+}
+
+pub async fn upsert_business_profile(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<UpsertBusinessProfileRequest>,
+) -> Result<Json<BusinessProfile>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save business profile" })),
+        )
+    })?;
+
+    if let Some(home_currency) = &req.home_currency
+        && *home_currency != currencies::home_currency(&mut tx, user_id).await?
+    {
+        let has_entries = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM journal_entries WHERE user_id = $1)",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check journal entries: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to save business profile" })),
+            )
+        })?;
+
+        // Posted amounts are in the old currency and would silently change meaning
+        if has_entries {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Home currency cannot change once documents have been posted"
+                })),
+            ));
+        }
+    }
+
+    let profile = sqlx::query_as::<_, BusinessProfile>(
+        r#"
+        INSERT INTO business_profiles (
+            user_id, business_name, email, phone, website, tax_number, country,
+            address_line1, address_line2, city, province, postal_code, home_currency
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, COALESCE($13, $14))
+        ON CONFLICT (user_id) DO UPDATE SET
+            business_name = EXCLUDED.business_name,
+            email = EXCLUDED.email,
+            phone = EXCLUDED.phone,
+            website = EXCLUDED.website,
+            tax_number = EXCLUDED.tax_number,
+            country = EXCLUDED.country,
+            address_line1 = EXCLUDED.address_line1,
+            address_line2 = EXCLUDED.address_line2,
+            city = EXCLUDED.city,
+            province = EXCLUDED.province,
+            postal_code = EXCLUDED.postal_code,
+            home_currency = COALESCE($13, business_profiles.home_currency),
+            updated_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.business_name)
+    .bind(req.email)
+    .bind(req.phone)
+    .bind(req.website)
+    .bind(req.tax_number)
+    .bind(req.country)
+    .bind(req.address_line1)
+    .bind(req.address_line2)
+    .bind(req.city)
+    .bind(req.province)
+    .bind(req.postal_code)
+    .bind(req.home_currency)
+    .bind(currencies::DEFAULT_HOME_CURRENCY)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save business profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save business profile" })),
+        )
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit business profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save business profile" })),
+        )
+    })?;
+
+    Ok(Json(profile))
+}
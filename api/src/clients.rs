@@ -3,12 +3,16 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::currencies::validate_currency;
+use crate::invoices::validate_non_negative;
+
 #[derive(Debug, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "VARCHAR", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
@@ -40,6 +44,10 @@ pub struct Client {
     pub city: Option<String>,
     pub province: Option<String>,
     pub postal_code: Option<String>,
+    /// Rate used for time billed to this client unless an entry sets its own.
+    pub default_hourly_rate: Option<Decimal>,
+    /// Currency new invoices and payments default to; `None` uses the home currency.
+    pub currency: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -59,6 +67,10 @@ pub struct CreateClientRequest {
     pub city: Option<String>,
     pub province: Option<String>,
     pub postal_code: Option<String>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub default_hourly_rate: Option<Decimal>,
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -76,6 +88,36 @@ pub struct UpdateClientRequest {
     pub city: Option<String>,
     pub province: Option<String>,
     pub postal_code: Option<String>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub default_hourly_rate: Option<Decimal>,
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+}
+
+/// Loads a client owned by `user_id`, for documents that reference one.
+pub(crate) async fn fetch_owned_client(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    client_id: Uuid,
+) -> Result<Client, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Client>("SELECT * FROM clients WHERE id = $1 AND user_id = $2")
+        .bind(client_id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch client: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch client" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Client not found" })),
+            )
+        })
 }
 
 pub async fn list_clients(
@@ -125,9 +167,9 @@ pub async fn create_client(
         INSERT INTO clients (
             user_id, client_type, company_name, first_name, last_name, email,
             phone_numbers, country, address_line1, address_line2,
-            city, province, postal_code
+            city, province, postal_code, default_hourly_rate, currency
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
         RETURNING *
         "#,
     )
@@ -144,6 +186,8 @@ pub async fn create_client(
     .bind(req.city)
     .bind(req.province)
     .bind(req.postal_code)
+    .bind(req.default_hourly_rate)
+    .bind(req.currency)
     .fetch_one(&pool)
     .await
     .map_err(|e| {
@@ -255,8 +299,10 @@ pub async fn update_client(
             city = COALESCE($10, city),
             province = COALESCE($11, province),
             postal_code = COALESCE($12, postal_code),
+            default_hourly_rate = COALESCE($13, default_hourly_rate),
+            currency = COALESCE($14, currency),
             updated_at = NOW()
-        WHERE id = $13 AND user_id = $14
+        WHERE id = $15 AND user_id = $16
         RETURNING *
         "#,
     )
@@ -272,6 +318,8 @@ pub async fn update_client(
     .bind(req.city)
     .bind(req.province)
     .bind(req.postal_code)
+    .bind(req.default_hourly_rate)
+    .bind(req.currency)
     .bind(id)
     .bind(user_id)
     .fetch_one(&pool)
@@ -298,6 +346,16 @@ pub async fn delete_client(
         .execute(&pool)
         .await
         .map_err(|e| {
+            // Documents such as invoices keep their client from being deleted
+            if e.as_database_error()
+                .is_some_and(|db| db.is_foreign_key_violation())
+            {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({ "error": "Client has documents and cannot be deleted" })),
+                );
+            }
+
             tracing::error!("Failed to delete client: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
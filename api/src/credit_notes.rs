@@ -0,0 +1,930 @@
+use axum::{
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::business;
+use crate::clients;
+use crate::currencies;
+use crate::invoices::{
+    self, InvoiceStatus, LineItemRequest, LineItemTaxRow, NewLineItem, compute_totals, line_amount,
+    round_money,
+};
+use crate::ledger::{self, SourceType};
+use crate::numbering::{self, DocumentType};
+use crate::payments::AllocationRequest;
+use crate::pdf;
+use crate::taxes::{self, AppliedTax, TaxSummary};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CreditNote {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Uuid,
+    pub invoice_id: Uuid,
+    pub credit_note_number: String,
+    pub issue_date: NaiveDate,
+    pub reason: Option<String>,
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub total: Decimal,
+    pub amount_applied: Decimal,
+    /// Client credit not yet allocated to other invoices.
+    pub unapplied_amount: Decimal,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl CreditNote {
+    /// Home currency value of allocating `amount` more of the client credit
+    /// left over, at the credited invoice's `exchange_rate`.
+    fn allocated_home(&self, exchange_rate: Decimal, amount: Decimal) -> Decimal {
+        let left_over = self.total - self.amount_applied;
+        currencies::settled_home(
+            left_over,
+            currencies::to_home(self.subtotal, exchange_rate)
+                + currencies::to_home(self.tax_total, exchange_rate)
+                - currencies::to_home(self.amount_applied, exchange_rate),
+            exchange_rate,
+            left_over - self.unapplied_amount,
+            amount,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CreditNoteAllocation {
+    pub id: Uuid,
+    pub credit_note_id: Uuid,
+    pub invoice_id: Uuid,
+    pub amount: Decimal,
+    pub applied_on: NaiveDate,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CreditNoteLineItem {
+    pub id: Uuid,
+    pub credit_note_id: Uuid,
+    pub position: i32,
+    pub description: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub amount: Decimal,
+    #[sqlx(skip)]
+    pub taxes: Vec<AppliedTax>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CreditNoteTax {
+    pub id: Uuid,
+    pub credit_note_id: Uuid,
+    pub tax_rate_id: Uuid,
+    pub position: i32,
+    pub name: String,
+    pub rate: Decimal,
+    pub is_compound: bool,
+    pub taxable_amount: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreditNoteWithLineItems {
+    #[serde(flatten)]
+    pub credit_note: CreditNote,
+    pub line_items: Vec<CreditNoteLineItem>,
+    pub taxes: Vec<CreditNoteTax>,
+    pub allocations: Vec<CreditNoteAllocation>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCreditNoteRequest {
+    pub invoice_id: Uuid,
+    pub issue_date: Option<NaiveDate>,
+    pub reason: Option<String>,
+    /// Lines being credited; when omitted the whole invoice is credited.
+    #[validate(
+        length(min = 1, message = "At least one line item is required"),
+        nested
+    )]
+    pub line_items: Option<Vec<LineItemRequest>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApplyCreditNoteRequest {
+    #[validate(
+        length(min = 1, message = "At least one allocation is required"),
+        nested
+    )]
+    pub allocations: Vec<AllocationRequest>,
+}
+
+/// Splits a credit into the part that reduces the invoice balance and the part
+/// left over as client credit.
+pub fn split_credit(total: Decimal, balance_due: Decimal) -> (Decimal, Decimal) {
+    let applied = total.min(balance_due.max(Decimal::ZERO));
+    (applied, total - applied)
+}
+
+async fn fetch_credit_note(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<CreditNote, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, CreditNote>("SELECT * FROM credit_notes WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch credit note: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch credit note" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Credit note not found" })),
+            )
+        })
+}
+
+async fn lock_credit_note(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<CreditNote, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, CreditNote>(
+        "SELECT * FROM credit_notes WHERE id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credit note: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch credit note" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Credit note not found" })),
+        )
+    })
+}
+
+async fn fetch_allocations(
+    conn: &mut PgConnection,
+    credit_note_id: Uuid,
+) -> Result<Vec<CreditNoteAllocation>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, CreditNoteAllocation>(
+        "SELECT * FROM credit_note_allocations WHERE credit_note_id = $1 ORDER BY created_at",
+    )
+    .bind(credit_note_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credit note allocations: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch credit note allocations" })),
+        )
+    })
+}
+
+async fn fetch_line_items(
+    conn: &mut PgConnection,
+    credit_note_id: Uuid,
+) -> Result<Vec<CreditNoteLineItem>, (StatusCode, Json<Value>)> {
+    let mut line_items = sqlx::query_as::<_, CreditNoteLineItem>(
+        "SELECT * FROM credit_note_line_items WHERE credit_note_id = $1 ORDER BY position",
+    )
+    .bind(credit_note_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credit note line items: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch credit note line items" })),
+        )
+    })?;
+
+    let tax_rows = sqlx::query_as::<_, LineItemTaxRow>(
+        r#"
+        SELECT t.line_item_id, t.tax_rate_id, t.name, t.rate, t.is_compound
+        FROM credit_note_line_item_taxes t
+        JOIN credit_note_line_items l ON l.id = t.line_item_id
+        WHERE l.credit_note_id = $1
+        ORDER BY t.position
+        "#,
+    )
+    .bind(credit_note_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credit note line item taxes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch credit note line items" })),
+        )
+    })?;
+
+    for row in tax_rows {
+        if let Some(line_item) = line_items
+            .iter_mut()
+            .find(|item| item.id == row.line_item_id)
+        {
+            line_item.taxes.push(row.tax);
+        }
+    }
+
+    Ok(line_items)
+}
+
+async fn fetch_credit_note_taxes(
+    conn: &mut PgConnection,
+    credit_note_id: Uuid,
+) -> Result<Vec<CreditNoteTax>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, CreditNoteTax>(
+        "SELECT * FROM credit_note_taxes WHERE credit_note_id = $1 ORDER BY position",
+    )
+    .bind(credit_note_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credit note taxes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch credit note taxes" })),
+        )
+    })
+}
+
+async fn insert_line_items(
+    conn: &mut PgConnection,
+    credit_note_id: Uuid,
+    line_items: &[NewLineItem],
+) -> Result<(), (StatusCode, Json<Value>)> {
+    for (position, item) in line_items.iter().enumerate() {
+        let line_item_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO credit_note_line_items (
+                credit_note_id, position, description, quantity, unit_price, amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+        )
+        .bind(credit_note_id)
+        .bind(position as i32)
+        .bind(&item.description)
+        .bind(item.quantity)
+        .bind(item.unit_price)
+        .bind(line_amount(item.quantity, item.unit_price))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert credit note line item: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to save credit note line items" })),
+            )
+        })?;
+
+        for (tax_position, tax) in item.taxes.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO credit_note_line_item_taxes (
+                    line_item_id, tax_rate_id, position, name, rate, is_compound
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(line_item_id)
+            .bind(tax.tax_rate_id)
+            .bind(tax_position as i32)
+            .bind(&tax.name)
+            .bind(tax.rate)
+            .bind(tax.is_compound)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to insert credit note line item tax: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to save credit note line items" })),
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn insert_tax_summaries(
+    conn: &mut PgConnection,
+    credit_note_id: Uuid,
+    tax_summaries: &[TaxSummary],
+) -> Result<(), (StatusCode, Json<Value>)> {
+    for (position, summary) in tax_summaries.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO credit_note_taxes (
+                credit_note_id, tax_rate_id, position, name, rate, is_compound,
+                taxable_amount, amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(credit_note_id)
+        .bind(summary.tax.tax_rate_id)
+        .bind(position as i32)
+        .bind(&summary.tax.name)
+        .bind(summary.tax.rate)
+        .bind(summary.tax.is_compound)
+        .bind(summary.taxable_amount)
+        .bind(summary.amount)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert credit note tax: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to save credit note taxes" })),
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+pub async fn list_credit_notes(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<CreditNote>>, (StatusCode, Json<Value>)> {
+    let credit_notes = sqlx::query_as::<_, CreditNote>(
+        "SELECT * FROM credit_notes WHERE user_id = $1 ORDER BY issue_date DESC, created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credit notes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch credit notes" })),
+        )
+    })?;
+
+    Ok(Json(credit_notes))
+}
+
+/// Issues a credit note against an invoice. The credit first reduces the
+/// invoice's balance due; anything beyond it becomes client credit.
+pub async fn create_credit_note(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateCreditNoteRequest>,
+) -> Result<(StatusCode, Json<CreditNoteWithLineItems>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create credit note" })),
+        )
+    })?;
+
+    let invoice = invoices::lock_invoice(&mut tx, user_id, req.invoice_id).await?;
+
+    if !matches!(
+        invoice.status,
+        InvoiceStatus::Sent | InvoiceStatus::PartiallyPaid | InvoiceStatus::Paid
+    ) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!(
+                    "Cannot credit a {} invoice",
+                    invoice.status.as_str()
+                )
+            })),
+        ));
+    }
+
+    let issue_date = req
+        .issue_date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    if issue_date < invoice.issue_date {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Credit note cannot be dated before the invoice" })),
+        ));
+    }
+
+    let line_items: Vec<NewLineItem> = match &req.line_items {
+        Some(items) => {
+            let client = clients::fetch_owned_client(&mut tx, user_id, invoice.client_id).await?;
+            let requested_taxes: Vec<Option<Vec<Uuid>>> =
+                items.iter().map(|item| item.tax_rate_ids.clone()).collect();
+            let line_taxes =
+                taxes::resolve_line_taxes(&mut tx, user_id, &client, &requested_taxes).await?;
+
+            items
+                .iter()
+                .zip(line_taxes)
+                .map(|(item, taxes)| NewLineItem {
+                    description: item.description.clone(),
+                    quantity: item.quantity,
+                    unit_price: item.unit_price,
+                    taxes,
+                })
+                .collect()
+        }
+        None => invoices::fetch_line_items(&mut tx, invoice.id)
+            .await?
+            .into_iter()
+            .map(|item| NewLineItem {
+                description: item.description,
+                quantity: item.quantity,
+                unit_price: item.unit_price,
+                taxes: item.taxes,
+            })
+            .collect(),
+    };
+
+    let lines: Vec<(Decimal, Vec<AppliedTax>)> = line_items
+        .iter()
+        .map(|item| {
+            (
+                line_amount(item.quantity, item.unit_price),
+                item.taxes.clone(),
+            )
+        })
+        .collect();
+    let (totals, tax_summaries) = compute_totals(&lines, invoice.tax_rounding);
+
+    if totals.total <= Decimal::ZERO {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Credit note total must be greater than zero" })),
+        ));
+    }
+
+    let already_credited = sqlx::query_scalar::<_, Decimal>(
+        "SELECT COALESCE(SUM(total), 0) FROM credit_notes WHERE invoice_id = $1",
+    )
+    .bind(invoice.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to sum invoice credits: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create credit note" })),
+        )
+    })?;
+
+    if already_credited + totals.total > invoice.total {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!(
+                    "Credit exceeds the {} left to credit on this invoice",
+                    invoice.total - already_credited
+                )
+            })),
+        ));
+    }
+
+    let (amount_applied, unapplied_amount) = split_credit(totals.total, invoice.balance_due);
+    let credit_note_number =
+        numbering::next_number(&mut tx, user_id, DocumentType::CreditNote).await?;
+
+    let credit_note = sqlx::query_as::<_, CreditNote>(
+        r#"
+        INSERT INTO credit_notes (
+            user_id, client_id, invoice_id, credit_note_number, issue_date, reason,
+            subtotal, tax_total, total, amount_applied, unapplied_amount
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(invoice.client_id)
+    .bind(invoice.id)
+    .bind(&credit_note_number)
+    .bind(issue_date)
+    .bind(&req.reason)
+    .bind(totals.subtotal)
+    .bind(totals.tax_total)
+    .bind(totals.total)
+    .bind(amount_applied)
+    .bind(unapplied_amount)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create credit note: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create credit note" })),
+        )
+    })?;
+
+    // Credit notes are in the invoice's currency and take back at its rate
+    let exchange_rate = invoice.exchange_rate.unwrap_or(Decimal::ONE);
+    let applied_home = invoice.settled_home(credit_note.amount_applied);
+
+    insert_line_items(&mut tx, credit_note.id, &line_items).await?;
+    insert_tax_summaries(&mut tx, credit_note.id, &tax_summaries).await?;
+    invoices::refresh_balance(&mut tx, invoice.id).await?;
+
+    ledger::post(
+        &mut tx,
+        user_id,
+        (SourceType::CreditNote, credit_note.id),
+        credit_note.issue_date,
+        &format!("Credit note {}", credit_note.credit_note_number),
+        &ledger::credit_note_postings(
+            currencies::to_home(credit_note.subtotal, exchange_rate),
+            currencies::to_home(credit_note.tax_total, exchange_rate),
+            applied_home,
+        ),
+    )
+    .await?;
+
+    let line_items = fetch_line_items(&mut tx, credit_note.id).await?;
+    let taxes = fetch_credit_note_taxes(&mut tx, credit_note.id).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit credit note: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create credit note" })),
+        )
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreditNoteWithLineItems {
+            credit_note,
+            line_items,
+            taxes,
+            allocations: Vec::new(),
+        }),
+    ))
+}
+
+pub async fn get_credit_note(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CreditNoteWithLineItems>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch credit note" })),
+        )
+    })?;
+
+    let credit_note = fetch_credit_note(&mut conn, user_id, id).await?;
+    let line_items = fetch_line_items(&mut conn, credit_note.id).await?;
+    let taxes = fetch_credit_note_taxes(&mut conn, credit_note.id).await?;
+    let allocations = fetch_allocations(&mut conn, credit_note.id).await?;
+
+    Ok(Json(CreditNoteWithLineItems {
+        credit_note,
+        line_items,
+        taxes,
+        allocations,
+    }))
+}
+
+/// Applies client credit left over by a credit note to invoices of the same
+/// client and currency. The credit is posted to the ledger when applied, with
+/// any exchange difference between the credited and the settled invoices
+/// realised as a gain or loss.
+pub async fn apply_credit_note(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ApplyCreditNoteRequest>,
+) -> Result<Json<CreditNoteWithLineItems>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply credit note" })),
+        )
+    })?;
+
+    let credit_note = lock_credit_note(&mut tx, user_id, id).await?;
+    let (currency, exchange_rate) = sqlx::query_as::<_, (String, Option<Decimal>)>(
+        "SELECT currency, exchange_rate FROM invoices WHERE id = $1",
+    )
+    .bind(credit_note.invoice_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credited invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply credit note" })),
+        )
+    })?;
+    let exchange_rate = exchange_rate.unwrap_or(Decimal::ONE);
+
+    let mut allocations: Vec<(Uuid, Decimal)> = req
+        .allocations
+        .iter()
+        .map(|allocation| (allocation.invoice_id, round_money(allocation.amount)))
+        .collect();
+    // Lock invoices in a stable order so concurrent allocations cannot deadlock
+    allocations.sort_by_key(|(invoice_id, _)| *invoice_id);
+
+    if allocations.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Each invoice can only be allocated once per credit note" })),
+        ));
+    }
+
+    let allocated: Decimal = allocations.iter().map(|(_, amount)| *amount).sum();
+    if allocated > credit_note.unapplied_amount {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Allocations exceed the unapplied credit" })),
+        ));
+    }
+
+    // Credit applied later is posted when it is applied, not when it was issued
+    let applied_on = chrono::Utc::now().date_naive().max(credit_note.issue_date);
+    let mut receivable_settled = Decimal::ZERO;
+    for (invoice_id, amount) in &allocations {
+        let invoice = invoices::lock_invoice(&mut tx, user_id, *invoice_id).await?;
+
+        if invoice.client_id != credit_note.client_id {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "Credit can only be applied to the same client's invoices"
+                })),
+            ));
+        }
+
+        if invoice.currency != currency {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!(
+                        "{} credit cannot be applied to a {} invoice",
+                        currency, invoice.currency
+                    )
+                })),
+            ));
+        }
+
+        if !matches!(
+            invoice.status,
+            InvoiceStatus::Sent | InvoiceStatus::PartiallyPaid
+        ) {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": format!(
+                        "Cannot apply credit to a {} invoice",
+                        invoice.status.as_str()
+                    )
+                })),
+            ));
+        }
+
+        if *amount > invoice.balance_due {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Allocation exceeds the invoice balance due" })),
+            ));
+        }
+        receivable_settled += invoice.settled_home(*amount);
+
+        sqlx::query(
+            r#"
+            INSERT INTO credit_note_allocations (credit_note_id, invoice_id, amount, applied_on)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (credit_note_id, invoice_id)
+            DO UPDATE SET amount = credit_note_allocations.amount + EXCLUDED.amount
+            "#,
+        )
+        .bind(credit_note.id)
+        .bind(invoice_id)
+        .bind(amount)
+        .bind(applied_on)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save credit note allocation: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to apply credit note" })),
+            )
+        })?;
+
+        invoices::refresh_balance(&mut tx, *invoice_id).await?;
+    }
+
+    ledger::post(
+        &mut tx,
+        user_id,
+        (SourceType::CreditNote, credit_note.id),
+        applied_on,
+        &format!(
+            "Credit note {} applied to invoices",
+            credit_note.credit_note_number
+        ),
+        &ledger::allocation_postings(
+            credit_note.allocated_home(exchange_rate, allocated),
+            receivable_settled,
+        ),
+    )
+    .await?;
+
+    let credit_note = sqlx::query_as::<_, CreditNote>(
+        r#"
+        UPDATE credit_notes
+        SET unapplied_amount = total - amount_applied - COALESCE(
+            (SELECT SUM(a.amount) FROM credit_note_allocations a WHERE a.credit_note_id = credit_notes.id),
+            0
+        )
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(credit_note.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update credit note: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply credit note" })),
+        )
+    })?;
+
+    let line_items = fetch_line_items(&mut tx, credit_note.id).await?;
+    let taxes = fetch_credit_note_taxes(&mut tx, credit_note.id).await?;
+    let allocations = fetch_allocations(&mut tx, credit_note.id).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit credit note allocation: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply credit note" })),
+        )
+    })?;
+
+    Ok(Json(CreditNoteWithLineItems {
+        credit_note,
+        line_items,
+        taxes,
+        allocations,
+    }))
+}
+
+pub async fn get_credit_note_pdf(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to render credit note" })),
+        )
+    })?;
+
+    let credit_note = fetch_credit_note(&mut conn, user_id, id).await?;
+    let line_items = fetch_line_items(&mut conn, credit_note.id).await?;
+    let taxes = fetch_credit_note_taxes(&mut conn, credit_note.id).await?;
+    let invoice_number = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT invoice_number FROM invoices WHERE id = $1",
+    )
+    .bind(credit_note.invoice_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch credited invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to render credit note" })),
+        )
+    })?;
+    let client = clients::fetch_owned_client(&mut conn, user_id, credit_note.client_id).await?;
+    let business = business::fetch_business_profile(&mut conn, user_id).await?;
+
+    let document = pdf::DocumentModel::for_credit_note(
+        &credit_note,
+        invoice_number.as_deref(),
+        &line_items,
+        &taxes,
+        &client,
+        business.as_ref(),
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "inline; filename=\"{}.pdf\"",
+                    credit_note.credit_note_number
+                ),
+            ),
+        ],
+        pdf::render(&document),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_split_credit() {
+        assert_eq!(
+            split_credit(decimal("40.00"), decimal("100.00")),
+            (decimal("40.00"), Decimal::ZERO)
+        );
+        assert_eq!(
+            split_credit(decimal("40.00"), decimal("25.00")),
+            (decimal("25.00"), decimal("15.00"))
+        );
+        assert_eq!(
+            split_credit(decimal("40.00"), Decimal::ZERO),
+            (Decimal::ZERO, decimal("40.00"))
+        );
+    }
+
+    #[test]
+    fn test_allocated_home_adds_up_to_the_credit_left_over() {
+        let mut credit_note = CreditNote {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            client_id: Uuid::new_v4(),
+            invoice_id: Uuid::new_v4(),
+            credit_note_number: "CN-0001".to_string(),
+            issue_date: NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(),
+            reason: None,
+            subtotal: decimal("90.91"),
+            tax_total: decimal("9.09"),
+            total: decimal("100.00"),
+            amount_applied: decimal("40.00"),
+            unapplied_amount: decimal("60.00"),
+            created_at: chrono::Utc::now(),
+        };
+        let rate = decimal("1.0833");
+
+        let first = credit_note.allocated_home(rate, decimal("25.00"));
+        credit_note.unapplied_amount = decimal("35.00");
+        let rest = credit_note.allocated_home(rate, decimal("35.00"));
+
+        assert_eq!(first, decimal("27.08"));
+        // 98.48 + 9.85 - 43.33 left over as client credit when issued
+        assert_eq!(first + rest, decimal("65.00"));
+    }
+}
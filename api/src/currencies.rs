@@ -0,0 +1,659 @@
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::clients::Client;
+use crate::invoices::{round_money, validate_positive};
+use crate::reports;
+
+/// Home currency of users who have not chosen one.
+pub const DEFAULT_HOME_CURRENCY: &str = "USD";
+
+/// Largest rates file accepted by the import, enough for the full ECB history.
+pub const MAX_RATES_FILE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Base currency of the rates published by the European Central Bank.
+const ECB_BASE_CURRENCY: &str = "EUR";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RateSource {
+    Manual,
+    Csv,
+    Ecb,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExchangeRate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub base_currency: String,
+    pub quote_currency: String,
+    /// Units of the quote currency one unit of the base currency buys.
+    pub rate: Decimal,
+    pub rate_date: NaiveDate,
+    pub source: RateSource,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A rate read from an import file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewExchangeRate {
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: Decimal,
+    pub rate_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateExchangeRateRequest {
+    #[validate(custom(function = "validate_currency"))]
+    pub base_currency: String,
+    #[validate(custom(function = "validate_currency"))]
+    pub quote_currency: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub rate: Decimal,
+    pub rate_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExchangeRatesQuery {
+    /// Only rates to or from this currency.
+    pub currency: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSummary {
+    pub source: RateSource,
+    pub imported: usize,
+}
+
+/// ISO 4217 style code: three upper case letters, e.g. `EUR`.
+pub fn validate_currency(code: &str) -> Result<(), ValidationError> {
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_currency_code"))
+    }
+}
+
+/// Converts a document amount to the home currency.
+pub fn to_home(amount: Decimal, exchange_rate: Decimal) -> Decimal {
+    round_money(amount * exchange_rate)
+}
+
+/// Home currency value of settling `amount` of a document after
+/// `settled_before` was already settled. Each part is the difference of the
+/// rounded running totals, so the parts always add up to `total_home` once
+/// the whole `total` is settled.
+pub fn settled_home(
+    total: Decimal,
+    total_home: Decimal,
+    exchange_rate: Decimal,
+    settled_before: Decimal,
+    amount: Decimal,
+) -> Decimal {
+    let home = |settled: Decimal| {
+        if settled >= total {
+            total_home
+        } else {
+            to_home(settled, exchange_rate)
+        }
+    };
+
+    home(settled_before + amount) - home(settled_before)
+}
+
+/// Reads rates from CSV with a header row naming `date`, `base`, `quote` and
+/// `rate` columns in any order. Dates are `YYYY-MM-DD`.
+pub fn parse_rates_csv(text: &str) -> Result<Vec<NewExchangeRate>, String> {
+    let mut records = reports::parse_csv(text, ',').into_iter();
+    let header: Vec<String> = records
+        .next()
+        .ok_or("CSV file is empty")?
+        .iter()
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+    let column = |names: &[&str]| {
+        header
+            .iter()
+            .position(|name| names.contains(&name.as_str()))
+            .ok_or_else(|| format!("CSV header needs a {} column", names[0]))
+    };
+    let date = column(&["date", "rate_date"])?;
+    let base = column(&["base", "base_currency"])?;
+    let quote = column(&["quote", "quote_currency"])?;
+    let rate = column(&["rate"])?;
+
+    records
+        .enumerate()
+        .map(|(index, record)| {
+            let line = index + 2;
+            let field = |column: usize| record.get(column).map(|field| field.trim()).unwrap_or("");
+
+            let parsed = NewExchangeRate {
+                base_currency: field(base).to_ascii_uppercase(),
+                quote_currency: field(quote).to_ascii_uppercase(),
+                rate: field(rate)
+                    .parse()
+                    .map_err(|_| format!("Line {}: invalid rate", line))?,
+                rate_date: NaiveDate::parse_from_str(field(date), "%Y-%m-%d")
+                    .map_err(|_| format!("Line {}: invalid date", line))?,
+            };
+            validate_rate(&parsed).map_err(|e| format!("Line {}: {}", line, e))?;
+
+            Ok(parsed)
+        })
+        .collect()
+}
+
+/// Reads the European Central Bank's daily or historical reference rate XML
+/// (`eurofxref-daily.xml`, `eurofxref-hist.xml`): a `<Cube time="...">` per
+/// day holding `<Cube currency="USD" rate="1.0876"/>` per currency, each the
+/// value of one euro.
+pub fn parse_ecb_xml(text: &str) -> Result<Vec<NewExchangeRate>, String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| format!("Invalid XML: {}", e))?;
+
+    let mut rates = Vec::new();
+    for cube in document
+        .descendants()
+        .filter(|node| node.has_tag_name("Cube"))
+    {
+        let (Some(currency), Some(rate)) = (cube.attribute("currency"), cube.attribute("rate"))
+        else {
+            continue;
+        };
+
+        let time = cube
+            .ancestors()
+            .find_map(|node| node.attribute("time"))
+            .ok_or("Rate listed outside a dated Cube")?;
+
+        let parsed = NewExchangeRate {
+            base_currency: ECB_BASE_CURRENCY.to_string(),
+            quote_currency: currency.to_string(),
+            rate: rate
+                .parse()
+                .map_err(|_| format!("Invalid rate {:?} for {}", rate, currency))?,
+            rate_date: NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid date {:?}", time))?,
+        };
+        validate_rate(&parsed)?;
+        rates.push(parsed);
+    }
+
+    if rates.is_empty() {
+        return Err("No exchange rates found in the XML file".to_string());
+    }
+
+    Ok(rates)
+}
+
+fn validate_rate(rate: &NewExchangeRate) -> Result<(), String> {
+    for currency in [&rate.base_currency, &rate.quote_currency] {
+        if validate_currency(currency).is_err() {
+            return Err(format!("Invalid currency code {:?}", currency));
+        }
+    }
+    if rate.base_currency == rate.quote_currency {
+        return Err("Base and quote currencies must differ".to_string());
+    }
+    if rate.rate <= Decimal::ZERO {
+        return Err("Rate must be positive".to_string());
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn home_currency(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<String, (StatusCode, Json<Value>)> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT home_currency FROM business_profiles WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map(|currency| currency.unwrap_or_else(|| DEFAULT_HOME_CURRENCY.to_string()))
+    .map_err(|e| {
+        tracing::error!("Failed to fetch home currency: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch home currency" })),
+        )
+    })
+}
+
+/// Currency of a new document for `client`: the one requested, else the
+/// client's, else the home currency.
+pub(crate) async fn document_currency(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    client: &Client,
+    requested: Option<String>,
+) -> Result<String, (StatusCode, Json<Value>)> {
+    match requested.or_else(|| client.currency.clone()) {
+        Some(currency) => Ok(currency),
+        None => home_currency(conn, user_id).await,
+    }
+}
+
+/// Value of one `from` in `to` on `date`, from the latest stored rate on or
+/// before it. Rates are used as stored, inverted, or crossed through a
+/// common base currency (e.g. ECB euro rates), in that order of preference.
+pub(crate) async fn find_rate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    from: &str,
+    to: &str,
+    date: NaiveDate,
+) -> Result<Option<Decimal>, (StatusCode, Json<Value>)> {
+    if from == to {
+        return Ok(Some(Decimal::ONE));
+    }
+
+    sqlx::query_scalar::<_, Decimal>(
+        r#"
+        SELECT rate FROM (
+            SELECT rate_date, 1 AS preference, rate
+            FROM exchange_rates
+            WHERE user_id = $1 AND base_currency = $2 AND quote_currency = $3
+                AND rate_date <= $4
+            UNION ALL
+            SELECT rate_date, 2, ROUND(1 / rate, 8)
+            FROM exchange_rates
+            WHERE user_id = $1 AND base_currency = $3 AND quote_currency = $2
+                AND rate_date <= $4
+            UNION ALL
+            SELECT f.rate_date, 3, ROUND(t.rate / f.rate, 8)
+            FROM exchange_rates f
+            JOIN exchange_rates t
+                ON t.user_id = f.user_id
+                AND t.base_currency = f.base_currency
+                AND t.rate_date = f.rate_date
+            WHERE f.user_id = $1 AND f.quote_currency = $2 AND t.quote_currency = $3
+                AND f.rate_date <= $4
+        ) rates
+        ORDER BY rate_date DESC, preference
+        LIMIT 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .bind(date)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up exchange rate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to look up exchange rate" })),
+        )
+    })
+}
+
+/// Exchange rate fixed on a document dated `date`: 1 in the home currency,
+/// otherwise the rate given with the document or the stored rate.
+pub(crate) async fn document_rate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    currency: &str,
+    given: Option<Decimal>,
+    date: NaiveDate,
+) -> Result<Decimal, (StatusCode, Json<Value>)> {
+    let home = home_currency(conn, user_id).await?;
+    if currency == home {
+        return Ok(Decimal::ONE);
+    }
+    if let Some(rate) = given {
+        return Ok(rate);
+    }
+
+    find_rate(conn, user_id, currency, &home, date)
+        .await?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!(
+                        "No exchange rate from {} to {} on or before {}; add one or set exchange_rate",
+                        currency, home, date
+                    )
+                })),
+            )
+        })
+}
+
+async fn save_rate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    rate: &NewExchangeRate,
+    source: RateSource,
+) -> Result<ExchangeRate, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, ExchangeRate>(
+        r#"
+        INSERT INTO exchange_rates (
+            user_id, base_currency, quote_currency, rate, rate_date, source
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, base_currency, quote_currency, rate_date)
+        DO UPDATE SET rate = EXCLUDED.rate, source = EXCLUDED.source, updated_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(&rate.base_currency)
+    .bind(&rate.quote_currency)
+    .bind(rate.rate)
+    .bind(rate.rate_date)
+    .bind(source)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save exchange rate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save exchange rate" })),
+        )
+    })
+}
+
+/// Upserts imported rates in one statement; a rate listed twice for the same
+/// pair and date keeps the later value. Returns the number of rates saved.
+async fn save_rates(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    rates: &[NewExchangeRate],
+    source: RateSource,
+) -> Result<usize, (StatusCode, Json<Value>)> {
+    let mut latest = HashMap::new();
+    for rate in rates {
+        latest.insert(
+            (&rate.base_currency, &rate.quote_currency, rate.rate_date),
+            rate.rate,
+        );
+    }
+
+    let mut base_currencies = Vec::with_capacity(latest.len());
+    let mut quote_currencies = Vec::with_capacity(latest.len());
+    let mut values = Vec::with_capacity(latest.len());
+    let mut rate_dates = Vec::with_capacity(latest.len());
+    for ((base_currency, quote_currency, rate_date), rate) in latest {
+        base_currencies.push(base_currency.as_str());
+        quote_currencies.push(quote_currency.as_str());
+        values.push(rate);
+        rate_dates.push(rate_date);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO exchange_rates (
+            user_id, base_currency, quote_currency, rate, rate_date, source
+        )
+        SELECT $1, base_currency, quote_currency, rate, rate_date, $6
+        FROM UNNEST($2::varchar[], $3::varchar[], $4::numeric[], $5::date[])
+            AS rates(base_currency, quote_currency, rate, rate_date)
+        ON CONFLICT (user_id, base_currency, quote_currency, rate_date)
+        DO UPDATE SET rate = EXCLUDED.rate, source = EXCLUDED.source, updated_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(&base_currencies)
+    .bind(&quote_currencies)
+    .bind(&values)
+    .bind(&rate_dates)
+    .bind(source)
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save exchange rates: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to import exchange rates" })),
+        )
+    })?;
+
+    Ok(base_currencies.len())
+}
+
+pub async fn list_exchange_rates(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<ExchangeRatesQuery>,
+) -> Result<Json<Vec<ExchangeRate>>, (StatusCode, Json<Value>)> {
+    let rates = sqlx::query_as::<_, ExchangeRate>(
+        r#"
+        SELECT * FROM exchange_rates
+        WHERE user_id = $1
+            AND ($2::varchar IS NULL OR $2 IN (base_currency, quote_currency))
+            AND ($3::date IS NULL OR rate_date >= $3)
+            AND ($4::date IS NULL OR rate_date <= $4)
+        ORDER BY rate_date DESC, base_currency, quote_currency
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.currency)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch exchange rates: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch exchange rates" })),
+        )
+    })?;
+
+    Ok(Json(rates))
+}
+
+/// Records a rate, replacing any rate for the same pair and day.
+pub async fn create_exchange_rate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateExchangeRateRequest>,
+) -> Result<(StatusCode, Json<ExchangeRate>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    if req.base_currency == req.quote_currency {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Base and quote currencies must differ" })),
+        ));
+    }
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save exchange rate" })),
+        )
+    })?;
+
+    let rate = save_rate(
+        &mut conn,
+        user_id,
+        &NewExchangeRate {
+            base_currency: req.base_currency,
+            quote_currency: req.quote_currency,
+            rate: req.rate,
+            rate_date: req.rate_date,
+        },
+        RateSource::Manual,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(rate)))
+}
+
+pub async fn delete_exchange_rate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM exchange_rates WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete exchange rate: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete exchange rate" })),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Exchange rate not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Imports rates from an uploaded CSV or ECB XML file in the "file" field.
+/// Existing rates for the same pair and day are replaced.
+pub async fn import_exchange_rates(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    mut multipart: Multipart,
+) -> Result<Json<ImportSummary>, (StatusCode, Json<Value>)> {
+    let multipart_error = |e: axum::extract::multipart::MultipartError| {
+        (e.status(), Json(json!({ "error": e.body_text() })))
+    };
+
+    let mut text = None;
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() == Some("file") {
+            text = Some(field.text().await.map_err(multipart_error)?);
+            break;
+        }
+    }
+
+    let text = text.ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Missing rates file in the \"file\" field" })),
+        )
+    })?;
+
+    let (source, parsed) = if text.trim_start().starts_with('<') {
+        (RateSource::Ecb, parse_ecb_xml(&text))
+    } else {
+        (RateSource::Csv, parse_rates_csv(&text))
+    };
+    let rates = parsed.map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to import exchange rates" })),
+        )
+    })?;
+
+    let imported = save_rates(&mut conn, user_id, &rates, source).await?;
+
+    Ok(Json(ImportSummary { source, imported }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_ecb_xml() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+	<gesmes:subject>Reference rates</gesmes:subject>
+	<!-- <Cube currency="GBP" rate="0.85"/> -->
+	<Cube>
+		<Cube time='2026-10-16' note="a > b">
+			<Cube currency='USD' rate='1.0876'/>
+			<Cube currency='JPY' rate='162.45'/>
+		</Cube>
+		<Cube time="2026-10-15">
+			<Cube currency="USD" rate="1.0901"/>
+		</Cube>
+	</Cube>
+</gesmes:Envelope>"#;
+
+        let rates = parse_ecb_xml(xml).unwrap();
+        assert_eq!(rates.len(), 3);
+        assert_eq!(
+            rates[1],
+            NewExchangeRate {
+                base_currency: "EUR".to_string(),
+                quote_currency: "JPY".to_string(),
+                rate: decimal("162.45"),
+                rate_date: NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+            }
+        );
+        assert_eq!(rates[2].rate, decimal("1.0901"));
+        assert!(parse_ecb_xml("<Cube></Cube>").is_err());
+        assert!(parse_ecb_xml("<Cube currency='USD' rate='1.1'").is_err());
+    }
+
+    #[test]
+    fn test_parse_rates_csv() {
+        let rates = parse_rates_csv("Rate,Date,Base,Quote\r\n1.37,2026-10-16,usd,CAD\r\n").unwrap();
+        assert_eq!(rates[0].base_currency, "USD");
+        assert_eq!(rates[0].rate, decimal("1.37"));
+
+        assert_eq!(
+            parse_rates_csv("date,base,quote,rate\n2026-10-16,USD,USD,1\n"),
+            Err("Line 2: Base and quote currencies must differ".to_string())
+        );
+        assert!(parse_rates_csv("date,base,rate\n").is_err());
+    }
+
+    #[test]
+    fn test_settled_parts_add_up_to_the_home_total() {
+        let (total, rate) = (decimal("100.00"), decimal("1.3333"));
+        let total_home = decimal("133.34");
+
+        let parts: Vec<Decimal> = [decimal("33.33"), decimal("33.33"), decimal("33.34")]
+            .iter()
+            .scan(Decimal::ZERO, |settled, amount| {
+                let part = settled_home(total, total_home, rate, *settled, *amount);
+                *settled += amount;
+                Some(part)
+            })
+            .collect();
+
+        assert_eq!(
+            parts,
+            vec![decimal("44.44"), decimal("44.44"), decimal("44.46")]
+        );
+        assert_eq!(parts.iter().sum::<Decimal>(), total_home);
+    }
+}
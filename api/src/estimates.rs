@@ -0,0 +1,1035 @@
+use axum::{
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::business;
+use crate::clients::{self, Client};
+use crate::currencies::{self, validate_currency};
+use crate::invoices::{
+    self, Invoice, InvoiceWithLineItems, LineItemRequest, LineItemTaxRow, NewLineItem,
+    compute_totals, line_amount, validate_positive,
+};
+use crate::numbering::{self, DocumentType};
+use crate::pdf;
+use crate::taxes::{self, AppliedTax, TaxRounding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EstimateStatus {
+    Draft,
+    Sent,
+    Accepted,
+    Declined,
+}
+
+impl EstimateStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EstimateStatus::Draft => "draft",
+            EstimateStatus::Sent => "sent",
+            EstimateStatus::Accepted => "accepted",
+            EstimateStatus::Declined => "declined",
+        }
+    }
+
+    /// Whether the lifecycle allows moving from this status to `next`.
+    ///
+    /// draft -> sent -> accepted or declined; a declined estimate can be resent.
+    /// A draft that is no longer needed can be declined directly, since only the
+    /// latest numbered draft can be deleted.
+    pub fn can_transition_to(&self, next: EstimateStatus) -> bool {
+        use EstimateStatus::*;
+
+        matches!(
+            (self, next),
+            (Draft, Sent)
+                | (Draft, Declined)
+                | (Sent, Accepted)
+                | (Sent, Declined)
+                | (Declined, Sent)
+        )
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Estimate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Uuid,
+    pub estimate_number: String,
+    pub status: EstimateStatus,
+    pub issue_date: NaiveDate,
+    pub expiry_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub tax_rounding: TaxRounding,
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub total: Decimal,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub currency: String,
+    /// Rate agreed with the client, used by the invoice it converts into.
+    pub exchange_rate: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EstimateLineItem {
+    pub id: Uuid,
+    pub estimate_id: Uuid,
+    pub position: i32,
+    pub description: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub amount: Decimal,
+    #[sqlx(skip)]
+    pub taxes: Vec<AppliedTax>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EstimateTax {
+    pub id: Uuid,
+    pub estimate_id: Uuid,
+    pub tax_rate_id: Uuid,
+    pub position: i32,
+    pub name: String,
+    pub rate: Decimal,
+    pub is_compound: bool,
+    pub taxable_amount: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EstimateWithLineItems {
+    #[serde(flatten)]
+    pub estimate: Estimate,
+    pub line_items: Vec<EstimateLineItem>,
+    pub taxes: Vec<EstimateTax>,
+    /// The invoice this estimate was converted into, if any.
+    pub invoice_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateEstimateRequest {
+    pub client_id: Uuid,
+    pub issue_date: Option<NaiveDate>,
+    pub expiry_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub tax_rounding: Option<TaxRounding>,
+    /// Defaults to the client's currency, then the home currency.
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    /// Fixes the exchange rate of the invoice the estimate converts into.
+    #[validate(custom(function = "validate_positive"))]
+    pub exchange_rate: Option<Decimal>,
+    #[validate(
+        length(min = 1, message = "At least one line item is required"),
+        nested
+    )]
+    pub line_items: Vec<LineItemRequest>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateEstimateRequest {
+    pub client_id: Option<Uuid>,
+    pub issue_date: Option<NaiveDate>,
+    pub expiry_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub tax_rounding: Option<TaxRounding>,
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub exchange_rate: Option<Decimal>,
+    #[validate(
+        length(min = 1, message = "At least one line item is required"),
+        nested
+    )]
+    pub line_items: Option<Vec<LineItemRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateEstimateStatusRequest {
+    pub status: EstimateStatus,
+}
+
+/// An estimate can no longer be accepted once its expiry date has passed.
+pub fn is_expired(expiry_date: Option<NaiveDate>, today: NaiveDate) -> bool {
+    expiry_date.is_some_and(|expiry_date| expiry_date < today)
+}
+
+async fn lock_estimate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Estimate, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Estimate>(
+        "SELECT * FROM estimates WHERE id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch estimate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch estimate" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Estimate not found" })),
+        )
+    })
+}
+
+async fn fetch_estimate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Estimate, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Estimate>("SELECT * FROM estimates WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch estimate: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch estimate" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Estimate not found" })),
+            )
+        })
+}
+
+async fn fetch_line_items(
+    conn: &mut PgConnection,
+    estimate_id: Uuid,
+) -> Result<Vec<EstimateLineItem>, (StatusCode, Json<Value>)> {
+    let mut line_items = sqlx::query_as::<_, EstimateLineItem>(
+        "SELECT * FROM estimate_line_items WHERE estimate_id = $1 ORDER BY position",
+    )
+    .bind(estimate_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch estimate line items: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch estimate line items" })),
+        )
+    })?;
+
+    let tax_rows = sqlx::query_as::<_, LineItemTaxRow>(
+        r#"
+        SELECT t.line_item_id, t.tax_rate_id, t.name, t.rate, t.is_compound
+        FROM estimate_line_item_taxes t
+        JOIN estimate_line_items l ON l.id = t.line_item_id
+        WHERE l.estimate_id = $1
+        ORDER BY t.position
+        "#,
+    )
+    .bind(estimate_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch estimate line item taxes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch estimate line items" })),
+        )
+    })?;
+
+    for row in tax_rows {
+        if let Some(line_item) = line_items
+            .iter_mut()
+            .find(|item| item.id == row.line_item_id)
+        {
+            line_item.taxes.push(row.tax);
+        }
+    }
+
+    Ok(line_items)
+}
+
+async fn fetch_estimate_taxes(
+    conn: &mut PgConnection,
+    estimate_id: Uuid,
+) -> Result<Vec<EstimateTax>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, EstimateTax>(
+        "SELECT * FROM estimate_taxes WHERE estimate_id = $1 ORDER BY position",
+    )
+    .bind(estimate_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch estimate taxes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch estimate taxes" })),
+        )
+    })
+}
+
+async fn fetch_estimate_details(
+    conn: &mut PgConnection,
+    estimate: Estimate,
+) -> Result<EstimateWithLineItems, (StatusCode, Json<Value>)> {
+    let line_items = fetch_line_items(conn, estimate.id).await?;
+    let taxes = fetch_estimate_taxes(conn, estimate.id).await?;
+    let invoice_id =
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM invoices WHERE estimate_id = $1")
+            .bind(estimate.id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch converted invoice: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch estimate" })),
+                )
+            })?;
+
+    Ok(EstimateWithLineItems {
+        estimate,
+        line_items,
+        taxes,
+        invoice_id,
+    })
+}
+
+/// Appends line items to an estimate, resolving each line's taxes.
+async fn add_line_items(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    estimate_id: Uuid,
+    client: &Client,
+    line_items: &[LineItemRequest],
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let requested_taxes: Vec<Option<Vec<Uuid>>> = line_items
+        .iter()
+        .map(|item| item.tax_rate_ids.clone())
+        .collect();
+    let line_taxes = taxes::resolve_line_taxes(conn, user_id, client, &requested_taxes).await?;
+
+    let next_position = sqlx::query_scalar::<_, i32>(
+        "SELECT COALESCE(MAX(position) + 1, 0) FROM estimate_line_items WHERE estimate_id = $1",
+    )
+    .bind(estimate_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch estimate line positions: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save estimate line items" })),
+        )
+    })?;
+
+    for (offset, (item, item_taxes)) in line_items.iter().zip(line_taxes).enumerate() {
+        let line_item_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO estimate_line_items (
+                estimate_id, position, description, quantity, unit_price, amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+        )
+        .bind(estimate_id)
+        .bind(next_position + offset as i32)
+        .bind(&item.description)
+        .bind(item.quantity)
+        .bind(item.unit_price)
+        .bind(line_amount(item.quantity, item.unit_price))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert estimate line item: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to save estimate line items" })),
+            )
+        })?;
+
+        for (position, tax) in item_taxes.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO estimate_line_item_taxes (
+                    line_item_id, tax_rate_id, position, name, rate, is_compound
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(line_item_id)
+            .bind(tax.tax_rate_id)
+            .bind(position as i32)
+            .bind(&tax.name)
+            .bind(tax.rate)
+            .bind(tax.is_compound)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to insert estimate line item tax: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to save estimate line items" })),
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Recomputes an estimate's tax summary and totals from its stored line items.
+async fn recalculate_totals(
+    conn: &mut PgConnection,
+    estimate_id: Uuid,
+) -> Result<Estimate, (StatusCode, Json<Value>)> {
+    let tax_rounding =
+        sqlx::query_scalar::<_, TaxRounding>("SELECT tax_rounding FROM estimates WHERE id = $1")
+            .bind(estimate_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch estimate: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to calculate estimate totals" })),
+                )
+            })?;
+
+    let lines: Vec<(Decimal, Vec<AppliedTax>)> = fetch_line_items(conn, estimate_id)
+        .await?
+        .into_iter()
+        .map(|item| (item.amount, item.taxes))
+        .collect();
+    let (totals, tax_summaries) = compute_totals(&lines, tax_rounding);
+
+    sqlx::query("DELETE FROM estimate_taxes WHERE estimate_id = $1")
+        .bind(estimate_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to clear estimate taxes: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to calculate estimate totals" })),
+            )
+        })?;
+
+    for (position, summary) in tax_summaries.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO estimate_taxes (
+                estimate_id, tax_rate_id, position, name, rate, is_compound,
+                taxable_amount, amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(estimate_id)
+        .bind(summary.tax.tax_rate_id)
+        .bind(position as i32)
+        .bind(&summary.tax.name)
+        .bind(summary.tax.rate)
+        .bind(summary.tax.is_compound)
+        .bind(summary.taxable_amount)
+        .bind(summary.amount)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert estimate tax: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to calculate estimate totals" })),
+            )
+        })?;
+    }
+
+    sqlx::query_as::<_, Estimate>(
+        r#"
+        UPDATE estimates
+        SET subtotal = $1, tax_total = $2, total = $3, updated_at = NOW()
+        WHERE id = $4
+        RETURNING *
+        "#,
+    )
+    .bind(totals.subtotal)
+    .bind(totals.tax_total)
+    .bind(totals.total)
+    .bind(estimate_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update estimate totals: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to calculate estimate totals" })),
+        )
+    })
+}
+
+pub async fn list_estimates(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<Estimate>>, (StatusCode, Json<Value>)> {
+    let estimates = sqlx::query_as::<_, Estimate>(
+        "SELECT * FROM estimates WHERE user_id = $1 ORDER BY issue_date DESC, created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch estimates: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch estimates" })),
+        )
+    })?;
+
+    Ok(Json(estimates))
+}
+
+pub async fn create_estimate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateEstimateRequest>,
+) -> Result<(StatusCode, Json<EstimateWithLineItems>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let issue_date = req
+        .issue_date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    if req
+        .expiry_date
+        .is_some_and(|expiry_date| expiry_date < issue_date)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Expiry date cannot be before the issue date" })),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create estimate" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut tx, user_id, req.client_id).await?;
+
+    let currency = currencies::document_currency(&mut tx, user_id, &client, req.currency).await?;
+    let estimate_number = numbering::next_number(&mut tx, user_id, DocumentType::Estimate).await?;
+
+    let estimate = sqlx::query_as::<_, Estimate>(
+        r#"
+        INSERT INTO estimates (
+            user_id, client_id, estimate_number, issue_date, expiry_date, notes, tax_rounding,
+            currency, exchange_rate
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.client_id)
+    .bind(&estimate_number)
+    .bind(issue_date)
+    .bind(req.expiry_date)
+    .bind(&req.notes)
+    .bind(req.tax_rounding.unwrap_or_default())
+    .bind(currency)
+    .bind(req.exchange_rate)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create estimate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create estimate" })),
+        )
+    })?;
+
+    add_line_items(&mut tx, user_id, estimate.id, &client, &req.line_items).await?;
+    let estimate = recalculate_totals(&mut tx, estimate.id).await?;
+    let details = fetch_estimate_details(&mut tx, estimate).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit estimate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create estimate" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(details)))
+}
+
+pub async fn get_estimate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<EstimateWithLineItems>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch estimate" })),
+        )
+    })?;
+
+    let estimate = fetch_estimate(&mut conn, user_id, id).await?;
+    let details = fetch_estimate_details(&mut conn, estimate).await?;
+
+    Ok(Json(details))
+}
+
+pub async fn update_estimate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateEstimateRequest>,
+) -> Result<Json<EstimateWithLineItems>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update estimate" })),
+        )
+    })?;
+
+    let existing = lock_estimate(&mut tx, user_id, id).await?;
+
+    if existing.status != EstimateStatus::Draft {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Only draft estimates can be edited" })),
+        ));
+    }
+
+    let client_id = req.client_id.unwrap_or(existing.client_id);
+    let client = clients::fetch_owned_client(&mut tx, user_id, client_id).await?;
+
+    let issue_date = req.issue_date.unwrap_or(existing.issue_date);
+    let expiry_date = req.expiry_date.or(existing.expiry_date);
+    if expiry_date.is_some_and(|expiry_date| expiry_date < issue_date) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Expiry date cannot be before the issue date" })),
+        ));
+    }
+
+    // A rate given for the old currency does not carry over to a new one
+    let exchange_rate = req
+        .exchange_rate
+        .or(existing.exchange_rate.filter(|_| req.currency.is_none()));
+    let currency = req.currency.unwrap_or(existing.currency);
+
+    if let Some(items) = &req.line_items {
+        sqlx::query("DELETE FROM estimate_line_items WHERE estimate_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to delete estimate line items: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to update estimate" })),
+                )
+            })?;
+
+        add_line_items(&mut tx, user_id, id, &client, items).await?;
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE estimates
+        SET
+            client_id = $1,
+            issue_date = $2,
+            expiry_date = $3,
+            notes = COALESCE($4, notes),
+            tax_rounding = COALESCE($5, tax_rounding),
+            currency = $6,
+            exchange_rate = $7,
+            updated_at = NOW()
+        WHERE id = $8 AND user_id = $9
+        "#,
+    )
+    .bind(client_id)
+    .bind(issue_date)
+    .bind(expiry_date)
+    .bind(req.notes)
+    .bind(req.tax_rounding)
+    .bind(&currency)
+    .bind(exchange_rate)
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update estimate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update estimate" })),
+        )
+    })?;
+
+    let estimate = recalculate_totals(&mut tx, id).await?;
+    let details = fetch_estimate_details(&mut tx, estimate).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit estimate update: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update estimate" })),
+        )
+    })?;
+
+    Ok(Json(details))
+}
+
+pub async fn update_estimate_status(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateEstimateStatusRequest>,
+) -> Result<Json<Estimate>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update estimate status" })),
+        )
+    })?;
+
+    let existing = lock_estimate(&mut tx, user_id, id).await?;
+
+    if !existing.status.can_transition_to(req.status) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!(
+                    "Cannot change estimate status from {} to {}",
+                    existing.status.as_str(),
+                    req.status.as_str()
+                )
+            })),
+        ));
+    }
+
+    if req.status == EstimateStatus::Accepted
+        && is_expired(existing.expiry_date, chrono::Utc::now().date_naive())
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Expired estimates cannot be accepted" })),
+        ));
+    }
+
+    let estimate = sqlx::query_as::<_, Estimate>(
+        r#"
+        UPDATE estimates
+        SET status = $1, updated_at = NOW()
+        WHERE id = $2 AND user_id = $3
+        RETURNING *
+        "#,
+    )
+    .bind(req.status)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update estimate status: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update estimate status" })),
+        )
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit estimate status: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update estimate status" })),
+        )
+    })?;
+
+    Ok(Json(estimate))
+}
+
+pub async fn delete_estimate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete estimate" })),
+        )
+    })?;
+
+    let existing = lock_estimate(&mut tx, user_id, id).await?;
+
+    if existing.status != EstimateStatus::Draft {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Only draft estimates can be deleted" })),
+        ));
+    }
+
+    // Deleting anything but the latest numbered draft would leave a gap in the sequence
+    if !numbering::release_number(
+        &mut tx,
+        user_id,
+        DocumentType::Estimate,
+        &existing.estimate_number,
+    )
+    .await?
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Only the most recently numbered draft can be deleted; decline this estimate instead"
+            })),
+        ));
+    }
+
+    sqlx::query("DELETE FROM estimates WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete estimate: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete estimate" })),
+            )
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit estimate deletion: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete estimate" })),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Converts an accepted estimate into a draft invoice with the same lines and
+/// taxes. The invoice keeps a link back to the estimate.
+pub async fn convert_estimate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<InvoiceWithLineItems>), (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to convert estimate" })),
+        )
+    })?;
+
+    let estimate = lock_estimate(&mut tx, user_id, id).await?;
+
+    if estimate.status != EstimateStatus::Accepted {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Only accepted estimates can be converted to invoices" })),
+        ));
+    }
+
+    let already_converted = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM invoices WHERE estimate_id = $1)",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to check converted invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to convert estimate" })),
+        )
+    })?;
+
+    if already_converted {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Estimate has already been converted to an invoice" })),
+        ));
+    }
+
+    clients::fetch_owned_client(&mut tx, user_id, estimate.client_id).await?;
+    let invoice_number = numbering::next_number(&mut tx, user_id, DocumentType::Invoice).await?;
+
+    // The invoice is in the currency and at the rate the client accepted
+    let invoice = sqlx::query_as::<_, Invoice>(
+        r#"
+        INSERT INTO invoices (
+            user_id, client_id, invoice_number, notes, tax_rounding, estimate_id, currency,
+            exchange_rate
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(estimate.client_id)
+    .bind(&invoice_number)
+    .bind(&estimate.notes)
+    .bind(estimate.tax_rounding)
+    .bind(estimate.id)
+    .bind(&estimate.currency)
+    .bind(estimate.exchange_rate)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create invoice from estimate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to convert estimate" })),
+        )
+    })?;
+
+    // Taxes are copied as quoted, even if a rate has since been archived
+    let line_items: Vec<NewLineItem> = fetch_line_items(&mut tx, estimate.id)
+        .await?
+        .into_iter()
+        .map(|item| NewLineItem {
+            description: item.description,
+            quantity: item.quantity,
+            unit_price: item.unit_price,
+            taxes: item.taxes,
+        })
+        .collect();
+
+    invoices::insert_line_items(&mut tx, invoice.id, &line_items).await?;
+    let invoice = invoices::recalculate_totals(&mut tx, invoice.id).await?;
+    let details = invoices::fetch_invoice_details(&mut tx, invoice).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit estimate conversion: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to convert estimate" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(details)))
+}
+
+pub async fn get_estimate_pdf(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to render estimate" })),
+        )
+    })?;
+
+    let estimate = fetch_estimate(&mut conn, user_id, id).await?;
+    let line_items = fetch_line_items(&mut conn, estimate.id).await?;
+    let taxes = fetch_estimate_taxes(&mut conn, estimate.id).await?;
+    let client = clients::fetch_owned_client(&mut conn, user_id, estimate.client_id).await?;
+    let business = business::fetch_business_profile(&mut conn, user_id).await?;
+
+    let document = pdf::DocumentModel::for_estimate(
+        &estimate,
+        &line_items,
+        &taxes,
+        &client,
+        business.as_ref(),
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}.pdf\"", estimate.estimate_number),
+            ),
+        ],
+        pdf::render(&document),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_transitions() {
+        use EstimateStatus::*;
+
+        assert!(Draft.can_transition_to(Sent));
+        assert!(Sent.can_transition_to(Accepted));
+        assert!(Sent.can_transition_to(Declined));
+        assert!(Declined.can_transition_to(Sent));
+        assert!(!Draft.can_transition_to(Accepted));
+        assert!(!Accepted.can_transition_to(Declined));
+        assert!(!Accepted.can_transition_to(Draft));
+    }
+
+    #[test]
+    fn test_unwanted_draft_can_be_declined() {
+        use EstimateStatus::*;
+
+        // The way out for drafts that cannot be deleted without a numbering gap
+        assert!(Draft.can_transition_to(Declined));
+        assert!(!Declined.can_transition_to(Draft));
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+
+        assert!(!is_expired(None, today));
+        assert!(!is_expired(Some(today), today));
+        assert!(is_expired(today.pred_opt(), today));
+    }
+}
@@ -0,0 +1,1149 @@
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::clients;
+use crate::currencies::{self, validate_currency};
+use crate::invoices::{
+    self, InvoiceWithLineItems, NewLineItem, round_money, validate_non_negative, validate_positive,
+};
+use crate::ledger::{self, SourceType};
+use crate::middleware::AccessToken;
+use crate::projects;
+use crate::supabase::SupabaseClient;
+use crate::supabase::storage::UploadOptions;
+use crate::taxes::{self, TaxRate};
+
+/// Storage bucket holding receipt files, keyed `{user_id}/{expense_id}/{filename}`.
+const RECEIPTS_BUCKET: &str = "receipts";
+
+/// Lifetime of signed receipt download URLs, in seconds.
+const RECEIPT_URL_EXPIRES_IN: u32 = 3600;
+
+/// Largest receipt accepted, matching the bucket's file size limit.
+pub const MAX_RECEIPT_SIZE: usize = 10 * 1024 * 1024;
+
+const RECEIPT_CONTENT_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+    "image/heic",
+    "application/pdf",
+];
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Expense {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Option<Uuid>,
+    pub vendor: String,
+    pub category: String,
+    pub description: Option<String>,
+    pub expense_date: NaiveDate,
+    pub amount: Decimal,
+    pub tax_rate_id: Option<Uuid>,
+    pub tax_amount: Decimal,
+    pub total: Decimal,
+    pub receipt_path: Option<String>,
+    pub receipt_filename: Option<String>,
+    pub receipt_content_type: Option<String>,
+    pub notes: Option<String>,
+    pub billable: bool,
+    /// Invoice line the expense was rebilled on; `None` while unbilled.
+    pub invoice_line_item_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    pub currency: String,
+    /// Home currency value of one unit of `currency` on the expense date.
+    pub exchange_rate: Decimal,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReceiptResponse {
+    pub path: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    /// Signed download URL, valid for `expires_in` seconds.
+    pub url: String,
+    pub expires_in: u32,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateExpenseRequest {
+    /// Client the cost was incurred for, so it can be rebilled later.
+    pub client_id: Option<Uuid>,
+    /// Project the cost belongs to; sets the client when `client_id` is omitted.
+    pub project_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 255))]
+    pub vendor: String,
+    #[validate(length(min = 1, max = 100))]
+    pub category: String,
+    pub description: Option<String>,
+    pub expense_date: Option<NaiveDate>,
+    /// Net amount, before tax.
+    #[validate(custom(function = "validate_positive"))]
+    pub amount: Decimal,
+    pub tax_rate_id: Option<Uuid>,
+    /// Tax as printed on the receipt; computed from `tax_rate_id` when omitted.
+    #[validate(custom(function = "validate_non_negative"))]
+    pub tax_amount: Option<Decimal>,
+    pub notes: Option<String>,
+    /// Rebill the expense to its client; requires `client_id`.
+    #[serde(default)]
+    pub billable: bool,
+    /// Currency paid in; defaults to the home currency.
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    /// Fixes the exchange rate instead of looking it up on the expense date.
+    #[validate(custom(function = "validate_positive"))]
+    pub exchange_rate: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateExpenseRequest {
+    pub client_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 255))]
+    pub vendor: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub expense_date: Option<NaiveDate>,
+    #[validate(custom(function = "validate_positive"))]
+    pub amount: Option<Decimal>,
+    pub tax_rate_id: Option<Uuid>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub tax_amount: Option<Decimal>,
+    pub notes: Option<String>,
+    pub billable: Option<bool>,
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub exchange_rate: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnbilledExpensesQuery {
+    pub client_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RebillExpensesRequest {
+    pub client_id: Uuid,
+    #[validate(length(min = 1, message = "At least one expense is required"))]
+    pub expense_ids: Vec<Uuid>,
+    /// Draft invoice to add the expenses to; a new draft is created when omitted.
+    pub invoice_id: Option<Uuid>,
+    /// Percentage added on top of each expense's net amount.
+    #[validate(custom(function = "validate_non_negative"))]
+    pub markup_percent: Option<Decimal>,
+}
+
+/// Tax on an expense: the amount entered from the receipt if any, otherwise
+/// the tax rate applied to the net amount, otherwise nothing.
+pub fn expense_tax(amount: Decimal, rate: Option<Decimal>, entered: Option<Decimal>) -> Decimal {
+    match (entered, rate) {
+        (Some(entered), _) => entered,
+        (None, Some(rate)) => round_money(amount * rate / Decimal::ONE_HUNDRED),
+        (None, None) => Decimal::ZERO,
+    }
+}
+
+/// Reduces an uploaded file name to characters that are safe in a storage path.
+pub fn sanitize_filename(filename: &str) -> String {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(100)
+        .collect();
+    let sanitized = sanitized.trim_start_matches('.');
+
+    if sanitized.is_empty() {
+        "receipt".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// Price an expense is rebilled at: its net amount plus the markup percentage.
+pub fn rebill_price(amount: Decimal, markup_percent: Option<Decimal>) -> Decimal {
+    let markup = markup_percent.unwrap_or(Decimal::ZERO);
+    round_money(amount * (Decimal::ONE_HUNDRED + markup) / Decimal::ONE_HUNDRED)
+}
+
+fn rebill_description(expense: &Expense) -> String {
+    let detail = expense.description.as_deref().unwrap_or(&expense.category);
+    format!(
+        "{} {}: {}",
+        expense.expense_date.format("%Y-%m-%d"),
+        expense.vendor,
+        detail
+    )
+}
+
+fn receipt_path(user_id: Uuid, expense_id: Uuid, filename: &str) -> String {
+    format!("{}/{}/{}", user_id, expense_id, sanitize_filename(filename))
+}
+
+fn storage_client(access_token: &AccessToken) -> Result<SupabaseClient, (StatusCode, Json<Value>)> {
+    SupabaseClient::new()
+        .map(|client| client.with_access_token(&access_token.0))
+        .map_err(|e| {
+            tracing::error!("Failed to create Supabase client: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Server configuration error" })),
+            )
+        })
+}
+
+async fn fetch_expense(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Expense, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Expense>("SELECT * FROM expenses WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch expense: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch expense" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Expense not found" })),
+            )
+        })
+}
+
+/// Loads the rate of a tax rate owned by `user_id` that is still in use.
+pub(crate) async fn fetch_tax_rate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    tax_rate_id: Uuid,
+) -> Result<Decimal, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, TaxRate>(
+        "SELECT * FROM tax_rates WHERE id = $1 AND user_id = $2 AND NOT is_archived",
+    )
+    .bind(tax_rate_id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch tax rate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch tax rate" })),
+        )
+    })?
+    .map(|tax_rate| tax_rate.rate)
+    .ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Tax rate {} not found", tax_rate_id) })),
+        )
+    })
+}
+
+/// Posts an expense's cost and recoverable tax to the ledger.
+async fn post_expense(
+    conn: &mut PgConnection,
+    expense: &Expense,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let account = ledger::expense_account(conn, expense.user_id, &expense.category).await?;
+
+    ledger::post(
+        conn,
+        expense.user_id,
+        (SourceType::Expense, expense.id),
+        expense.expense_date,
+        &format!("Expense: {}", expense.vendor),
+        &ledger::expense_postings(
+            account,
+            currencies::to_home(expense.amount, expense.exchange_rate),
+            currencies::to_home(expense.tax_amount, expense.exchange_rate),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn list_expenses(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<Expense>>, (StatusCode, Json<Value>)> {
+    let expenses = sqlx::query_as::<_, Expense>(
+        "SELECT * FROM expenses WHERE user_id = $1 ORDER BY expense_date DESC, created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch expenses: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch expenses" })),
+        )
+    })?;
+
+    Ok(Json(expenses))
+}
+
+/// Records an expense and posts it to the ledger. `req` must be validated.
+pub(crate) async fn insert_expense(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    req: CreateExpenseRequest,
+) -> Result<Expense, (StatusCode, Json<Value>)> {
+    let client_id =
+        projects::project_client(&mut *conn, user_id, req.project_id, req.client_id).await?;
+    if req.billable && client_id.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Billable expenses need a client" })),
+        ));
+    }
+
+    if let Some(client_id) = client_id {
+        clients::fetch_owned_client(&mut *conn, user_id, client_id).await?;
+    }
+
+    let rate = match req.tax_rate_id {
+        Some(tax_rate_id) => Some(fetch_tax_rate(&mut *conn, user_id, tax_rate_id).await?),
+        None => None,
+    };
+    let tax_amount = expense_tax(req.amount, rate, req.tax_amount);
+
+    let expense_date = req
+        .expense_date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let currency = match req.currency {
+        Some(currency) => currency,
+        None => currencies::home_currency(&mut *conn, user_id).await?,
+    };
+    let exchange_rate = currencies::document_rate(
+        &mut *conn,
+        user_id,
+        &currency,
+        req.exchange_rate,
+        expense_date,
+    )
+    .await?;
+
+    let expense = sqlx::query_as::<_, Expense>(
+        r#"
+        INSERT INTO expenses (
+            user_id, client_id, vendor, category, description, expense_date,
+            amount, tax_rate_id, tax_amount, total, notes, billable, project_id,
+            currency, exchange_rate
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(req.vendor)
+    .bind(req.category)
+    .bind(req.description)
+    .bind(expense_date)
+    .bind(req.amount)
+    .bind(req.tax_rate_id)
+    .bind(tax_amount)
+    .bind(req.amount + tax_amount)
+    .bind(req.notes)
+    .bind(req.billable)
+    .bind(req.project_id)
+    .bind(currency)
+    .bind(exchange_rate)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create expense: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create expense" })),
+        )
+    })?;
+
+    post_expense(conn, &expense).await?;
+
+    Ok(expense)
+}
+
+pub async fn create_expense(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateExpenseRequest>,
+) -> Result<(StatusCode, Json<Expense>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create expense" })),
+        )
+    })?;
+
+    let expense = insert_expense(&mut tx, user_id, req).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create expense" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(expense)))
+}
+
+pub async fn get_expense(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Expense>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch expense" })),
+        )
+    })?;
+
+    Ok(Json(fetch_expense(&mut conn, user_id, id).await?))
+}
+
+pub async fn update_expense(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateExpenseRequest>,
+) -> Result<Json<Expense>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update expense" })),
+        )
+    })?;
+
+    let existing = sqlx::query_as::<_, Expense>(
+        "SELECT * FROM expenses WHERE id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch expense: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update expense" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Expense not found" })),
+        )
+    })?;
+
+    // Once on an invoice, what was billed is fixed until the line is removed
+    if existing.invoice_line_item_id.is_some()
+        && (req
+            .client_id
+            .is_some_and(|client_id| Some(client_id) != existing.client_id)
+            || req.amount.is_some_and(|amount| amount != existing.amount)
+            || req
+                .currency
+                .as_ref()
+                .is_some_and(|currency| *currency != existing.currency)
+            || req.billable == Some(false))
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Expense has been billed; remove it from the invoice before changing its client, amount, currency or billable flag"
+            })),
+        ));
+    }
+
+    let project_id = req.project_id.or(existing.project_id);
+    let client_id = projects::project_client(
+        &mut tx,
+        user_id,
+        project_id,
+        req.client_id.or(existing.client_id),
+    )
+    .await?;
+    let billable = req.billable.unwrap_or(existing.billable);
+    if billable && client_id.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Billable expenses need a client" })),
+        ));
+    }
+
+    if let Some(client_id) = req.client_id {
+        clients::fetch_owned_client(&mut tx, user_id, client_id).await?;
+    }
+
+    let amount = req.amount.unwrap_or(existing.amount);
+    let tax_rate_id = req.tax_rate_id.or(existing.tax_rate_id);
+
+    // Tax is only recomputed from the rate when the amount or rate changes, so
+    // a tax amount entered from the receipt survives unrelated edits
+    let tax_amount =
+        if req.tax_amount.is_some() || req.amount.is_some() || req.tax_rate_id.is_some() {
+            let rate = match tax_rate_id {
+                Some(tax_rate_id) => Some(fetch_tax_rate(&mut tx, user_id, tax_rate_id).await?),
+                None => None,
+            };
+            expense_tax(
+                amount,
+                rate,
+                req.tax_amount
+                    .or(rate.is_none().then_some(existing.tax_amount)),
+            )
+        } else {
+            existing.tax_amount
+        };
+
+    // The rate is looked up again when the currency or date changes, unless given
+    let expense_date = req.expense_date.unwrap_or(existing.expense_date);
+    let currency = req.currency.unwrap_or_else(|| existing.currency.clone());
+    let exchange_rate = match req.exchange_rate {
+        None if currency == existing.currency && expense_date == existing.expense_date => {
+            existing.exchange_rate
+        }
+        given => {
+            currencies::document_rate(&mut tx, user_id, &currency, given, expense_date).await?
+        }
+    };
+
+    let expense = sqlx::query_as::<_, Expense>(
+        r#"
+        UPDATE expenses
+        SET
+            client_id = $1,
+            vendor = COALESCE($2, vendor),
+            category = COALESCE($3, category),
+            description = COALESCE($4, description),
+            expense_date = $5,
+            amount = $6,
+            tax_rate_id = $7,
+            tax_amount = $8,
+            total = $9,
+            notes = COALESCE($10, notes),
+            billable = $11,
+            project_id = $12,
+            currency = $13,
+            exchange_rate = $14,
+            updated_at = NOW()
+        WHERE id = $15 AND user_id = $16
+        RETURNING *
+        "#,
+    )
+    .bind(client_id)
+    .bind(req.vendor)
+    .bind(req.category)
+    .bind(req.description)
+    .bind(expense_date)
+    .bind(amount)
+    .bind(tax_rate_id)
+    .bind(tax_amount)
+    .bind(amount + tax_amount)
+    .bind(req.notes)
+    .bind(billable)
+    .bind(project_id)
+    .bind(&currency)
+    .bind(exchange_rate)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update expense: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update expense" })),
+        )
+    })?;
+
+    // Posted entries are never edited: a changed cost is reversed and reposted
+    if expense.amount != existing.amount
+        || expense.tax_amount != existing.tax_amount
+        || expense.expense_date != existing.expense_date
+        || expense.category != existing.category
+        || expense.exchange_rate != existing.exchange_rate
+    {
+        ledger::reverse_source(&mut tx, user_id, (SourceType::Expense, expense.id)).await?;
+        post_expense(&mut tx, &expense).await?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update expense" })),
+        )
+    })?;
+
+    Ok(Json(expense))
+}
+
+pub async fn delete_expense(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    access_token: AccessToken,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete expense" })),
+        )
+    })?;
+
+    let expense = fetch_expense(&mut tx, user_id, id).await?;
+    let receipt_path = expense.receipt_path;
+
+    let result = sqlx::query(
+        "DELETE FROM expenses WHERE id = $1 AND user_id = $2 AND invoice_line_item_id IS NULL",
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete expense: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete expense" })),
+        )
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Billed expenses cannot be deleted" })),
+        ));
+    }
+
+    ledger::reverse_source(&mut tx, user_id, (SourceType::Expense, id)).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete expense" })),
+        )
+    })?;
+
+    // The expense is gone either way; a leftover file is only logged
+    if let Some(path) = receipt_path {
+        let storage = storage_client(&access_token)?;
+        if let Err(e) = storage.delete(RECEIPTS_BUCKET, vec![path]).await {
+            tracing::warn!("Failed to delete receipt of expense {}: {}", id, e);
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_unbilled_expenses(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<UnbilledExpensesQuery>,
+) -> Result<Json<Vec<Expense>>, (StatusCode, Json<Value>)> {
+    let expenses = sqlx::query_as::<_, Expense>(
+        r#"
+        SELECT * FROM expenses
+        WHERE user_id = $1
+            AND billable
+            AND invoice_line_item_id IS NULL
+            AND ($2::UUID IS NULL OR client_id = $2)
+        ORDER BY expense_date, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.client_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch unbilled expenses: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch expenses" })),
+        )
+    })?;
+
+    Ok(Json(expenses))
+}
+
+/// Adds unbilled expenses to a new or existing draft invoice, one line each.
+pub async fn rebill_expenses(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<RebillExpensesRequest>,
+) -> Result<Json<InvoiceWithLineItems>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to bill expenses" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut tx, user_id, req.client_id).await?;
+
+    // Locked so concurrent requests cannot bill the same expense twice
+    let expenses = sqlx::query_as::<_, Expense>(
+        r#"
+        SELECT * FROM expenses
+        WHERE user_id = $1 AND id = ANY($2)
+        ORDER BY id
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .bind(&req.expense_ids)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch expenses: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to bill expenses" })),
+        )
+    })?;
+
+    let mut ordered: Vec<&Expense> = Vec::with_capacity(expenses.len());
+    for id in &req.expense_ids {
+        if ordered.iter().any(|expense| expense.id == *id) {
+            continue;
+        }
+        let expense = expenses
+            .iter()
+            .find(|expense| expense.id == *id)
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("Expense {} not found", id) })),
+                )
+            })?;
+        if !expense.billable || expense.client_id != Some(client.id) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("Expense {} is not billable to this client", id)
+                })),
+            ));
+        }
+        if expense.invoice_line_item_id.is_some() {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({ "error": format!("Expense {} has already been billed", id) })),
+            ));
+        }
+        ordered.push(expense);
+    }
+    ordered.sort_by_key(|expense| (expense.expense_date, expense.created_at));
+
+    let project_id = projects::common_project(ordered.iter().map(|expense| expense.project_id));
+    let invoice =
+        invoices::open_billing_invoice(&mut tx, user_id, client.id, project_id, req.invoice_id)
+            .await?;
+
+    // Costs are rebilled at face value, so they must be in the invoice's currency
+    if let Some(expense) = ordered
+        .iter()
+        .find(|expense| expense.currency != invoice.currency)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!(
+                    "Expense {} is in {} but the invoice is in {}",
+                    expense.id, expense.currency, invoice.currency
+                )
+            })),
+        ));
+    }
+
+    // Rebilled costs take the client's regional taxes, like any other charge
+    let line_taxes = taxes::resolve_line_taxes(&mut tx, user_id, &client, &[None]).await?;
+    let default_taxes = line_taxes.into_iter().next().unwrap_or_default();
+
+    let new_items: Vec<NewLineItem> = ordered
+        .iter()
+        .map(|expense| NewLineItem {
+            description: rebill_description(expense),
+            quantity: Decimal::ONE,
+            unit_price: rebill_price(expense.amount, req.markup_percent),
+            taxes: default_taxes.clone(),
+        })
+        .collect();
+    let line_items = invoices::insert_line_items(&mut tx, invoice.id, &new_items).await?;
+
+    for (expense, line_item) in ordered.iter().zip(&line_items) {
+        sqlx::query(
+            "UPDATE expenses SET invoice_line_item_id = $1, updated_at = NOW() WHERE id = $2",
+        )
+        .bind(line_item.id)
+        .bind(expense.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark expense as billed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to bill expenses" })),
+            )
+        })?;
+    }
+
+    let invoice = invoices::recalculate_totals(&mut tx, invoice.id).await?;
+    let details = invoices::fetch_invoice_details(&mut tx, invoice).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit expense billing: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to bill expenses" })),
+        )
+    })?;
+
+    Ok(Json(details))
+}
+
+pub async fn upload_receipt(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    access_token: AccessToken,
+    Path(id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<ReceiptResponse>), (StatusCode, Json<Value>)> {
+    let multipart_error = |e: axum::extract::multipart::MultipartError| {
+        (e.status(), Json(json!({ "error": e.body_text() })))
+    };
+
+    let mut upload = None;
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let filename = field.file_name().unwrap_or("receipt").to_string();
+        let content_type = field.content_type().unwrap_or_default().to_string();
+        let bytes = field.bytes().await.map_err(multipart_error)?;
+        upload = Some((filename, content_type, bytes));
+        break;
+    }
+
+    let (filename, content_type, bytes) = upload.ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Missing receipt file in the \"file\" field" })),
+        )
+    })?;
+
+    if !RECEIPT_CONTENT_TYPES.contains(&content_type.as_str()) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Json(json!({
+                "error": "Receipts must be JPEG, PNG, WebP or HEIC images or PDF files"
+            })),
+        ));
+    }
+    if bytes.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Receipt file is empty" })),
+        ));
+    }
+    if bytes.len() > MAX_RECEIPT_SIZE {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({ "error": "Receipt file is larger than 10 MB" })),
+        ));
+    }
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to upload receipt" })),
+        )
+    })?;
+
+    let expense = fetch_expense(&mut conn, user_id, id).await?;
+    let path = receipt_path(user_id, expense.id, &filename);
+
+    let storage = storage_client(&access_token)?;
+    storage
+        .upload(
+            RECEIPTS_BUCKET,
+            &path,
+            bytes.to_vec(),
+            Some(UploadOptions {
+                content_type: content_type.clone(),
+                upsert: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to upload receipt: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({ "error": "Failed to upload receipt" })),
+            )
+        })?;
+
+    sqlx::query(
+        r#"
+        UPDATE expenses
+        SET receipt_path = $1, receipt_filename = $2, receipt_content_type = $3, updated_at = NOW()
+        WHERE id = $4 AND user_id = $5
+        "#,
+    )
+    .bind(&path)
+    .bind(&filename)
+    .bind(&content_type)
+    .bind(expense.id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to save receipt: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to upload receipt" })),
+        )
+    })?;
+
+    // A receipt uploaded under another name replaces the previous file
+    if let Some(previous) = expense.receipt_path.filter(|previous| *previous != path)
+        && let Err(e) = storage.delete(RECEIPTS_BUCKET, vec![previous]).await
+    {
+        tracing::warn!("Failed to delete previous receipt of expense {}: {}", id, e);
+    }
+
+    let url = storage
+        .create_signed_url(RECEIPTS_BUCKET, &path, RECEIPT_URL_EXPIRES_IN)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to sign receipt URL: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({ "error": "Failed to create receipt download URL" })),
+            )
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ReceiptResponse {
+            path,
+            filename: Some(filename),
+            content_type: Some(content_type),
+            url,
+            expires_in: RECEIPT_URL_EXPIRES_IN,
+        }),
+    ))
+}
+
+pub async fn get_receipt(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    access_token: AccessToken,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ReceiptResponse>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch receipt" })),
+        )
+    })?;
+
+    let expense = fetch_expense(&mut conn, user_id, id).await?;
+    let path = expense.receipt_path.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Expense has no receipt" })),
+        )
+    })?;
+
+    let url = storage_client(&access_token)?
+        .create_signed_url(RECEIPTS_BUCKET, &path, RECEIPT_URL_EXPIRES_IN)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to sign receipt URL: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({ "error": "Failed to create receipt download URL" })),
+            )
+        })?;
+
+    Ok(Json(ReceiptResponse {
+        path,
+        filename: expense.receipt_filename,
+        content_type: expense.receipt_content_type,
+        url,
+        expires_in: RECEIPT_URL_EXPIRES_IN,
+    }))
+}
+
+pub async fn delete_receipt(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    access_token: AccessToken,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete receipt" })),
+        )
+    })?;
+
+    let expense = fetch_expense(&mut conn, user_id, id).await?;
+    let path = expense.receipt_path.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Expense has no receipt" })),
+        )
+    })?;
+
+    storage_client(&access_token)?
+        .delete(RECEIPTS_BUCKET, vec![path])
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete receipt: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({ "error": "Failed to delete receipt" })),
+            )
+        })?;
+
+    sqlx::query(
+        r#"
+        UPDATE expenses
+        SET receipt_path = NULL, receipt_filename = NULL, receipt_content_type = NULL,
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to clear receipt: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete receipt" })),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expense_tax() {
+        let amount = Decimal::new(10000, 2);
+
+        assert_eq!(expense_tax(amount, None, None), Decimal::ZERO);
+        assert_eq!(
+            expense_tax(amount, Some(Decimal::new(13, 0)), None),
+            Decimal::new(1300, 2)
+        );
+        // The receipt's own figure wins over the computed one
+        assert_eq!(
+            expense_tax(
+                amount,
+                Some(Decimal::new(13, 0)),
+                Some(Decimal::new(1299, 2))
+            ),
+            Decimal::new(1299, 2)
+        );
+    }
+
+    #[test]
+    fn test_rebill_price() {
+        let amount = Decimal::new(4999, 2);
+
+        assert_eq!(rebill_price(amount, None), amount);
+        assert_eq!(rebill_price(amount, Some(Decimal::ZERO)), amount);
+        // 49.99 * 1.15 = 57.4885
+        assert_eq!(
+            rebill_price(amount, Some(Decimal::new(15, 0))),
+            Decimal::new(5749, 2)
+        );
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("receipt.pdf"), "receipt.pdf");
+        assert_eq!(
+            sanitize_filename("Lunch at Café #2.jpg"),
+            "Lunch_at_Caf___2.jpg"
+        );
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("C:\\Users\\me\\scan.png"), "scan.png");
+        assert_eq!(sanitize_filename(".."), "receipt");
+        assert_eq!(sanitize_filename(""), "receipt");
+    }
+}
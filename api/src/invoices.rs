@@ -0,0 +1,1484 @@
+use axum::{
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::NaiveDate;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::business;
+use crate::clients::{self, Client};
+use crate::currencies::{self, validate_currency};
+use crate::ledger::{self, SourceType};
+use crate::numbering::{self, DocumentType};
+use crate::pdf;
+use crate::projects;
+use crate::taxes::{self, AppliedTax, TaxRounding, TaxSummary};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    Draft,
+    Sent,
+    PartiallyPaid,
+    Paid,
+    Void,
+}
+
+impl InvoiceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Sent => "sent",
+            InvoiceStatus::PartiallyPaid => "partially_paid",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Void => "void",
+        }
+    }
+
+    /// Whether the lifecycle allows moving from this status to `next`.
+    ///
+    /// draft -> sent -> partially_paid -> paid, with void reachable from any
+    /// status that has not been (partially) paid.
+    pub fn can_transition_to(&self, next: InvoiceStatus) -> bool {
+        use InvoiceStatus::*;
+
+        matches!(
+            (self, next),
+            (Draft, Sent)
+                | (Draft, Void)
+                | (Sent, PartiallyPaid)
+                | (Sent, Paid)
+                | (Sent, Void)
+                | (PartiallyPaid, Paid)
+        )
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Invoice {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Uuid,
+    pub invoice_number: Option<String>,
+    pub status: InvoiceStatus,
+    pub issue_date: NaiveDate,
+    pub due_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub tax_rounding: TaxRounding,
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub total: Decimal,
+    pub amount_paid: Decimal,
+    pub amount_credited: Decimal,
+    pub balance_due: Decimal,
+    pub voided_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub void_reason: Option<String>,
+    pub estimate_id: Option<Uuid>,
+    pub recurring_profile_id: Option<Uuid>,
+    pub recurrence_date: Option<NaiveDate>,
+    pub project_id: Option<Uuid>,
+    pub currency: String,
+    /// Home currency value of one unit of `currency`, fixed when issued.
+    pub exchange_rate: Option<Decimal>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Invoice {
+    /// The receivable posted when the invoice was issued, in the home currency.
+    pub fn total_home(&self) -> Decimal {
+        let exchange_rate = self.exchange_rate.unwrap_or(Decimal::ONE);
+        currencies::to_home(self.subtotal, exchange_rate)
+            + currencies::to_home(self.tax_total, exchange_rate)
+    }
+
+    /// Home currency value of settling `amount` more of the invoice.
+    pub fn settled_home(&self, amount: Decimal) -> Decimal {
+        currencies::settled_home(
+            self.total,
+            self.total_home(),
+            self.exchange_rate.unwrap_or(Decimal::ONE),
+            self.total - self.balance_due,
+            amount,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InvoiceLineItem {
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub position: i32,
+    pub description: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub amount: Decimal,
+    #[sqlx(skip)]
+    pub taxes: Vec<AppliedTax>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InvoiceTax {
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub tax_rate_id: Uuid,
+    pub position: i32,
+    pub name: String,
+    pub rate: Decimal,
+    pub is_compound: bool,
+    pub taxable_amount: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceWithLineItems {
+    #[serde(flatten)]
+    pub invoice: Invoice,
+    pub line_items: Vec<InvoiceLineItem>,
+    pub taxes: Vec<InvoiceTax>,
+}
+
+#[derive(sqlx::FromRow)]
+pub(crate) struct LineItemTaxRow {
+    pub line_item_id: Uuid,
+    #[sqlx(flatten)]
+    pub tax: AppliedTax,
+}
+
+/// A line item with its taxes already resolved, ready to be stored.
+#[derive(Debug, Clone)]
+pub(crate) struct NewLineItem {
+    pub description: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub taxes: Vec<AppliedTax>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct LineItemRequest {
+    #[validate(length(min = 1, message = "Description is required"))]
+    pub description: String,
+    #[validate(custom(function = "validate_positive"))]
+    pub quantity: Decimal,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub unit_price: Decimal,
+    /// Tax rates for this line; when omitted the client's regional defaults apply.
+    pub tax_rate_ids: Option<Vec<Uuid>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateInvoiceRequest {
+    pub client_id: Uuid,
+    pub project_id: Option<Uuid>,
+    pub issue_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub tax_rounding: Option<TaxRounding>,
+    /// Defaults to the client's currency, then the home currency.
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    /// Fixes the exchange rate instead of looking it up on the issue date.
+    #[validate(custom(function = "validate_positive"))]
+    pub exchange_rate: Option<Decimal>,
+    #[validate(
+        length(min = 1, message = "At least one line item is required"),
+        nested
+    )]
+    pub line_items: Vec<LineItemRequest>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateInvoiceRequest {
+    pub client_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    pub issue_date: Option<NaiveDate>,
+    /// `null` removes the due date, leaving it out keeps the current one.
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub due_date: Option<Option<NaiveDate>>,
+    pub notes: Option<String>,
+    pub tax_rounding: Option<TaxRounding>,
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    #[validate(custom(function = "validate_positive"))]
+    pub exchange_rate: Option<Decimal>,
+    #[validate(
+        length(min = 1, message = "At least one line item is required"),
+        nested
+    )]
+    pub line_items: Option<Vec<LineItemRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateInvoiceStatusRequest {
+    pub status: InvoiceStatus,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct VoidInvoiceRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReceivablesSummary {
+    pub outstanding_count: i64,
+    pub outstanding_total: Decimal,
+    pub overdue_count: i64,
+    pub overdue_total: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvoiceTotals {
+    pub subtotal: Decimal,
+    pub tax_total: Decimal,
+    pub total: Decimal,
+}
+
+pub(crate) fn validate_positive(value: &Decimal) -> Result<(), ValidationError> {
+    if *value > Decimal::ZERO {
+        Ok(())
+    } else {
+        Err(ValidationError::new("must_be_positive"))
+    }
+}
+
+pub(crate) fn validate_non_negative(value: &Decimal) -> Result<(), ValidationError> {
+    if *value < Decimal::ZERO {
+        Err(ValidationError::new("must_not_be_negative"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Deserializes a field that may be absent (`None`), `null` (`Some(None)`) or set,
+/// so updates can tell "leave unchanged" apart from "clear". Pair with `#[serde(default)]`.
+pub(crate) fn deserialize_nullable<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Rounds a monetary amount to cents, half away from zero.
+pub fn round_money(amount: Decimal) -> Decimal {
+    amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+pub fn line_amount(quantity: Decimal, unit_price: Decimal) -> Decimal {
+    round_money(quantity * unit_price)
+}
+
+/// Status of an issued invoice given how much of its total has been settled.
+pub fn settled_status(total: Decimal, settled: Decimal) -> InvoiceStatus {
+    if settled <= Decimal::ZERO {
+        InvoiceStatus::Sent
+    } else if settled < total {
+        InvoiceStatus::PartiallyPaid
+    } else {
+        InvoiceStatus::Paid
+    }
+}
+
+/// Computes document totals from each line's amount and taxes.
+pub fn compute_totals(
+    lines: &[(Decimal, Vec<AppliedTax>)],
+    rounding: TaxRounding,
+) -> (InvoiceTotals, Vec<TaxSummary>) {
+    let subtotal: Decimal = lines.iter().map(|(amount, _)| *amount).sum();
+    let tax_summaries = taxes::summarize_taxes(lines, rounding);
+    let tax_total: Decimal = tax_summaries.iter().map(|summary| summary.amount).sum();
+
+    let totals = InvoiceTotals {
+        subtotal,
+        tax_total,
+        total: subtotal + tax_total,
+    };
+
+    (totals, tax_summaries)
+}
+
+pub(crate) fn validate_dates(
+    issue_date: NaiveDate,
+    due_date: Option<NaiveDate>,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    if due_date.is_some_and(|due_date| due_date < issue_date) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Due date cannot be before the issue date" })),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn lock_invoice(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Invoice, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE id = $1 AND user_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch invoice: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch invoice" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Invoice not found" })),
+            )
+        })
+}
+
+/// Draft invoice that rebilled items are added to: the given invoice, locked
+/// and checked to be a draft for `client_id`, or a new draft dated today for
+/// `project_id`.
+///
+/// Callers must run [`recalculate_totals`] after adding line items.
+pub(crate) async fn open_billing_invoice(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    client_id: Uuid,
+    project_id: Option<Uuid>,
+    invoice_id: Option<Uuid>,
+) -> Result<Invoice, (StatusCode, Json<Value>)> {
+    if let Some(invoice_id) = invoice_id {
+        let invoice = lock_invoice(conn, user_id, invoice_id).await?;
+        if invoice.status != InvoiceStatus::Draft {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "Items can only be added to draft invoices" })),
+            ));
+        }
+        if invoice.client_id != client_id {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Invoice belongs to a different client" })),
+            ));
+        }
+        return Ok(invoice);
+    }
+
+    let invoice_number = numbering::next_number(conn, user_id, DocumentType::Invoice).await?;
+    let home_currency = currencies::home_currency(conn, user_id).await?;
+
+    sqlx::query_as::<_, Invoice>(
+        r#"
+        INSERT INTO invoices (user_id, client_id, project_id, invoice_number, issue_date, currency)
+        VALUES (
+            $1, $2, $3, $4, CURRENT_DATE,
+            COALESCE((SELECT currency FROM clients WHERE id = $2), $5)
+        )
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(project_id)
+    .bind(&invoice_number)
+    .bind(home_currency)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create invoice" })),
+        )
+    })
+}
+
+/// Fixes an issued invoice's exchange rate and posts its receivable, sales
+/// and tax to the ledger in the home currency.
+pub(crate) async fn post_issue(
+    conn: &mut PgConnection,
+    invoice: Invoice,
+) -> Result<Invoice, (StatusCode, Json<Value>)> {
+    let exchange_rate = currencies::document_rate(
+        conn,
+        invoice.user_id,
+        &invoice.currency,
+        invoice.exchange_rate,
+        invoice.issue_date,
+    )
+    .await?;
+
+    let invoice = sqlx::query_as::<_, Invoice>(
+        "UPDATE invoices SET exchange_rate = $1, updated_at = NOW() WHERE id = $2 RETURNING *",
+    )
+    .bind(exchange_rate)
+    .bind(invoice.id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fix invoice exchange rate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to issue invoice" })),
+        )
+    })?;
+
+    ledger::post(
+        conn,
+        invoice.user_id,
+        (SourceType::Invoice, invoice.id),
+        invoice.issue_date,
+        &format!(
+            "Invoice {}",
+            invoice.invoice_number.as_deref().unwrap_or_default()
+        ),
+        &ledger::invoice_postings(
+            currencies::to_home(invoice.subtotal, exchange_rate),
+            currencies::to_home(invoice.tax_total, exchange_rate),
+        ),
+    )
+    .await?;
+
+    Ok(invoice)
+}
+
+/// Returns items rebilled on an invoice's lines to the unbilled pool.
+async fn release_billed_items(
+    conn: &mut PgConnection,
+    invoice_id: Uuid,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    sqlx::query(
+        r#"
+        UPDATE expenses
+        SET invoice_line_item_id = NULL, updated_at = NOW()
+        WHERE invoice_line_item_id IN (
+            SELECT id FROM invoice_line_items WHERE invoice_id = $1
+        )
+        "#,
+    )
+    .bind(invoice_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to release billed expenses: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to void invoice" })),
+        )
+    })?;
+
+    sqlx::query(
+        r#"
+        UPDATE time_entries
+        SET invoice_line_item_id = NULL, updated_at = NOW()
+        WHERE invoice_line_item_id IN (
+            SELECT id FROM invoice_line_items WHERE invoice_id = $1
+        )
+        "#,
+    )
+    .bind(invoice_id)
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to release billed time entries: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to void invoice" })),
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Recomputes `amount_paid`, `amount_credited`, `balance_due` and the payment
+/// status of an issued invoice from its payment allocations, its credit notes
+/// and credit applied from other credit notes.
+/// The invoice row must already be locked.
+pub(crate) async fn refresh_balance(
+    conn: &mut PgConnection,
+    invoice_id: Uuid,
+) -> Result<Invoice, (StatusCode, Json<Value>)> {
+    let (total, amount_paid, amount_credited) = sqlx::query_as::<_, (Decimal, Decimal, Decimal)>(
+        r#"
+        SELECT
+            i.total,
+            COALESCE((SELECT SUM(a.amount) FROM payment_allocations a WHERE a.invoice_id = i.id), 0),
+            COALESCE((SELECT SUM(c.amount_applied) FROM credit_notes c WHERE c.invoice_id = i.id), 0)
+                + COALESCE((SELECT SUM(a.amount) FROM credit_note_allocations a WHERE a.invoice_id = i.id), 0)
+        FROM invoices i
+        WHERE i.id = $1
+        "#,
+    )
+    .bind(invoice_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to sum invoice payments: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update invoice balance" })),
+        )
+    })?;
+
+    sqlx::query_as::<_, Invoice>(
+        r#"
+        UPDATE invoices
+        SET
+            amount_paid = $1,
+            amount_credited = $2,
+            balance_due = total - $1 - $2,
+            status = $3,
+            updated_at = NOW()
+        WHERE id = $4
+        RETURNING *
+        "#,
+    )
+    .bind(amount_paid)
+    .bind(amount_credited)
+    .bind(settled_status(total, amount_paid + amount_credited))
+    .bind(invoice_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update invoice balance: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update invoice balance" })),
+        )
+    })
+}
+
+pub(crate) async fn fetch_line_items(
+    conn: &mut PgConnection,
+    invoice_id: Uuid,
+) -> Result<Vec<InvoiceLineItem>, (StatusCode, Json<Value>)> {
+    let mut line_items = sqlx::query_as::<_, InvoiceLineItem>(
+        "SELECT * FROM invoice_line_items WHERE invoice_id = $1 ORDER BY position",
+    )
+    .bind(invoice_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch invoice line items: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch invoice line items" })),
+        )
+    })?;
+
+    let tax_rows = sqlx::query_as::<_, LineItemTaxRow>(
+        r#"
+        SELECT t.line_item_id, t.tax_rate_id, t.name, t.rate, t.is_compound
+        FROM invoice_line_item_taxes t
+        JOIN invoice_line_items l ON l.id = t.line_item_id
+        WHERE l.invoice_id = $1
+        ORDER BY t.position
+        "#,
+    )
+    .bind(invoice_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch invoice line item taxes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch invoice line items" })),
+        )
+    })?;
+
+    for row in tax_rows {
+        if let Some(line_item) = line_items
+            .iter_mut()
+            .find(|item| item.id == row.line_item_id)
+        {
+            line_item.taxes.push(row.tax);
+        }
+    }
+
+    Ok(line_items)
+}
+
+pub(crate) async fn fetch_invoice_taxes(
+    conn: &mut PgConnection,
+    invoice_id: Uuid,
+) -> Result<Vec<InvoiceTax>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, InvoiceTax>(
+        "SELECT * FROM invoice_taxes WHERE invoice_id = $1 ORDER BY position",
+    )
+    .bind(invoice_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch invoice taxes: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch invoice taxes" })),
+        )
+    })
+}
+
+pub(crate) async fn fetch_invoice_details(
+    conn: &mut PgConnection,
+    invoice: Invoice,
+) -> Result<InvoiceWithLineItems, (StatusCode, Json<Value>)> {
+    let line_items = fetch_line_items(conn, invoice.id).await?;
+    let taxes = fetch_invoice_taxes(conn, invoice.id).await?;
+
+    Ok(InvoiceWithLineItems {
+        invoice,
+        line_items,
+        taxes,
+    })
+}
+
+/// Appends line items to an invoice, resolving each line's taxes.
+///
+/// Callers must run [`recalculate_totals`] afterwards.
+pub(crate) async fn add_line_items(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    invoice_id: Uuid,
+    client: &Client,
+    line_items: &[LineItemRequest],
+) -> Result<Vec<InvoiceLineItem>, (StatusCode, Json<Value>)> {
+    let requested_taxes: Vec<Option<Vec<Uuid>>> = line_items
+        .iter()
+        .map(|item| item.tax_rate_ids.clone())
+        .collect();
+    let line_taxes = taxes::resolve_line_taxes(conn, user_id, client, &requested_taxes).await?;
+
+    let new_items: Vec<NewLineItem> = line_items
+        .iter()
+        .zip(line_taxes)
+        .map(|(item, taxes)| NewLineItem {
+            description: item.description.clone(),
+            quantity: item.quantity,
+            unit_price: item.unit_price,
+            taxes,
+        })
+        .collect();
+
+    insert_line_items(conn, invoice_id, &new_items).await
+}
+
+/// Appends line items whose taxes are already resolved.
+///
+/// Callers must run [`recalculate_totals`] afterwards.
+pub(crate) async fn insert_line_items(
+    conn: &mut PgConnection,
+    invoice_id: Uuid,
+    line_items: &[NewLineItem],
+) -> Result<Vec<InvoiceLineItem>, (StatusCode, Json<Value>)> {
+    let next_position = sqlx::query_scalar::<_, i32>(
+        "SELECT COALESCE(MAX(position) + 1, 0) FROM invoice_line_items WHERE invoice_id = $1",
+    )
+    .bind(invoice_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch invoice line positions: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save invoice line items" })),
+        )
+    })?;
+
+    let mut inserted = Vec::with_capacity(line_items.len());
+
+    for (offset, item) in line_items.iter().enumerate() {
+        let mut line_item = sqlx::query_as::<_, InvoiceLineItem>(
+            r#"
+            INSERT INTO invoice_line_items (
+                invoice_id, position, description, quantity, unit_price, amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(invoice_id)
+        .bind(next_position + offset as i32)
+        .bind(&item.description)
+        .bind(item.quantity)
+        .bind(item.unit_price)
+        .bind(line_amount(item.quantity, item.unit_price))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert invoice line item: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to save invoice line items" })),
+            )
+        })?;
+
+        for (position, tax) in item.taxes.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO invoice_line_item_taxes (
+                    line_item_id, tax_rate_id, position, name, rate, is_compound
+                )
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(line_item.id)
+            .bind(tax.tax_rate_id)
+            .bind(position as i32)
+            .bind(&tax.name)
+            .bind(tax.rate)
+            .bind(tax.is_compound)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to insert invoice line item tax: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to save invoice line items" })),
+                )
+            })?;
+        }
+
+        line_item.taxes = item.taxes.clone();
+        inserted.push(line_item);
+    }
+
+    Ok(inserted)
+}
+
+/// Recomputes an invoice's tax summary and totals from its stored line items.
+pub(crate) async fn recalculate_totals(
+    conn: &mut PgConnection,
+    invoice_id: Uuid,
+) -> Result<Invoice, (StatusCode, Json<Value>)> {
+    let tax_rounding =
+        sqlx::query_scalar::<_, TaxRounding>("SELECT tax_rounding FROM invoices WHERE id = $1")
+            .bind(invoice_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch invoice: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to calculate invoice totals" })),
+                )
+            })?;
+
+    let lines: Vec<(Decimal, Vec<AppliedTax>)> = fetch_line_items(conn, invoice_id)
+        .await?
+        .into_iter()
+        .map(|item| (item.amount, item.taxes))
+        .collect();
+    let (totals, tax_summaries) = compute_totals(&lines, tax_rounding);
+
+    sqlx::query("DELETE FROM invoice_taxes WHERE invoice_id = $1")
+        .bind(invoice_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to clear invoice taxes: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to calculate invoice totals" })),
+            )
+        })?;
+
+    for (position, summary) in tax_summaries.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO invoice_taxes (
+                invoice_id, tax_rate_id, position, name, rate, is_compound,
+                taxable_amount, amount
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(invoice_id)
+        .bind(summary.tax.tax_rate_id)
+        .bind(position as i32)
+        .bind(&summary.tax.name)
+        .bind(summary.tax.rate)
+        .bind(summary.tax.is_compound)
+        .bind(summary.taxable_amount)
+        .bind(summary.amount)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert invoice tax: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to calculate invoice totals" })),
+            )
+        })?;
+    }
+
+    sqlx::query_as::<_, Invoice>(
+        r#"
+        UPDATE invoices
+        SET
+            subtotal = $1,
+            tax_total = $2,
+            total = $3,
+            balance_due = $3 - amount_paid,
+            updated_at = NOW()
+        WHERE id = $4
+        RETURNING *
+        "#,
+    )
+    .bind(totals.subtotal)
+    .bind(totals.tax_total)
+    .bind(totals.total)
+    .bind(invoice_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update invoice totals: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to calculate invoice totals" })),
+        )
+    })
+}
+
+pub async fn list_invoices(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<Invoice>>, (StatusCode, Json<Value>)> {
+    let invoices = sqlx::query_as::<_, Invoice>(
+        "SELECT * FROM invoices WHERE user_id = $1 ORDER BY issue_date DESC, created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch invoices: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch invoices" })),
+        )
+    })?;
+
+    Ok(Json(invoices))
+}
+
+pub async fn create_invoice(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateInvoiceRequest>,
+) -> Result<(StatusCode, Json<InvoiceWithLineItems>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let issue_date = req
+        .issue_date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    validate_dates(issue_date, req.due_date)?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create invoice" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut tx, user_id, req.client_id).await?;
+    projects::project_client(&mut tx, user_id, req.project_id, Some(client.id)).await?;
+
+    let currency = currencies::document_currency(&mut tx, user_id, &client, req.currency).await?;
+    let invoice_number = numbering::next_number(&mut tx, user_id, DocumentType::Invoice).await?;
+
+    let invoice = sqlx::query_as::<_, Invoice>(
+        r#"
+        INSERT INTO invoices (
+            user_id, client_id, invoice_number, issue_date, due_date, notes, tax_rounding,
+            project_id, currency, exchange_rate
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.client_id)
+    .bind(&invoice_number)
+    .bind(issue_date)
+    .bind(req.due_date)
+    .bind(&req.notes)
+    .bind(req.tax_rounding.unwrap_or_default())
+    .bind(req.project_id)
+    .bind(currency)
+    .bind(req.exchange_rate)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create invoice" })),
+        )
+    })?;
+
+    add_line_items(&mut tx, user_id, invoice.id, &client, &req.line_items).await?;
+    let invoice = recalculate_totals(&mut tx, invoice.id).await?;
+    let details = fetch_invoice_details(&mut tx, invoice).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create invoice" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(details)))
+}
+
+pub async fn get_invoice(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InvoiceWithLineItems>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch invoice" })),
+        )
+    })?;
+
+    let invoice =
+        sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch invoice: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch invoice" })),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": "Invoice not found" })),
+                )
+            })?;
+
+    let details = fetch_invoice_details(&mut conn, invoice).await?;
+
+    Ok(Json(details))
+}
+
+pub async fn update_invoice(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateInvoiceRequest>,
+) -> Result<Json<InvoiceWithLineItems>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update invoice" })),
+        )
+    })?;
+
+    let existing = lock_invoice(&mut tx, user_id, id).await?;
+
+    if existing.status != InvoiceStatus::Draft {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Only draft invoices can be edited" })),
+        ));
+    }
+
+    let client_id = req.client_id.unwrap_or(existing.client_id);
+    let client = clients::fetch_owned_client(&mut tx, user_id, client_id).await?;
+
+    // A project from another client is dropped when the client changes
+    let project_id = req.project_id.or(existing.project_id.filter(|_| {
+        req.client_id
+            .is_none_or(|client_id| client_id == existing.client_id)
+    }));
+    projects::project_client(&mut tx, user_id, project_id, Some(client.id)).await?;
+
+    let issue_date = req.issue_date.unwrap_or(existing.issue_date);
+    let due_date = req.due_date.unwrap_or(existing.due_date);
+    validate_dates(issue_date, due_date)?;
+
+    // A rate given for the old currency does not carry over to a new one
+    let exchange_rate = req
+        .exchange_rate
+        .or(existing.exchange_rate.filter(|_| req.currency.is_none()));
+    let currency = req.currency.unwrap_or(existing.currency);
+
+    if let Some(items) = &req.line_items {
+        sqlx::query("DELETE FROM invoice_line_items WHERE invoice_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to delete invoice line items: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to update invoice" })),
+                )
+            })?;
+
+        add_line_items(&mut tx, user_id, id, &client, items).await?;
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE invoices
+        SET
+            client_id = $1,
+            issue_date = $2,
+            due_date = $3,
+            notes = COALESCE($4, notes),
+            tax_rounding = COALESCE($5, tax_rounding),
+            project_id = $6,
+            currency = $7,
+            exchange_rate = $8,
+            updated_at = NOW()
+        WHERE id = $9 AND user_id = $10
+        "#,
+    )
+    .bind(client_id)
+    .bind(issue_date)
+    .bind(due_date)
+    .bind(req.notes)
+    .bind(req.tax_rounding)
+    .bind(project_id)
+    .bind(&currency)
+    .bind(exchange_rate)
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update invoice" })),
+        )
+    })?;
+
+    let invoice = recalculate_totals(&mut tx, id).await?;
+    let details = fetch_invoice_details(&mut tx, invoice).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit invoice update: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update invoice" })),
+        )
+    })?;
+
+    Ok(Json(details))
+}
+
+pub async fn update_invoice_status(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateInvoiceStatusRequest>,
+) -> Result<Json<Invoice>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update invoice status" })),
+        )
+    })?;
+
+    if matches!(
+        req.status,
+        InvoiceStatus::PartiallyPaid | InvoiceStatus::Paid
+    ) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Record a payment to mark an invoice as paid" })),
+        ));
+    }
+
+    let existing = lock_invoice(&mut tx, user_id, id).await?;
+
+    if !existing.status.can_transition_to(req.status) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!(
+                    "Cannot change invoice status from {} to {}",
+                    existing.status.as_str(),
+                    req.status.as_str()
+                )
+            })),
+        ));
+    }
+
+    let invoice = if req.status == InvoiceStatus::Void {
+        mark_void(&mut tx, &existing, None).await?
+    } else {
+        sqlx::query_as::<_, Invoice>(
+            r#"
+            UPDATE invoices
+            SET status = $1, updated_at = NOW()
+            WHERE id = $2 AND user_id = $3
+            RETURNING *
+            "#,
+        )
+        .bind(req.status)
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update invoice status: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to update invoice status" })),
+            )
+        })?
+    };
+
+    let invoice = if invoice.status == InvoiceStatus::Sent {
+        post_issue(&mut tx, invoice).await?
+    } else {
+        invoice
+    };
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit invoice status: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update invoice status" })),
+        )
+    })?;
+
+    Ok(Json(invoice))
+}
+
+/// Voids a locked invoice. The record is kept, but its balance no longer counts
+/// towards receivables. Invoices with payments or credits must be reversed
+/// with a credit note instead.
+async fn mark_void(
+    conn: &mut PgConnection,
+    invoice: &Invoice,
+    reason: Option<String>,
+) -> Result<Invoice, (StatusCode, Json<Value>)> {
+    if !invoice.amount_paid.is_zero() || !invoice.amount_credited.is_zero() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Invoices with payments or credits cannot be voided; issue a credit note instead"
+            })),
+        ));
+    }
+
+    // Voided charges were never really billed, so their items can be rebilled
+    release_billed_items(conn, invoice.id).await?;
+    ledger::reverse_source(conn, invoice.user_id, (SourceType::Invoice, invoice.id)).await?;
+
+    sqlx::query_as::<_, Invoice>(
+        r#"
+        UPDATE invoices
+        SET status = 'void', balance_due = 0, voided_at = NOW(), void_reason = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(reason)
+    .bind(invoice.id)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to void invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to void invoice" })),
+        )
+    })
+}
+
+pub async fn void_invoice(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<VoidInvoiceRequest>,
+) -> Result<Json<Invoice>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to void invoice" })),
+        )
+    })?;
+
+    let existing = lock_invoice(&mut tx, user_id, id).await?;
+
+    if !existing.status.can_transition_to(InvoiceStatus::Void) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!("Cannot void a {} invoice", existing.status.as_str())
+            })),
+        ));
+    }
+
+    let invoice = mark_void(&mut tx, &existing, req.reason).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit invoice void: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to void invoice" })),
+        )
+    })?;
+
+    Ok(Json(invoice))
+}
+
+/// Totals of issued invoices still awaiting payment. Drafts and void invoices
+/// are excluded.
+pub async fn get_receivables_summary(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<ReceivablesSummary>, (StatusCode, Json<Value>)> {
+    let summary = sqlx::query_as::<_, ReceivablesSummary>(
+        r#"
+        SELECT
+            COUNT(*) AS outstanding_count,
+            COALESCE(SUM(balance_due), 0) AS outstanding_total,
+            COUNT(*) FILTER (WHERE due_date < CURRENT_DATE) AS overdue_count,
+            COALESCE(SUM(balance_due) FILTER (WHERE due_date < CURRENT_DATE), 0) AS overdue_total
+        FROM invoices
+        WHERE user_id = $1
+            AND status IN ('sent', 'partially_paid')
+            AND balance_due > 0
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch receivables summary: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch receivables summary" })),
+        )
+    })?;
+
+    Ok(Json(summary))
+}
+
+pub async fn delete_invoice(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete invoice" })),
+        )
+    })?;
+
+    let existing = lock_invoice(&mut tx, user_id, id).await?;
+
+    if existing.status != InvoiceStatus::Draft {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Only draft invoices can be deleted" })),
+        ));
+    }
+
+    // Deleting anything but the latest numbered draft would leave a gap in the sequence
+    if let Some(number) = &existing.invoice_number
+        && !numbering::release_number(&mut tx, user_id, DocumentType::Invoice, number).await?
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Only the most recently numbered draft can be deleted; void this invoice instead"
+            })),
+        ));
+    }
+
+    sqlx::query("DELETE FROM invoices WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete invoice: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete invoice" })),
+            )
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit invoice deletion: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete invoice" })),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_invoice_pdf(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to render invoice" })),
+        )
+    })?;
+
+    let invoice =
+        sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch invoice: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch invoice" })),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": "Invoice not found" })),
+                )
+            })?;
+
+    let line_items = fetch_line_items(&mut conn, invoice.id).await?;
+    let taxes = fetch_invoice_taxes(&mut conn, invoice.id).await?;
+    let client = clients::fetch_owned_client(&mut conn, user_id, invoice.client_id).await?;
+    let business = business::fetch_business_profile(&mut conn, user_id).await?;
+
+    let document =
+        pdf::DocumentModel::for_invoice(&invoice, &line_items, &taxes, &client, business.as_ref());
+    let filename = invoice
+        .invoice_number
+        .clone()
+        .unwrap_or_else(|| format!("invoice-{}", invoice.id));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}.pdf\"", filename),
+            ),
+        ],
+        pdf::render(&document),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn item(quantity: &str, unit_price: &str) -> LineItemRequest {
+        LineItemRequest {
+            description: "Work".to_string(),
+            quantity: Decimal::from_str(quantity).unwrap(),
+            unit_price: Decimal::from_str(unit_price).unwrap(),
+            tax_rate_ids: None,
+        }
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        use InvoiceStatus::*;
+
+        assert!(Draft.can_transition_to(Sent));
+        assert!(Draft.can_transition_to(Void));
+        assert!(Sent.can_transition_to(PartiallyPaid));
+        assert!(Sent.can_transition_to(Paid));
+        assert!(PartiallyPaid.can_transition_to(Paid));
+        assert!(!Draft.can_transition_to(Paid));
+        assert!(!Paid.can_transition_to(Void));
+        assert!(!Void.can_transition_to(Draft));
+        assert!(!Sent.can_transition_to(Draft));
+    }
+
+    #[test]
+    fn test_settled_status() {
+        let total = Decimal::from_str("100.00").unwrap();
+
+        assert_eq!(settled_status(total, Decimal::ZERO), InvoiceStatus::Sent);
+        assert_eq!(
+            settled_status(total, Decimal::from_str("99.99").unwrap()),
+            InvoiceStatus::PartiallyPaid
+        );
+        assert_eq!(settled_status(total, total), InvoiceStatus::Paid);
+    }
+
+    #[test]
+    fn test_line_amount_rounds_half_away_from_zero() {
+        let quantity = Decimal::from_str("3").unwrap();
+        let unit_price = Decimal::from_str("0.335").unwrap();
+        assert_eq!(line_amount(quantity, unit_price).to_string(), "1.01");
+    }
+
+    #[test]
+    fn test_compute_totals() {
+        let lines: Vec<(Decimal, Vec<AppliedTax>)> = [item("2", "49.995"), item("1.5", "10")]
+            .iter()
+            .map(|item| (line_amount(item.quantity, item.unit_price), Vec::new()))
+            .collect();
+        let (totals, tax_summaries) = compute_totals(&lines, TaxRounding::PerLine);
+        assert_eq!(totals.subtotal.to_string(), "114.99");
+        assert_eq!(totals.tax_total, Decimal::ZERO);
+        assert_eq!(totals.total.to_string(), "114.99");
+        assert!(tax_summaries.is_empty());
+    }
+
+    #[test]
+    fn test_update_request_distinguishes_cleared_due_date() {
+        let parse = |body: &str| serde_json::from_str::<UpdateInvoiceRequest>(body).unwrap();
+
+        assert_eq!(parse("{}").due_date, None);
+        assert_eq!(parse(r#"{"due_date": null}"#).due_date, Some(None));
+        assert_eq!(
+            parse(r#"{"due_date": "2026-11-30"}"#).due_date,
+            Some(NaiveDate::from_ymd_opt(2026, 11, 30))
+        );
+    }
+}
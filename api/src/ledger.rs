@@ -0,0 +1,751 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::accounts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AccountType {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+impl AccountType {
+    /// Whether debits increase the account's balance (assets and expenses).
+    pub fn is_debit_normal(&self) -> bool {
+        matches!(self, AccountType::Asset | AccountType::Expense)
+    }
+}
+
+/// Accounts the ledger posts documents to without the user picking one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SystemAccount {
+    Cash,
+    AccountsReceivable,
+    SalesTaxPayable,
+    CustomerCredits,
+    Sales,
+    ExchangeGainLoss,
+    OtherExpenses,
+}
+
+impl SystemAccount {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemAccount::Cash => "cash",
+            SystemAccount::AccountsReceivable => "accounts_receivable",
+            SystemAccount::SalesTaxPayable => "sales_tax_payable",
+            SystemAccount::CustomerCredits => "customer_credits",
+            SystemAccount::Sales => "sales",
+            SystemAccount::ExchangeGainLoss => "exchange_gain_loss",
+            SystemAccount::OtherExpenses => "other_expenses",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SourceType {
+    Invoice,
+    Payment,
+    CreditNote,
+    Expense,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub entry_date: NaiveDate,
+    pub description: String,
+    pub source_type: SourceType,
+    pub source_id: Uuid,
+    /// Entry this one cancels out, for reversals.
+    pub reverses_entry_id: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct JournalLine {
+    pub id: Uuid,
+    pub journal_entry_id: Uuid,
+    pub account_id: Uuid,
+    pub account_code: String,
+    pub account_name: String,
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JournalEntryWithLines {
+    #[serde(flatten)]
+    pub entry: JournalEntry,
+    pub lines: Vec<JournalLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrialBalanceRow {
+    pub account_id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub account_type: AccountType,
+    /// Net balance, shown on the side it falls on.
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrialBalance {
+    pub as_of: NaiveDate,
+    pub accounts: Vec<TrialBalanceRow>,
+    pub total_debit: Decimal,
+    pub total_credit: Decimal,
+}
+
+#[derive(sqlx::FromRow)]
+struct AccountTotals {
+    account_id: Uuid,
+    code: String,
+    name: String,
+    account_type: AccountType,
+    debit: Decimal,
+    credit: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JournalEntriesQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only entries with a line on this account.
+    pub account_id: Option<Uuid>,
+    pub source_type: Option<SourceType>,
+    pub source_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrialBalanceQuery {
+    /// Defaults to today.
+    pub as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum AccountRef {
+    System(SystemAccount),
+    Id(Uuid),
+}
+
+/// One side of a journal entry before its account is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Posting {
+    pub account: AccountRef,
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+impl Posting {
+    /// Debits `amount`; a negative amount is posted as a credit.
+    pub fn debit(account: AccountRef, amount: Decimal) -> Self {
+        if amount.is_sign_negative() {
+            Self::credit(account, -amount)
+        } else {
+            Posting {
+                account,
+                debit: amount,
+                credit: Decimal::ZERO,
+            }
+        }
+    }
+
+    /// Credits `amount`; a negative amount is posted as a debit.
+    pub fn credit(account: AccountRef, amount: Decimal) -> Self {
+        if amount.is_sign_negative() {
+            Self::debit(account, -amount)
+        } else {
+            Posting {
+                account,
+                debit: Decimal::ZERO,
+                credit: amount,
+            }
+        }
+    }
+}
+
+pub(crate) fn is_balanced(postings: &[Posting]) -> bool {
+    let debits: Decimal = postings.iter().map(|posting| posting.debit).sum();
+    let credits: Decimal = postings.iter().map(|posting| posting.credit).sum();
+    debits == credits
+}
+
+/// Issuing an invoice: the client owes the total, earned as sales plus the
+/// tax collected on the government's behalf.
+pub(crate) fn invoice_postings(subtotal: Decimal, tax_total: Decimal) -> Vec<Posting> {
+    use SystemAccount::*;
+
+    vec![
+        Posting::debit(AccountRef::System(AccountsReceivable), subtotal + tax_total),
+        Posting::credit(AccountRef::System(Sales), subtotal),
+        Posting::credit(AccountRef::System(SalesTaxPayable), tax_total),
+    ]
+}
+
+/// Receiving money: held as client credit until allocated to invoices.
+pub(crate) fn payment_postings(amount: Decimal) -> Vec<Posting> {
+    use SystemAccount::*;
+
+    vec![
+        Posting::debit(AccountRef::System(Cash), amount),
+        Posting::credit(AccountRef::System(CustomerCredits), amount),
+    ]
+}
+
+/// Allocating client credit to invoices settles their receivable. Credit
+/// received at a different exchange rate than the invoice was issued at
+/// realises the difference as an exchange gain or loss.
+pub(crate) fn allocation_postings(
+    credit_used: Decimal,
+    receivable_settled: Decimal,
+) -> Vec<Posting> {
+    use SystemAccount::*;
+
+    vec![
+        Posting::debit(AccountRef::System(CustomerCredits), credit_used),
+        Posting::credit(AccountRef::System(AccountsReceivable), receivable_settled),
+        Posting::credit(
+            AccountRef::System(ExchangeGainLoss),
+            credit_used - receivable_settled,
+        ),
+    ]
+}
+
+/// A credit note takes back sales and tax; what the invoice no longer owes
+/// reduces its receivable and the rest becomes client credit.
+pub(crate) fn credit_note_postings(
+    subtotal: Decimal,
+    tax_total: Decimal,
+    amount_applied: Decimal,
+) -> Vec<Posting> {
+    use SystemAccount::*;
+
+    vec![
+        Posting::debit(AccountRef::System(Sales), subtotal),
+        Posting::debit(AccountRef::System(SalesTaxPayable), tax_total),
+        Posting::credit(AccountRef::System(AccountsReceivable), amount_applied),
+        Posting::credit(
+            AccountRef::System(CustomerCredits),
+            subtotal + tax_total - amount_applied,
+        ),
+    ]
+}
+
+/// An expense paid in cash; tax paid on it is recoverable from the tax owed.
+pub(crate) fn expense_postings(
+    account: AccountRef,
+    amount: Decimal,
+    tax_amount: Decimal,
+) -> Vec<Posting> {
+    use SystemAccount::*;
+
+    vec![
+        Posting::debit(account, amount),
+        Posting::debit(AccountRef::System(SalesTaxPayable), tax_amount),
+        Posting::credit(AccountRef::System(Cash), amount + tax_amount),
+    ]
+}
+
+/// Nets an account's debit and credit totals onto the side the balance falls.
+pub fn net_balance(debit: Decimal, credit: Decimal) -> (Decimal, Decimal) {
+    if debit >= credit {
+        (debit - credit, Decimal::ZERO)
+    } else {
+        (Decimal::ZERO, credit - debit)
+    }
+}
+
+fn posting_error(e: sqlx::Error) -> (StatusCode, Json<Value>) {
+    tracing::error!("Failed to post journal entry: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": "Failed to post journal entry" })),
+    )
+}
+
+/// The user's account for a system role, created if missing from their chart.
+async fn system_account_id(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    account: SystemAccount,
+) -> Result<Uuid, (StatusCode, Json<Value>)> {
+    let query = "SELECT id FROM accounts WHERE user_id = $1 AND system_key = $2";
+
+    let account_id = sqlx::query_scalar::<_, Uuid>(query)
+        .bind(user_id)
+        .bind(account)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(posting_error)?;
+    if let Some(account_id) = account_id {
+        return Ok(account_id);
+    }
+
+    let definition = accounts::default_account(account);
+    accounts::insert_default_account(&mut *conn, user_id, definition)
+        .await
+        .map_err(posting_error)?;
+
+    sqlx::query_scalar::<_, Uuid>(query)
+        .bind(user_id)
+        .bind(account)
+        .fetch_optional(conn)
+        .await
+        .map_err(posting_error)?
+        .ok_or_else(|| {
+            tracing::error!(
+                "Account code {} is taken, cannot create the {} account",
+                definition.code,
+                account.as_str()
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to post journal entry" })),
+            )
+        })
+}
+
+/// Expense account an expense of `category` is posted to: the user's expense
+/// account of the same name, or Other Expenses.
+pub(crate) async fn expense_account(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    category: &str,
+) -> Result<AccountRef, (StatusCode, Json<Value>)> {
+    let account_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM accounts
+        WHERE user_id = $1
+            AND account_type = 'expense'
+            AND NOT is_archived
+            AND LOWER(name) = LOWER($2)
+        ORDER BY code
+        LIMIT 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(category.trim())
+    .fetch_optional(conn)
+    .await
+    .map_err(posting_error)?;
+
+    Ok(account_id.map_or(
+        AccountRef::System(SystemAccount::OtherExpenses),
+        AccountRef::Id,
+    ))
+}
+
+async fn insert_entry(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    source: (SourceType, Uuid),
+    entry_date: NaiveDate,
+    description: &str,
+    reverses_entry_id: Option<Uuid>,
+    lines: &[(Uuid, Decimal, Decimal)],
+) -> Result<JournalEntry, (StatusCode, Json<Value>)> {
+    let entry = sqlx::query_as::<_, JournalEntry>(
+        r#"
+        INSERT INTO journal_entries (
+            user_id, entry_date, description, source_type, source_id, reverses_entry_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(entry_date)
+    .bind(description)
+    .bind(source.0)
+    .bind(source.1)
+    .bind(reverses_entry_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(posting_error)?;
+
+    for (account_id, debit, credit) in lines {
+        sqlx::query(
+            r#"
+            INSERT INTO journal_lines (user_id, journal_entry_id, account_id, debit, credit)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(user_id)
+        .bind(entry.id)
+        .bind(account_id)
+        .bind(debit)
+        .bind(credit)
+        .execute(&mut *conn)
+        .await
+        .map_err(posting_error)?;
+    }
+
+    Ok(entry)
+}
+
+/// Records a journal entry for a document. Zero lines are dropped, and nothing
+/// is posted when no amount is left. Unbalanced postings are a bug and fail the
+/// caller's transaction.
+pub(crate) async fn post(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    source: (SourceType, Uuid),
+    entry_date: NaiveDate,
+    description: &str,
+    postings: &[Posting],
+) -> Result<Option<JournalEntry>, (StatusCode, Json<Value>)> {
+    if !is_balanced(postings) {
+        tracing::error!(
+            "Unbalanced journal entry for {:?} {}: {:?}",
+            source.0,
+            source.1,
+            postings
+        );
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Journal entry does not balance" })),
+        ));
+    }
+
+    let mut system_ids = HashMap::new();
+    let mut lines = Vec::new();
+    for posting in postings {
+        if posting.debit.is_zero() && posting.credit.is_zero() {
+            continue;
+        }
+
+        let account_id = match posting.account {
+            AccountRef::Id(account_id) => account_id,
+            AccountRef::System(account) => match system_ids.get(&account) {
+                Some(account_id) => *account_id,
+                None => {
+                    let account_id = system_account_id(conn, user_id, account).await?;
+                    system_ids.insert(account, account_id);
+                    account_id
+                }
+            },
+        };
+        lines.push((account_id, posting.debit, posting.credit));
+    }
+
+    if lines.is_empty() {
+        return Ok(None);
+    }
+
+    insert_entry(conn, user_id, source, entry_date, description, None, &lines)
+        .await
+        .map(Some)
+}
+
+/// Cancels everything posted for a document with reversing entries, each
+/// dated like the entry it reverses so past periods reflect the correction.
+pub(crate) async fn reverse_source(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    source: (SourceType, Uuid),
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let entries = sqlx::query_as::<_, JournalEntry>(
+        r#"
+        SELECT * FROM journal_entries e
+        WHERE e.user_id = $1
+            AND e.source_type = $2
+            AND e.source_id = $3
+            AND e.reverses_entry_id IS NULL
+            AND NOT EXISTS (SELECT 1 FROM journal_entries r WHERE r.reverses_entry_id = e.id)
+        ORDER BY e.created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(source.0)
+    .bind(source.1)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(posting_error)?;
+
+    for entry in entries {
+        let lines = sqlx::query_as::<_, (Uuid, Decimal, Decimal)>(
+            "SELECT account_id, credit, debit FROM journal_lines WHERE journal_entry_id = $1",
+        )
+        .bind(entry.id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(posting_error)?;
+
+        insert_entry(
+            conn,
+            user_id,
+            source,
+            entry.entry_date,
+            &format!("Reversal: {}", entry.description),
+            Some(entry.id),
+            &lines,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn list_journal_entries(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<JournalEntriesQuery>,
+) -> Result<Json<Vec<JournalEntryWithLines>>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch journal entries" })),
+        )
+    })?;
+
+    let entries = sqlx::query_as::<_, JournalEntry>(
+        r#"
+        SELECT * FROM journal_entries e
+        WHERE e.user_id = $1
+            AND ($2::date IS NULL OR e.entry_date >= $2)
+            AND ($3::date IS NULL OR e.entry_date <= $3)
+            AND ($4::uuid IS NULL OR EXISTS (
+                SELECT 1 FROM journal_lines l
+                WHERE l.journal_entry_id = e.id AND l.account_id = $4
+            ))
+            AND ($5::varchar IS NULL OR e.source_type = $5)
+            AND ($6::uuid IS NULL OR e.source_id = $6)
+        ORDER BY e.entry_date DESC, e.created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(query.account_id)
+    .bind(query.source_type)
+    .bind(query.source_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch journal entries: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch journal entries" })),
+        )
+    })?;
+
+    let entry_ids: Vec<Uuid> = entries.iter().map(|entry| entry.id).collect();
+    let lines = sqlx::query_as::<_, JournalLine>(
+        r#"
+        SELECT
+            l.id, l.journal_entry_id, l.account_id,
+            a.code AS account_code, a.name AS account_name,
+            l.debit, l.credit
+        FROM journal_lines l
+        JOIN accounts a ON a.id = l.account_id
+        WHERE l.journal_entry_id = ANY($1)
+        ORDER BY l.debit DESC, a.code
+        "#,
+    )
+    .bind(&entry_ids)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch journal lines: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch journal entries" })),
+        )
+    })?;
+
+    let mut lines_by_entry: HashMap<Uuid, Vec<JournalLine>> = HashMap::new();
+    for line in lines {
+        lines_by_entry
+            .entry(line.journal_entry_id)
+            .or_default()
+            .push(line);
+    }
+
+    Ok(Json(
+        entries
+            .into_iter()
+            .map(|entry| JournalEntryWithLines {
+                lines: lines_by_entry.remove(&entry.id).unwrap_or_default(),
+                entry,
+            })
+            .collect(),
+    ))
+}
+
+/// Net balance of every account with postings up to `as_of`. Total debits
+/// always equal total credits.
+pub async fn get_trial_balance(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<TrialBalanceQuery>,
+) -> Result<Json<TrialBalance>, (StatusCode, Json<Value>)> {
+    let as_of = query
+        .as_of
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    let totals = sqlx::query_as::<_, AccountTotals>(
+        r#"
+        SELECT
+            a.id AS account_id, a.code, a.name, a.account_type,
+            SUM(l.debit) AS debit, SUM(l.credit) AS credit
+        FROM accounts a
+        JOIN journal_lines l ON l.account_id = a.id
+        JOIN journal_entries e ON e.id = l.journal_entry_id
+        WHERE a.user_id = $1 AND e.entry_date <= $2
+        GROUP BY a.id
+        ORDER BY a.code
+        "#,
+    )
+    .bind(user_id)
+    .bind(as_of)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch trial balance: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch trial balance" })),
+        )
+    })?;
+
+    let accounts: Vec<TrialBalanceRow> = totals
+        .into_iter()
+        .map(|totals| {
+            let (debit, credit) = net_balance(totals.debit, totals.credit);
+            TrialBalanceRow {
+                account_id: totals.account_id,
+                code: totals.code,
+                name: totals.name,
+                account_type: totals.account_type,
+                debit,
+                credit,
+            }
+        })
+        .collect();
+
+    Ok(Json(TrialBalance {
+        as_of,
+        total_debit: accounts.iter().map(|row| row.debit).sum(),
+        total_credit: accounts.iter().map(|row| row.credit).sum(),
+        accounts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Random cent amounts up to 1,000,000.00.
+    fn amount(rng: &mut StdRng) -> Decimal {
+        Decimal::new(rng.gen_range(0..100_000_000), 2)
+    }
+
+    #[test]
+    fn test_document_postings_always_balance() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let expense_account = AccountRef::Id(Uuid::new_v4());
+
+        for _ in 0..1000 {
+            let (subtotal, tax_total) = (amount(&mut rng), amount(&mut rng));
+            let amount_applied = Decimal::new(
+                rng.gen_range(0..=(subtotal + tax_total).mantissa() as i64),
+                2,
+            );
+
+            let documents = [
+                invoice_postings(subtotal, tax_total),
+                payment_postings(subtotal),
+                allocation_postings(tax_total, subtotal),
+                credit_note_postings(subtotal, tax_total, amount_applied),
+                expense_postings(expense_account, subtotal, tax_total),
+            ];
+
+            for postings in documents {
+                assert!(is_balanced(&postings), "{:?}", postings);
+                assert!(postings.iter().all(|posting| {
+                    !posting.debit.is_sign_negative() && !posting.credit.is_sign_negative()
+                }));
+
+                let reversal: Vec<Posting> = postings
+                    .iter()
+                    .map(|posting| Posting::credit(posting.account, posting.debit - posting.credit))
+                    .collect();
+                assert!(is_balanced(&reversal));
+            }
+        }
+    }
+
+    #[test]
+    fn test_trial_balance_of_random_journal_balances() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let accounts: Vec<AccountRef> = (0..6).map(|_| AccountRef::Id(Uuid::new_v4())).collect();
+        let mut totals: HashMap<AccountRef, (Decimal, Decimal)> = HashMap::new();
+
+        for _ in 0..500 {
+            let postings = match rng.gen_range(0..4) {
+                0 => invoice_postings(amount(&mut rng), amount(&mut rng)),
+                1 => payment_postings(amount(&mut rng)),
+                2 => allocation_postings(amount(&mut rng), amount(&mut rng)),
+                _ => expense_postings(
+                    accounts[rng.gen_range(0..accounts.len())],
+                    amount(&mut rng),
+                    amount(&mut rng),
+                ),
+            };
+
+            for posting in postings {
+                let total = totals.entry(posting.account).or_default();
+                total.0 += posting.debit;
+                total.1 += posting.credit;
+            }
+        }
+
+        let rows: Vec<(Decimal, Decimal)> = totals
+            .values()
+            .map(|(debit, credit)| net_balance(*debit, *credit))
+            .collect();
+        let total_debit: Decimal = rows.iter().map(|row| row.0).sum();
+        let total_credit: Decimal = rows.iter().map(|row| row.1).sum();
+        assert_eq!(total_debit, total_credit);
+    }
+
+    #[test]
+    fn test_negative_amounts_post_to_the_other_side() {
+        let account = AccountRef::System(SystemAccount::Cash);
+        let posting = Posting::debit(account, Decimal::new(-500, 2));
+
+        assert_eq!(posting.debit, Decimal::ZERO);
+        assert_eq!(posting.credit, Decimal::new(500, 2));
+        assert_eq!(
+            Posting::credit(account, Decimal::new(-500, 2)),
+            Posting::debit(account, Decimal::new(500, 2))
+        );
+    }
+}
@@ -1,11 +1,30 @@
+mod accounts;
 mod auth;
+mod banking;
+mod business;
 mod clients;
+mod credit_notes;
+mod currencies;
+mod estimates;
+mod expenses;
+mod invoices;
+mod jwks;
+mod ledger;
 mod middleware;
+mod numbering;
+mod payments;
+mod pdf;
+mod projects;
+mod rate_limit;
+mod recurring;
+mod reports;
 mod supabase;
+mod taxes;
+mod time_entries;
 
 use axum::{
     Json, Router,
-    extract::Extension,
+    extract::{DefaultBodyLimit, Extension},
     middleware as axum_middleware,
     routing::{delete, get, post, put},
 };
@@ -13,6 +32,7 @@ use serde_json::{Value, json};
 use sqlx::PgPool;
 use std::env;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
@@ -40,6 +60,15 @@ async fn main() {
 
     tracing::info!("Database connection established");
 
+    recurring::spawn_scheduler(pool.clone());
+
+    let keys = Arc::new(jwks::KeyStore::from_env().expect("Invalid JWT key configuration"));
+    match keys.refresh().await {
+        Ok(count) => tracing::info!("Loaded {} JWT signing keys", count),
+        Err(e) => tracing::warn!("Failed to load JWT signing keys: {}", e),
+    }
+    jwks::spawn_refresher(keys.clone());
+
     // Configure CORS based on environment
     let cors = if let Ok(allowed_origins) = env::var("ALLOWED_ORIGINS") {
         // Production: Use specific allowed origins
@@ -75,8 +104,239 @@ async fn main() {
                 .put(update_client_handler)
                 .delete(delete_client_handler),
         )
+        .route(
+            "/invoices",
+            get(list_invoices_handler).post(create_invoice_handler),
+        )
+        .route(
+            "/invoices/{id}",
+            get(get_invoice_handler)
+                .put(update_invoice_handler)
+                .delete(delete_invoice_handler),
+        )
+        .route("/clients/{id}/credit", get(get_client_credit_handler))
+        .route("/invoices/summary", get(get_receivables_summary_handler))
+        .route("/invoices/{id}/status", post(update_invoice_status_handler))
+        .route("/invoices/{id}/void", post(void_invoice_handler))
+        .route("/invoices/{id}/pdf", get(get_invoice_pdf_handler))
+        .route(
+            "/estimates",
+            get(list_estimates_handler).post(create_estimate_handler),
+        )
+        .route(
+            "/estimates/{id}",
+            get(get_estimate_handler)
+                .put(update_estimate_handler)
+                .delete(delete_estimate_handler),
+        )
+        .route(
+            "/estimates/{id}/status",
+            post(update_estimate_status_handler),
+        )
+        .route("/estimates/{id}/convert", post(convert_estimate_handler))
+        .route("/estimates/{id}/pdf", get(get_estimate_pdf_handler))
+        .route(
+            "/recurring-profiles",
+            get(list_recurring_profiles_handler).post(create_recurring_profile_handler),
+        )
+        .route(
+            "/recurring-profiles/{id}",
+            get(get_recurring_profile_handler)
+                .put(update_recurring_profile_handler)
+                .delete(delete_recurring_profile_handler),
+        )
+        .route(
+            "/credit-notes",
+            get(list_credit_notes_handler).post(create_credit_note_handler),
+        )
+        .route("/credit-notes/{id}", get(get_credit_note_handler))
+        .route("/credit-notes/{id}/pdf", get(get_credit_note_pdf_handler))
+        .route(
+            "/credit-notes/{id}/allocations",
+            post(apply_credit_note_handler),
+        )
+        .route(
+            "/payments",
+            get(list_payments_handler).post(create_payment_handler),
+        )
+        .route(
+            "/payments/{id}",
+            get(get_payment_handler).delete(delete_payment_handler),
+        )
+        .route("/payments/{id}/allocations", post(apply_payment_handler))
+        .route(
+            "/expenses",
+            get(list_expenses_handler).post(create_expense_handler),
+        )
+        .route(
+            "/expenses/{id}",
+            get(get_expense_handler)
+                .put(update_expense_handler)
+                .delete(delete_expense_handler),
+        )
+        .route("/expenses/unbilled", get(list_unbilled_expenses_handler))
+        .route("/expenses/rebill", post(rebill_expenses_handler))
+        .route(
+            "/expenses/{id}/receipt",
+            get(get_receipt_handler)
+                .post(upload_receipt_handler)
+                .delete(delete_receipt_handler)
+                // Leave room for the multipart framing around the file itself
+                .layer(DefaultBodyLimit::max(
+                    expenses::MAX_RECEIPT_SIZE + 64 * 1024,
+                )),
+        )
+        .route(
+            "/time-entries",
+            get(list_time_entries_handler).post(create_time_entry_handler),
+        )
+        .route(
+            "/time-entries/{id}",
+            get(get_time_entry_handler)
+                .put(update_time_entry_handler)
+                .delete(delete_time_entry_handler),
+        )
+        .route("/time-entries/timer", get(get_running_timer_handler))
+        .route("/time-entries/timer/start", post(start_timer_handler))
+        .route("/time-entries/timer/stop", post(stop_timer_handler))
+        .route(
+            "/time-entries/unbilled",
+            get(list_unbilled_time_entries_handler),
+        )
+        .route("/time-entries/invoice", post(invoice_time_entries_handler))
+        .route(
+            "/projects",
+            get(list_projects_handler).post(create_project_handler),
+        )
+        .route(
+            "/projects/{id}",
+            get(get_project_handler)
+                .put(update_project_handler)
+                .delete(delete_project_handler),
+        )
+        .route("/projects/{id}/summary", get(get_project_summary_handler))
+        .route(
+            "/accounts",
+            get(list_accounts_handler).post(create_account_handler),
+        )
+        .route(
+            "/accounts/{id}",
+            get(get_account_handler)
+                .put(update_account_handler)
+                .delete(delete_account_handler),
+        )
+        .route("/ledger/entries", get(list_journal_entries_handler))
+        .route("/ledger/trial-balance", get(get_trial_balance_handler))
+        .route("/reports/profit-and-loss", get(get_profit_and_loss_handler))
+        .route("/reports/balance-sheet", get(get_balance_sheet_handler))
+        .route("/reports/cash-flow", get(get_cash_flow_handler))
+        .route("/reports/ar-aging", get(get_ar_aging_handler))
+        .route("/reports/sales-tax", get(get_sales_tax_handler))
+        .route("/reports/fx-gain-loss", get(get_fx_gain_loss_handler))
+        .route(
+            "/exchange-rates",
+            get(list_exchange_rates_handler).post(create_exchange_rate_handler),
+        )
+        .route(
+            "/exchange-rates/import",
+            post(import_exchange_rates_handler)
+                // Leave room for the multipart framing around the file itself
+                .layer(DefaultBodyLimit::max(
+                    currencies::MAX_RATES_FILE_SIZE + 64 * 1024,
+                )),
+        )
+        .route("/exchange-rates/{id}", delete(delete_exchange_rate_handler))
+        .route(
+            "/bank-accounts",
+            get(list_bank_accounts_handler).post(create_bank_account_handler),
+        )
+        .route(
+            "/bank-accounts/{id}",
+            get(get_bank_account_handler)
+                .put(update_bank_account_handler)
+                .delete(delete_bank_account_handler),
+        )
+        .route(
+            "/bank-accounts/{id}/transactions",
+            get(list_bank_transactions_handler).post(create_bank_transaction_handler),
+        )
+        .route(
+            "/bank-accounts/{id}/reconciliations",
+            get(list_reconciliations_handler).post(create_reconciliation_handler),
+        )
+        .route(
+            "/bank-accounts/{id}/import",
+            post(import_statement_handler)
+                // Leave room for the multipart framing around the file itself
+                .layer(DefaultBodyLimit::max(
+                    banking::MAX_STATEMENT_SIZE + 64 * 1024,
+                )),
+        )
+        .route(
+            "/bank-transactions/{id}/matches",
+            get(suggest_matches_handler),
+        )
+        .route(
+            "/bank-transactions/{id}",
+            delete(delete_bank_transaction_handler),
+        )
+        .route(
+            "/bank-reconciliations/{id}",
+            get(get_reconciliation_handler).delete(delete_reconciliation_handler),
+        )
+        .route(
+            "/bank-reconciliations/{id}/transactions",
+            put(reconcile_transactions_handler),
+        )
+        .route(
+            "/bank-reconciliations/{id}/complete",
+            post(complete_reconciliation_handler),
+        )
+        .route(
+            "/bank-rules",
+            get(list_bank_rules_handler).post(create_bank_rule_handler),
+        )
+        .route("/bank-rules/apply", post(apply_bank_rules_handler))
+        .route(
+            "/bank-rules/{id}",
+            put(update_bank_rule_handler).delete(delete_bank_rule_handler),
+        )
+        .route(
+            "/business-profile",
+            get(get_business_profile_handler).put(upsert_business_profile_handler),
+        )
+        .route("/numbering/sequences", get(list_number_sequences_handler))
+        .route(
+            "/numbering/sequences/{document_type}",
+            put(update_number_sequence_handler),
+        )
+        .route(
+            "/numbering/sequences/{document_type}/preview",
+            get(preview_number_sequence_handler),
+        )
+        .route(
+            "/taxes/rates",
+            get(list_tax_rates_handler).post(create_tax_rate_handler),
+        )
+        .route(
+            "/taxes/rates/{id}",
+            put(update_tax_rate_handler).delete(delete_tax_rate_handler),
+        )
+        .route(
+            "/taxes/rules",
+            get(list_tax_rules_handler).post(create_tax_rule_handler),
+        )
+        .route("/taxes/rules/{id}", delete(delete_tax_rule_handler))
+        .route("/taxes/defaults", get(get_default_taxes_handler))
+        .route("/auth/signout", post(sign_out_handler))
+        .route("/auth/session", get(get_session_handler))
+        .route("/auth/update-password", post(update_password_handler))
         .layer(axum_middleware::from_fn_with_state(
-            pool.clone(),
+            middleware::AuthState {
+                pool: pool.clone(),
+                keys,
+                seeded_charts: Default::default(),
+            },
             middleware::auth_middleware,
         ));
 
@@ -86,6 +346,17 @@ async fn main() {
         .route("/auth/signup", post(auth::sign_up))
         .route("/auth/signin", post(auth::sign_in))
         .route("/auth/verify-email", post(auth::verify_email))
+        .route("/auth/refresh", post(auth::refresh))
+        .route("/auth/otp", post(auth::send_otp))
+        .route("/auth/recover", post(auth::recover))
+        .route(
+            "/auth/oauth/{provider}",
+            post(auth::start_oauth).layer(axum_middleware::from_fn_with_state(
+                rate_limit::RateLimiter::per_minute_from_env("OAUTH_START_RATE_LIMIT", 10),
+                rate_limit::limit_by_ip,
+            )),
+        )
+        .route("/auth/callback", post(auth::oauth_callback))
         .merge(protected_routes)
         .with_state(pool)
         .layer(sentry_tower::NewSentryLayer::new_from_top())
@@ -105,9 +376,12 @@ async fn main() {
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server failed to start");
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("Server failed to start");
 }
 
 async fn root() -> Json<Value> {
@@ -192,3 +466,1204 @@ async fn delete_client_handler(
 ) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
     clients::delete_client(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
 }
+
+async fn list_invoices_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<invoices::Invoice>>, (axum::http::StatusCode, Json<Value>)> {
+    invoices::list_invoices(axum::extract::State(pool), user_id).await
+}
+
+async fn create_invoice_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<invoices::CreateInvoiceRequest>,
+) -> Result<
+    (axum::http::StatusCode, Json<invoices::InvoiceWithLineItems>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    invoices::create_invoice(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_invoice_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<invoices::InvoiceWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    invoices::get_invoice(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn update_invoice_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<invoices::UpdateInvoiceRequest>,
+) -> Result<Json<invoices::InvoiceWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    invoices::update_invoice(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn update_invoice_status_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<invoices::UpdateInvoiceStatusRequest>,
+) -> Result<Json<invoices::Invoice>, (axum::http::StatusCode, Json<Value>)> {
+    invoices::update_invoice_status(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn get_invoice_pdf_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    invoices::get_invoice_pdf(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn delete_invoice_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    invoices::delete_invoice(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn list_number_sequences_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<numbering::NumberSequence>>, (axum::http::StatusCode, Json<Value>)> {
+    numbering::list_sequences(axum::extract::State(pool), user_id).await
+}
+
+async fn update_number_sequence_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(document_type): axum::extract::Path<numbering::DocumentType>,
+    Json(req): Json<numbering::UpdateSequenceRequest>,
+) -> Result<Json<numbering::NumberSequence>, (axum::http::StatusCode, Json<Value>)> {
+    numbering::update_sequence(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(document_type),
+        Json(req),
+    )
+    .await
+}
+
+async fn preview_number_sequence_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(document_type): axum::extract::Path<numbering::DocumentType>,
+) -> Result<Json<numbering::SequencePreview>, (axum::http::StatusCode, Json<Value>)> {
+    numbering::preview_sequence(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(document_type),
+    )
+    .await
+}
+
+async fn get_business_profile_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<business::BusinessProfile>, (axum::http::StatusCode, Json<Value>)> {
+    business::get_business_profile(axum::extract::State(pool), user_id).await
+}
+
+async fn upsert_business_profile_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<business::UpsertBusinessProfileRequest>,
+) -> Result<Json<business::BusinessProfile>, (axum::http::StatusCode, Json<Value>)> {
+    business::upsert_business_profile(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn list_tax_rates_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<taxes::TaxRate>>, (axum::http::StatusCode, Json<Value>)> {
+    taxes::list_tax_rates(axum::extract::State(pool), user_id).await
+}
+
+async fn create_tax_rate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<taxes::CreateTaxRateRequest>,
+) -> Result<(axum::http::StatusCode, Json<taxes::TaxRate>), (axum::http::StatusCode, Json<Value>)> {
+    taxes::create_tax_rate(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn update_tax_rate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<taxes::UpdateTaxRateRequest>,
+) -> Result<Json<taxes::TaxRate>, (axum::http::StatusCode, Json<Value>)> {
+    taxes::update_tax_rate(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_tax_rate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    taxes::delete_tax_rate(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn list_tax_rules_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<taxes::TaxRule>>, (axum::http::StatusCode, Json<Value>)> {
+    taxes::list_tax_rules(axum::extract::State(pool), user_id).await
+}
+
+async fn create_tax_rule_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<taxes::CreateTaxRuleRequest>,
+) -> Result<(axum::http::StatusCode, Json<taxes::TaxRule>), (axum::http::StatusCode, Json<Value>)> {
+    taxes::create_tax_rule(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn delete_tax_rule_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    taxes::delete_tax_rule(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn get_default_taxes_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<taxes::DefaultTaxesQuery>,
+) -> Result<Json<Vec<taxes::TaxRate>>, (axum::http::StatusCode, Json<Value>)> {
+    taxes::get_default_taxes(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn list_payments_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<payments::Payment>>, (axum::http::StatusCode, Json<Value>)> {
+    payments::list_payments(axum::extract::State(pool), user_id).await
+}
+
+async fn create_payment_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<payments::CreatePaymentRequest>,
+) -> Result<
+    (
+        axum::http::StatusCode,
+        Json<payments::PaymentWithAllocations>,
+    ),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    payments::create_payment(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_payment_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<payments::PaymentWithAllocations>, (axum::http::StatusCode, Json<Value>)> {
+    payments::get_payment(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn apply_payment_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<payments::ApplyPaymentRequest>,
+) -> Result<Json<payments::PaymentWithAllocations>, (axum::http::StatusCode, Json<Value>)> {
+    payments::apply_payment(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_payment_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    payments::delete_payment(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn get_client_credit_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<payments::ClientCreditQuery>,
+) -> Result<Json<payments::ClientCredit>, (axum::http::StatusCode, Json<Value>)> {
+    payments::get_client_credit(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn list_estimates_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<estimates::Estimate>>, (axum::http::StatusCode, Json<Value>)> {
+    estimates::list_estimates(axum::extract::State(pool), user_id).await
+}
+
+async fn create_estimate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<estimates::CreateEstimateRequest>,
+) -> Result<
+    (
+        axum::http::StatusCode,
+        Json<estimates::EstimateWithLineItems>,
+    ),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    estimates::create_estimate(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_estimate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<estimates::EstimateWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    estimates::get_estimate(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn update_estimate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<estimates::UpdateEstimateRequest>,
+) -> Result<Json<estimates::EstimateWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    estimates::update_estimate(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn update_estimate_status_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<estimates::UpdateEstimateStatusRequest>,
+) -> Result<Json<estimates::Estimate>, (axum::http::StatusCode, Json<Value>)> {
+    estimates::update_estimate_status(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_estimate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    estimates::delete_estimate(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn convert_estimate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<
+    (axum::http::StatusCode, Json<invoices::InvoiceWithLineItems>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    estimates::convert_estimate(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn get_estimate_pdf_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    estimates::get_estimate_pdf(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn list_recurring_profiles_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<recurring::RecurringProfile>>, (axum::http::StatusCode, Json<Value>)> {
+    recurring::list_recurring_profiles(axum::extract::State(pool), user_id).await
+}
+
+async fn create_recurring_profile_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<recurring::CreateRecurringProfileRequest>,
+) -> Result<
+    (
+        axum::http::StatusCode,
+        Json<recurring::RecurringProfileWithLineItems>,
+    ),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    recurring::create_recurring_profile(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_recurring_profile_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<recurring::RecurringProfileWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    recurring::get_recurring_profile(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn update_recurring_profile_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<recurring::UpdateRecurringProfileRequest>,
+) -> Result<Json<recurring::RecurringProfileWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    recurring::update_recurring_profile(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_recurring_profile_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    recurring::delete_recurring_profile(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+    )
+    .await
+}
+
+async fn void_invoice_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<invoices::VoidInvoiceRequest>,
+) -> Result<Json<invoices::Invoice>, (axum::http::StatusCode, Json<Value>)> {
+    invoices::void_invoice(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn get_receivables_summary_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<invoices::ReceivablesSummary>, (axum::http::StatusCode, Json<Value>)> {
+    invoices::get_receivables_summary(axum::extract::State(pool), user_id).await
+}
+
+async fn list_credit_notes_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<credit_notes::CreditNote>>, (axum::http::StatusCode, Json<Value>)> {
+    credit_notes::list_credit_notes(axum::extract::State(pool), user_id).await
+}
+
+async fn create_credit_note_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<credit_notes::CreateCreditNoteRequest>,
+) -> Result<
+    (
+        axum::http::StatusCode,
+        Json<credit_notes::CreditNoteWithLineItems>,
+    ),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    credit_notes::create_credit_note(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_credit_note_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<credit_notes::CreditNoteWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    credit_notes::get_credit_note(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn apply_credit_note_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<credit_notes::ApplyCreditNoteRequest>,
+) -> Result<Json<credit_notes::CreditNoteWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    credit_notes::apply_credit_note(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn get_credit_note_pdf_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    credit_notes::get_credit_note_pdf(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn list_expenses_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<expenses::Expense>>, (axum::http::StatusCode, Json<Value>)> {
+    expenses::list_expenses(axum::extract::State(pool), user_id).await
+}
+
+async fn create_expense_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<expenses::CreateExpenseRequest>,
+) -> Result<(axum::http::StatusCode, Json<expenses::Expense>), (axum::http::StatusCode, Json<Value>)>
+{
+    expenses::create_expense(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_expense_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<expenses::Expense>, (axum::http::StatusCode, Json<Value>)> {
+    expenses::get_expense(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn update_expense_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<expenses::UpdateExpenseRequest>,
+) -> Result<Json<expenses::Expense>, (axum::http::StatusCode, Json<Value>)> {
+    expenses::update_expense(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_expense_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Extension(access_token): Extension<middleware::AccessToken>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    expenses::delete_expense(
+        axum::extract::State(pool),
+        user_id,
+        access_token,
+        axum::extract::Path(id),
+    )
+    .await
+}
+
+async fn list_unbilled_expenses_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<expenses::UnbilledExpensesQuery>,
+) -> Result<Json<Vec<expenses::Expense>>, (axum::http::StatusCode, Json<Value>)> {
+    expenses::list_unbilled_expenses(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn rebill_expenses_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<expenses::RebillExpensesRequest>,
+) -> Result<Json<invoices::InvoiceWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    expenses::rebill_expenses(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn upload_receipt_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Extension(access_token): Extension<middleware::AccessToken>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    multipart: axum::extract::Multipart,
+) -> Result<
+    (axum::http::StatusCode, Json<expenses::ReceiptResponse>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    expenses::upload_receipt(
+        axum::extract::State(pool),
+        user_id,
+        access_token,
+        axum::extract::Path(id),
+        multipart,
+    )
+    .await
+}
+
+async fn get_receipt_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Extension(access_token): Extension<middleware::AccessToken>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<expenses::ReceiptResponse>, (axum::http::StatusCode, Json<Value>)> {
+    expenses::get_receipt(
+        axum::extract::State(pool),
+        user_id,
+        access_token,
+        axum::extract::Path(id),
+    )
+    .await
+}
+
+async fn delete_receipt_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Extension(access_token): Extension<middleware::AccessToken>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    expenses::delete_receipt(
+        axum::extract::State(pool),
+        user_id,
+        access_token,
+        axum::extract::Path(id),
+    )
+    .await
+}
+
+async fn list_time_entries_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<time_entries::TimeEntry>>, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::list_time_entries(axum::extract::State(pool), user_id).await
+}
+
+async fn create_time_entry_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<time_entries::CreateTimeEntryRequest>,
+) -> Result<
+    (axum::http::StatusCode, Json<time_entries::TimeEntry>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    time_entries::create_time_entry(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_time_entry_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<time_entries::TimeEntry>, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::get_time_entry(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn update_time_entry_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<time_entries::UpdateTimeEntryRequest>,
+) -> Result<Json<time_entries::TimeEntry>, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::update_time_entry(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_time_entry_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::delete_time_entry(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn get_running_timer_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Option<time_entries::TimeEntry>>, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::get_running_timer(axum::extract::State(pool), user_id).await
+}
+
+async fn start_timer_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<time_entries::StartTimerRequest>,
+) -> Result<
+    (axum::http::StatusCode, Json<time_entries::TimeEntry>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    time_entries::start_timer(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn stop_timer_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<time_entries::TimeEntry>, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::stop_timer(axum::extract::State(pool), user_id).await
+}
+
+async fn list_unbilled_time_entries_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<time_entries::UnbilledTimeQuery>,
+) -> Result<Json<Vec<time_entries::TimeEntry>>, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::list_unbilled_time_entries(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn invoice_time_entries_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<time_entries::InvoiceTimeEntriesRequest>,
+) -> Result<Json<invoices::InvoiceWithLineItems>, (axum::http::StatusCode, Json<Value>)> {
+    time_entries::invoice_time_entries(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn list_projects_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<projects::Project>>, (axum::http::StatusCode, Json<Value>)> {
+    projects::list_projects(axum::extract::State(pool), user_id).await
+}
+
+async fn create_project_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<projects::CreateProjectRequest>,
+) -> Result<(axum::http::StatusCode, Json<projects::Project>), (axum::http::StatusCode, Json<Value>)>
+{
+    projects::create_project(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_project_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<projects::Project>, (axum::http::StatusCode, Json<Value>)> {
+    projects::get_project(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn update_project_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<projects::UpdateProjectRequest>,
+) -> Result<Json<projects::Project>, (axum::http::StatusCode, Json<Value>)> {
+    projects::update_project(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_project_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    projects::delete_project(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn get_project_summary_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<projects::ProjectSummary>, (axum::http::StatusCode, Json<Value>)> {
+    projects::get_project_summary(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn list_journal_entries_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<ledger::JournalEntriesQuery>,
+) -> Result<Json<Vec<ledger::JournalEntryWithLines>>, (axum::http::StatusCode, Json<Value>)> {
+    ledger::list_journal_entries(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn get_trial_balance_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<ledger::TrialBalanceQuery>,
+) -> Result<Json<ledger::TrialBalance>, (axum::http::StatusCode, Json<Value>)> {
+    ledger::get_trial_balance(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn list_accounts_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<accounts::Account>>, (axum::http::StatusCode, Json<Value>)> {
+    accounts::list_accounts(axum::extract::State(pool), user_id).await
+}
+
+async fn create_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<accounts::CreateAccountRequest>,
+) -> Result<(axum::http::StatusCode, Json<accounts::Account>), (axum::http::StatusCode, Json<Value>)>
+{
+    accounts::create_account(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<accounts::Account>, (axum::http::StatusCode, Json<Value>)> {
+    accounts::get_account(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn update_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<accounts::UpdateAccountRequest>,
+) -> Result<Json<accounts::Account>, (axum::http::StatusCode, Json<Value>)> {
+    accounts::update_account(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    accounts::delete_account(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn get_profit_and_loss_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<reports::ReportQuery>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    reports::get_profit_and_loss(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn get_balance_sheet_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<reports::ReportQuery>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    reports::get_balance_sheet(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn get_cash_flow_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<reports::ReportQuery>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    reports::get_cash_flow(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn get_ar_aging_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<reports::AgingQuery>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    reports::get_ar_aging(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn get_sales_tax_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<reports::SalesTaxQuery>,
+) -> Result<axum::response::Response, (axum::http::StatusCode, Json<Value>)> {
+    reports::get_sales_tax(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn get_fx_gain_loss_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<reports::FxGainLossQuery>,
+) -> Result<Json<reports::FxGainLossReport>, (axum::http::StatusCode, Json<Value>)> {
+    reports::get_fx_gain_loss(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn list_exchange_rates_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<currencies::ExchangeRatesQuery>,
+) -> Result<Json<Vec<currencies::ExchangeRate>>, (axum::http::StatusCode, Json<Value>)> {
+    currencies::list_exchange_rates(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn create_exchange_rate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<currencies::CreateExchangeRateRequest>,
+) -> Result<
+    (axum::http::StatusCode, Json<currencies::ExchangeRate>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    currencies::create_exchange_rate(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn delete_exchange_rate_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    currencies::delete_exchange_rate(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn import_exchange_rates_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    multipart: axum::extract::Multipart,
+) -> Result<Json<currencies::ImportSummary>, (axum::http::StatusCode, Json<Value>)> {
+    currencies::import_exchange_rates(axum::extract::State(pool), user_id, multipart).await
+}
+
+async fn list_bank_accounts_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<banking::BankAccount>>, (axum::http::StatusCode, Json<Value>)> {
+    banking::list_bank_accounts(axum::extract::State(pool), user_id).await
+}
+
+async fn create_bank_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<banking::CreateBankAccountRequest>,
+) -> Result<
+    (axum::http::StatusCode, Json<banking::BankAccount>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    banking::create_bank_account(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn get_bank_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<banking::BankAccount>, (axum::http::StatusCode, Json<Value>)> {
+    banking::get_bank_account(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn update_bank_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<banking::UpdateBankAccountRequest>,
+) -> Result<Json<banking::BankAccount>, (axum::http::StatusCode, Json<Value>)> {
+    banking::update_bank_account(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_bank_account_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    banking::delete_bank_account(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn list_bank_transactions_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<banking::BankTransactionsQuery>,
+) -> Result<Json<Vec<banking::BankTransaction>>, (axum::http::StatusCode, Json<Value>)> {
+    banking::list_bank_transactions(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        axum::extract::Query(query),
+    )
+    .await
+}
+
+async fn import_statement_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    multipart: axum::extract::Multipart,
+) -> Result<Json<banking::StatementImportSummary>, (axum::http::StatusCode, Json<Value>)> {
+    banking::import_statement(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        multipart,
+    )
+    .await
+}
+
+async fn suggest_matches_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<Vec<banking::MatchSuggestion>>, (axum::http::StatusCode, Json<Value>)> {
+    banking::suggest_matches(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn create_bank_transaction_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<banking::CreateBankTransactionRequest>,
+) -> Result<
+    (axum::http::StatusCode, Json<banking::BankTransaction>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    banking::create_bank_transaction(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_bank_transaction_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    banking::delete_bank_transaction(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn list_reconciliations_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<Vec<banking::BankReconciliation>>, (axum::http::StatusCode, Json<Value>)> {
+    banking::list_reconciliations(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn create_reconciliation_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<banking::CreateReconciliationRequest>,
+) -> Result<
+    (axum::http::StatusCode, Json<banking::ReconciliationDetails>),
+    (axum::http::StatusCode, Json<Value>),
+> {
+    banking::create_reconciliation(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn get_reconciliation_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<banking::ReconciliationDetails>, (axum::http::StatusCode, Json<Value>)> {
+    banking::get_reconciliation(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn reconcile_transactions_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<banking::ReconcileTransactionsRequest>,
+) -> Result<Json<banking::ReconciliationDetails>, (axum::http::StatusCode, Json<Value>)> {
+    banking::reconcile_transactions(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn complete_reconciliation_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<Json<banking::ReconciliationDetails>, (axum::http::StatusCode, Json<Value>)> {
+    banking::complete_reconciliation(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn delete_reconciliation_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    banking::delete_reconciliation(axum::extract::State(pool), user_id, axum::extract::Path(id))
+        .await
+}
+
+async fn list_bank_rules_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<banking::BankRule>>, (axum::http::StatusCode, Json<Value>)> {
+    banking::list_bank_rules(axum::extract::State(pool), user_id).await
+}
+
+async fn create_bank_rule_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(req): Json<banking::CreateBankRuleRequest>,
+) -> Result<(axum::http::StatusCode, Json<banking::BankRule>), (axum::http::StatusCode, Json<Value>)>
+{
+    banking::create_bank_rule(axum::extract::State(pool), user_id, Json(req)).await
+}
+
+async fn update_bank_rule_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Json(req): Json<banking::UpdateBankRuleRequest>,
+) -> Result<Json<banking::BankRule>, (axum::http::StatusCode, Json<Value>)> {
+    banking::update_bank_rule(
+        axum::extract::State(pool),
+        user_id,
+        axum::extract::Path(id),
+        Json(req),
+    )
+    .await
+}
+
+async fn delete_bank_rule_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> Result<axum::http::StatusCode, (axum::http::StatusCode, Json<Value>)> {
+    banking::delete_bank_rule(axum::extract::State(pool), user_id, axum::extract::Path(id)).await
+}
+
+async fn apply_bank_rules_handler(
+    axum::extract::State(pool): axum::extract::State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<banking::BankTransaction>>, (axum::http::StatusCode, Json<Value>)> {
+    banking::apply_bank_rules(axum::extract::State(pool), user_id).await
+}
+
+async fn sign_out_handler(
+    Extension(access_token): Extension<middleware::AccessToken>,
+    axum::extract::Query(query): axum::extract::Query<auth::SignOutQuery>,
+) -> Result<axum::http::StatusCode, auth::AuthError> {
+    auth::sign_out(access_token, query.scope).await
+}
+
+async fn get_session_handler(
+    Extension(access_token): Extension<middleware::AccessToken>,
+    Extension(claims): Extension<middleware::Claims>,
+) -> Result<Json<supabase::auth::Session>, auth::AuthError> {
+    auth::get_session(access_token, claims).await
+}
+
+async fn update_password_handler(
+    Extension(access_token): Extension<middleware::AccessToken>,
+    Json(req): Json<auth::UpdatePasswordRequest>,
+) -> Result<Json<supabase::auth::User>, auth::AuthError> {
+    auth::update_password(access_token, Json(req)).await
+}
@@ -4,22 +4,39 @@ use axum::{
     middleware::Next,
     response::{Json, Response},
 };
-use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
-use std::env;
+use std::collections::HashSet;
+use std::sync::{Arc, PoisonError, RwLock};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::accounts;
+use crate::jwks::{JwksError, KeyStore};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub email: String,
     pub exp: usize,
 }
 
+/// Raw bearer token of the authenticated request, for calls made to Supabase on the user's behalf.
+#[derive(Debug, Clone)]
+pub struct AccessToken(pub String);
+
+/// State shared by the authentication middleware.
+#[derive(Clone)]
+pub struct AuthState {
+    pub pool: PgPool,
+    pub keys: Arc<KeyStore>,
+    /// Users whose chart of accounts this process has already made sure of,
+    /// so seeding costs no database round trip after their first request.
+    pub seeded_charts: Arc<RwLock<HashSet<Uuid>>>,
+}
+
 pub async fn auth_middleware(
-    State(_pool): State<PgPool>,
+    State(state): State<AuthState>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
@@ -41,45 +58,35 @@ pub async fn auth_middleware(
         )
     })?;
 
-    let jwk_json = env::var("SUPABASE_JWT_JWK").map_err(|_| {
-        tracing::error!("SUPABASE_JWT_JWK not set");
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Server configuration error" })),
-        )
-    })?;
-
-    // Supabase uses ES256 algorithm with JWK format
-    let mut validation = Validation::new(Algorithm::ES256);
-    validation.set_audience(&["authenticated"]);
-
-    let jwk: serde_json::Value = serde_json::from_str(&jwk_json).map_err(|e| {
-        tracing::error!("Failed to parse JWK: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Server configuration error" })),
-        )
-    })?;
-
-    let decoding_key = DecodingKey::from_ec_components(
-        jwk["x"].as_str().unwrap_or(""),
-        jwk["y"].as_str().unwrap_or(""),
-    )
-    .map_err(|e| {
-        tracing::error!("Failed to create decoding key from JWK components: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Server configuration error" })),
-        )
-    })?;
+    let access_token = AccessToken(token.to_string());
 
-    let token_data = decode::<Claims>(token, &decoding_key, &validation).map_err(|e| {
-        tracing::warn!("JWT validation failed: {}", e);
-        (
-            StatusCode::UNAUTHORIZED,
-            Json(json!({ "error": "Invalid or expired token" })),
-        )
-    })?;
+    let token_data = state
+        .keys
+        .verify::<Claims>(token, "authenticated")
+        .await
+        .map_err(|e| match e {
+            JwksError::Config(_) => {
+                tracing::error!("JWT verification misconfigured: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Server configuration error" })),
+                )
+            }
+            JwksError::Fetch(_) | JwksError::Parse(_) => {
+                tracing::error!("Failed to load JWT signing keys: {}", e);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(json!({ "error": "Unable to verify token" })),
+                )
+            }
+            _ => {
+                tracing::warn!("JWT validation failed: {}", e);
+                (
+                    StatusCode::UNAUTHORIZED,
+                    Json(json!({ "error": "Invalid or expired token" })),
+                )
+            }
+        })?;
 
     let user_id = Uuid::parse_str(&token_data.claims.sub).map_err(|_| {
         (
@@ -88,7 +95,23 @@ pub async fn auth_middleware(
         )
     })?;
 
+    let seeded = state
+        .seeded_charts
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&user_id);
+    if !seeded {
+        accounts::ensure_default_chart(&state.pool, user_id).await?;
+        state
+            .seeded_charts
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(user_id);
+    }
+
     req.extensions_mut().insert(user_id);
+    req.extensions_mut().insert(access_token);
+    req.extensions_mut().insert(token_data.claims);
 
     Ok(next.run(req).await)
 }
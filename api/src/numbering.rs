@@ -0,0 +1,422 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+/// Document types that draw their numbers from a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentType {
+    Invoice,
+    Estimate,
+    CreditNote,
+}
+
+impl DocumentType {
+    pub const ALL: [DocumentType; 3] = [
+        DocumentType::Invoice,
+        DocumentType::Estimate,
+        DocumentType::CreditNote,
+    ];
+
+    pub fn default_prefix(&self) -> &'static str {
+        match self {
+            DocumentType::Invoice => "INV",
+            DocumentType::Estimate => "EST",
+            DocumentType::CreditNote => "CN",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct NumberSequence {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub document_type: DocumentType,
+    pub prefix: String,
+    pub padding: i16,
+    pub include_year: bool,
+    pub yearly_reset: bool,
+    pub current_year: i32,
+    pub last_value: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl NumberSequence {
+    /// The year and value the next allocation will use in `year`.
+    fn next_position(&self, year: i32) -> (i32, i64) {
+        if self.yearly_reset && year != self.current_year {
+            (year, 1)
+        } else {
+            (year, self.last_value + 1)
+        }
+    }
+
+    fn format(&self, year: i32, value: i64) -> String {
+        format_number(&self.prefix, self.include_year, self.padding, year, value)
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateSequenceRequest {
+    #[validate(length(max = 20))]
+    pub prefix: Option<String>,
+    #[validate(range(min = 1, max = 10))]
+    pub padding: Option<i16>,
+    pub include_year: Option<bool>,
+    pub yearly_reset: Option<bool>,
+    /// Starting value, e.g. to continue numbering from a previous system.
+    #[validate(range(min = 1))]
+    pub next_value: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SequencePreview {
+    pub document_type: DocumentType,
+    pub next_number: String,
+}
+
+/// Checks that a sequence format can never produce the same number twice.
+///
+/// Restarting at 1 each year only yields unique numbers when the year is part of
+/// the number.
+fn validate_format(include_year: bool, yearly_reset: bool) -> Result<(), &'static str> {
+    if yearly_reset && !include_year {
+        return Err(
+            "Numbers that restart every year must include the year; disable the yearly reset or include the year",
+        );
+    }
+    Ok(())
+}
+
+/// Formats a document number such as `INV-2026-0042`.
+pub fn format_number(
+    prefix: &str,
+    include_year: bool,
+    padding: i16,
+    year: i32,
+    value: i64,
+) -> String {
+    let mut parts = Vec::with_capacity(3);
+
+    if !prefix.is_empty() {
+        parts.push(prefix.to_string());
+    }
+    if include_year {
+        parts.push(year.to_string());
+    }
+    parts.push(format!("{:0width$}", value, width = padding as usize));
+
+    parts.join("-")
+}
+
+fn current_year() -> i32 {
+    chrono::Utc::now().year()
+}
+
+/// Locks the user's sequence for `document_type`, creating it with defaults first if needed.
+async fn lock_sequence(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    document_type: DocumentType,
+) -> Result<NumberSequence, (StatusCode, Json<Value>)> {
+    sqlx::query(
+        r#"
+        INSERT INTO number_sequences (user_id, document_type, prefix, current_year)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, document_type) DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(document_type)
+    .bind(document_type.default_prefix())
+    .bind(current_year())
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create number sequence: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to allocate document number" })),
+        )
+    })?;
+
+    sqlx::query_as::<_, NumberSequence>(
+        "SELECT * FROM number_sequences WHERE user_id = $1 AND document_type = $2 FOR UPDATE",
+    )
+    .bind(user_id)
+    .bind(document_type)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to lock number sequence: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to allocate document number" })),
+        )
+    })
+}
+
+/// Allocates the next number for a document.
+///
+/// Must run in the same transaction that inserts the document: the sequence row
+/// stays locked until commit, so concurrent creates are serialized and a rollback
+/// returns the number to the sequence.
+pub(crate) async fn next_number(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    document_type: DocumentType,
+) -> Result<String, (StatusCode, Json<Value>)> {
+    let sequence = lock_sequence(conn, user_id, document_type).await?;
+    let (year, value) = sequence.next_position(current_year());
+
+    sqlx::query("UPDATE number_sequences SET current_year = $1, last_value = $2 WHERE id = $3")
+        .bind(year)
+        .bind(value)
+        .bind(sequence.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to advance number sequence: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to allocate document number" })),
+            )
+        })?;
+
+    Ok(sequence.format(year, value))
+}
+
+/// Hands `number` back to its sequence when the document holding it is deleted.
+///
+/// Only the most recently allocated number can be released; returns `false` for any
+/// other number, since deleting that document would leave a gap.
+pub(crate) async fn release_number(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    document_type: DocumentType,
+    number: &str,
+) -> Result<bool, (StatusCode, Json<Value>)> {
+    let sequence = lock_sequence(conn, user_id, document_type).await?;
+
+    if sequence.last_value == 0
+        || sequence.format(sequence.current_year, sequence.last_value) != number
+    {
+        return Ok(false);
+    }
+
+    sqlx::query("UPDATE number_sequences SET last_value = last_value - 1 WHERE id = $1")
+        .bind(sequence.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to release document number: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to release document number" })),
+            )
+        })?;
+
+    Ok(true)
+}
+
+pub async fn list_sequences(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<NumberSequence>>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch number sequences" })),
+        )
+    })?;
+
+    let mut sequences = Vec::with_capacity(DocumentType::ALL.len());
+    for document_type in DocumentType::ALL {
+        sequences.push(lock_sequence(&mut tx, user_id, document_type).await?);
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit number sequences: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch number sequences" })),
+        )
+    })?;
+
+    Ok(Json(sequences))
+}
+
+pub async fn update_sequence(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(document_type): Path<DocumentType>,
+    Json(req): Json<UpdateSequenceRequest>,
+) -> Result<Json<NumberSequence>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update number sequence" })),
+        )
+    })?;
+
+    let existing = lock_sequence(&mut tx, user_id, document_type).await?;
+
+    validate_format(
+        req.include_year.unwrap_or(existing.include_year),
+        req.yearly_reset.unwrap_or(existing.yearly_reset),
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
+    let (current_year, last_value) = match req.next_value {
+        // Skipping ahead after numbers were issued this period would leave a gap
+        Some(_) if existing.next_position(current_year()).1 > 1 => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "The starting number can only be changed before any number is issued in the current period"
+                })),
+            ));
+        }
+        Some(next_value) => (current_year(), next_value - 1),
+        None => (existing.current_year, existing.last_value),
+    };
+
+    let sequence = sqlx::query_as::<_, NumberSequence>(
+        r#"
+        UPDATE number_sequences
+        SET
+            prefix = COALESCE($1, prefix),
+            padding = COALESCE($2, padding),
+            include_year = COALESCE($3, include_year),
+            yearly_reset = COALESCE($4, yearly_reset),
+            current_year = $5,
+            last_value = $6,
+            updated_at = NOW()
+        WHERE id = $7
+        RETURNING *
+        "#,
+    )
+    .bind(req.prefix.map(|prefix| prefix.trim().to_string()))
+    .bind(req.padding)
+    .bind(req.include_year)
+    .bind(req.yearly_reset)
+    .bind(current_year)
+    .bind(last_value)
+    .bind(existing.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update number sequence: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update number sequence" })),
+        )
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit number sequence: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update number sequence" })),
+        )
+    })?;
+
+    Ok(Json(sequence))
+}
+
+pub async fn preview_sequence(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(document_type): Path<DocumentType>,
+) -> Result<Json<SequencePreview>, (StatusCode, Json<Value>)> {
+    let sequence = sqlx::query_as::<_, NumberSequence>(
+        "SELECT * FROM number_sequences WHERE user_id = $1 AND document_type = $2",
+    )
+    .bind(user_id)
+    .bind(document_type)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch number sequence: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch number sequence" })),
+        )
+    })?;
+
+    let year = current_year();
+    let next_number = match sequence {
+        Some(sequence) => {
+            let (year, value) = sequence.next_position(year);
+            sequence.format(year, value)
+        }
+        None => format_number(document_type.default_prefix(), true, 4, year, 1),
+    };
+
+    Ok(Json(SequencePreview {
+        document_type,
+        next_number,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(yearly_reset: bool, current_year: i32, last_value: i64) -> NumberSequence {
+        NumberSequence {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            document_type: DocumentType::Invoice,
+            prefix: "INV".to_string(),
+            padding: 4,
+            include_year: true,
+            yearly_reset,
+            current_year,
+            last_value,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number("INV", true, 4, 2026, 42), "INV-2026-0042");
+        assert_eq!(format_number("INV", false, 6, 2026, 42), "INV-000042");
+        assert_eq!(format_number("", true, 2, 2026, 123), "2026-123");
+    }
+
+    #[test]
+    fn test_next_position_resets_yearly() {
+        assert_eq!(sequence(true, 2025, 17).next_position(2026), (2026, 1));
+        assert_eq!(sequence(true, 2026, 17).next_position(2026), (2026, 18));
+        assert_eq!(sequence(false, 2025, 17).next_position(2026), (2026, 18));
+    }
+
+    #[test]
+    fn test_yearly_reset_requires_year_in_number() {
+        assert!(validate_format(false, true).is_err());
+        assert!(validate_format(false, false).is_ok());
+        assert!(validate_format(true, true).is_ok());
+        assert!(validate_format(true, false).is_ok());
+    }
+}
@@ -0,0 +1,632 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::clients;
+use crate::currencies::{self, validate_currency};
+use crate::invoices::{self, InvoiceStatus, round_money, validate_positive};
+use crate::ledger::{self, SourceType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentMethod {
+    Cash,
+    Check,
+    BankTransfer,
+    CreditCard,
+    Other,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Payment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Uuid,
+    pub amount: Decimal,
+    pub payment_date: NaiveDate,
+    pub method: PaymentMethod,
+    pub reference: Option<String>,
+    pub note: Option<String>,
+    pub unapplied_amount: Decimal,
+    pub currency: String,
+    /// Home currency value of one unit of `currency` on the payment date.
+    pub exchange_rate: Decimal,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Payment {
+    /// Home currency value of allocating `amount` more of the payment.
+    fn allocated_home(&self, amount: Decimal) -> Decimal {
+        currencies::settled_home(
+            self.amount,
+            currencies::to_home(self.amount, self.exchange_rate),
+            self.exchange_rate,
+            self.amount - self.unapplied_amount,
+            amount,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PaymentAllocation {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub invoice_id: Uuid,
+    pub amount: Decimal,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentWithAllocations {
+    #[serde(flatten)]
+    pub payment: Payment,
+    pub allocations: Vec<PaymentAllocation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClientCredit {
+    pub client_id: Uuid,
+    pub currency: String,
+    pub available_credit: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientCreditQuery {
+    /// Defaults to the client's currency, then the home currency.
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AllocationRequest {
+    pub invoice_id: Uuid,
+    #[validate(custom(function = "validate_positive"))]
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreatePaymentRequest {
+    pub client_id: Uuid,
+    #[validate(custom(function = "validate_positive"))]
+    pub amount: Decimal,
+    pub payment_date: Option<NaiveDate>,
+    pub method: PaymentMethod,
+    #[validate(length(max = 100))]
+    pub reference: Option<String>,
+    pub note: Option<String>,
+    /// Defaults to the client's currency, then the home currency.
+    #[validate(custom(function = "validate_currency"))]
+    pub currency: Option<String>,
+    /// Fixes the exchange rate instead of looking it up on the payment date.
+    #[validate(custom(function = "validate_positive"))]
+    pub exchange_rate: Option<Decimal>,
+    /// Invoices settled by this payment; anything left over becomes client credit.
+    #[validate(nested)]
+    #[serde(default)]
+    pub allocations: Vec<AllocationRequest>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApplyPaymentRequest {
+    #[validate(
+        length(min = 1, message = "At least one allocation is required"),
+        nested
+    )]
+    pub allocations: Vec<AllocationRequest>,
+}
+
+async fn lock_payment(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Payment, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1 AND user_id = $2 FOR UPDATE")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch payment: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch payment" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Payment not found" })),
+            )
+        })
+}
+
+async fn fetch_allocations(
+    conn: &mut PgConnection,
+    payment_id: Uuid,
+) -> Result<Vec<PaymentAllocation>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, PaymentAllocation>(
+        "SELECT * FROM payment_allocations WHERE payment_id = $1 ORDER BY created_at",
+    )
+    .bind(payment_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch payment allocations: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch payment allocations" })),
+        )
+    })
+}
+
+/// Allocates part of a locked payment's unapplied amount to invoices of the
+/// same client and currency, then refreshes every affected balance from the
+/// allocation sums. The allocated credit is posted to the ledger on
+/// `entry_date`, with any exchange difference between the payment and the
+/// invoices realised as a gain or loss.
+async fn apply_allocations(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    payment: &Payment,
+    allocations: &[AllocationRequest],
+    entry_date: NaiveDate,
+) -> Result<Payment, (StatusCode, Json<Value>)> {
+    let mut allocations: Vec<(Uuid, Decimal)> = allocations
+        .iter()
+        .map(|allocation| (allocation.invoice_id, round_money(allocation.amount)))
+        .collect();
+    // Lock invoices in a stable order so concurrent payments cannot deadlock
+    allocations.sort_by_key(|(invoice_id, _)| *invoice_id);
+
+    if allocations.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Each invoice can only be allocated once per payment" })),
+        ));
+    }
+
+    let allocated: Decimal = allocations.iter().map(|(_, amount)| *amount).sum();
+    if allocated > payment.unapplied_amount {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Allocations exceed the unapplied payment amount" })),
+        ));
+    }
+
+    let mut receivable_settled = Decimal::ZERO;
+    for (invoice_id, amount) in &allocations {
+        let invoice = invoices::lock_invoice(conn, user_id, *invoice_id).await?;
+
+        if invoice.client_id != payment.client_id {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(
+                    json!({ "error": "Payments can only be applied to the same client's invoices" }),
+                ),
+            ));
+        }
+
+        if invoice.currency != payment.currency {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!(
+                        "A {} payment cannot be applied to a {} invoice",
+                        payment.currency, invoice.currency
+                    )
+                })),
+            ));
+        }
+
+        if !matches!(
+            invoice.status,
+            InvoiceStatus::Sent | InvoiceStatus::PartiallyPaid
+        ) {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": format!(
+                        "Cannot apply a payment to a {} invoice",
+                        invoice.status.as_str()
+                    )
+                })),
+            ));
+        }
+
+        if *amount > invoice.balance_due {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Allocation exceeds the invoice balance due" })),
+            ));
+        }
+        receivable_settled += invoice.settled_home(*amount);
+
+        sqlx::query(
+            r#"
+            INSERT INTO payment_allocations (payment_id, invoice_id, amount)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (payment_id, invoice_id)
+            DO UPDATE SET amount = payment_allocations.amount + EXCLUDED.amount
+            "#,
+        )
+        .bind(payment.id)
+        .bind(invoice_id)
+        .bind(amount)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save payment allocation: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to apply payment" })),
+            )
+        })?;
+
+        invoices::refresh_balance(conn, *invoice_id).await?;
+    }
+
+    ledger::post(
+        conn,
+        user_id,
+        (SourceType::Payment, payment.id),
+        entry_date,
+        "Payment applied to invoices",
+        &ledger::allocation_postings(payment.allocated_home(allocated), receivable_settled),
+    )
+    .await?;
+
+    refresh_unapplied(conn, payment.id).await
+}
+
+/// Recomputes a payment's unapplied amount from its allocations.
+async fn refresh_unapplied(
+    conn: &mut PgConnection,
+    payment_id: Uuid,
+) -> Result<Payment, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Payment>(
+        r#"
+        UPDATE payments
+        SET
+            unapplied_amount = amount - COALESCE(
+                (SELECT SUM(a.amount) FROM payment_allocations a WHERE a.payment_id = payments.id),
+                0
+            ),
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(payment_id)
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update payment: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply payment" })),
+        )
+    })
+}
+
+pub async fn list_payments(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<Payment>>, (StatusCode, Json<Value>)> {
+    let payments = sqlx::query_as::<_, Payment>(
+        "SELECT * FROM payments WHERE user_id = $1 ORDER BY payment_date DESC, created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch payments: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch payments" })),
+        )
+    })?;
+
+    Ok(Json(payments))
+}
+
+pub async fn create_payment(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreatePaymentRequest>,
+) -> Result<(StatusCode, Json<PaymentWithAllocations>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to record payment" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut tx, user_id, req.client_id).await?;
+    let currency = currencies::document_currency(&mut tx, user_id, &client, req.currency).await?;
+    let payment_date = req
+        .payment_date
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let exchange_rate =
+        currencies::document_rate(&mut tx, user_id, &currency, req.exchange_rate, payment_date)
+            .await?;
+
+    let amount = round_money(req.amount);
+    let payment = sqlx::query_as::<_, Payment>(
+        r#"
+        INSERT INTO payments (
+            user_id, client_id, amount, payment_date, method, reference, note, unapplied_amount,
+            currency, exchange_rate
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $3, $8, $9)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.client_id)
+    .bind(amount)
+    .bind(payment_date)
+    .bind(req.method)
+    .bind(&req.reference)
+    .bind(&req.note)
+    .bind(currency)
+    .bind(exchange_rate)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to record payment: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to record payment" })),
+        )
+    })?;
+
+    ledger::post(
+        &mut tx,
+        user_id,
+        (SourceType::Payment, payment.id),
+        payment.payment_date,
+        &match &payment.reference {
+            Some(reference) => format!("Payment received ({})", reference),
+            None => "Payment received".to_string(),
+        },
+        &ledger::payment_postings(currencies::to_home(payment.amount, payment.exchange_rate)),
+    )
+    .await?;
+
+    let payment = apply_allocations(
+        &mut tx,
+        user_id,
+        &payment,
+        &req.allocations,
+        payment.payment_date,
+    )
+    .await?;
+    let allocations = fetch_allocations(&mut tx, payment.id).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit payment: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to record payment" })),
+        )
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(PaymentWithAllocations {
+            payment,
+            allocations,
+        }),
+    ))
+}
+
+pub async fn get_payment(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PaymentWithAllocations>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch payment" })),
+        )
+    })?;
+
+    let payment =
+        sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch payment: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch payment" })),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": "Payment not found" })),
+                )
+            })?;
+
+    let allocations = fetch_allocations(&mut conn, payment.id).await?;
+
+    Ok(Json(PaymentWithAllocations {
+        payment,
+        allocations,
+    }))
+}
+
+pub async fn apply_payment(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ApplyPaymentRequest>,
+) -> Result<Json<PaymentWithAllocations>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply payment" })),
+        )
+    })?;
+
+    let payment = lock_payment(&mut tx, user_id, id).await?;
+    // Credit applied later is posted when it is applied, not when it was received
+    let entry_date = chrono::Utc::now().date_naive().max(payment.payment_date);
+    let payment =
+        apply_allocations(&mut tx, user_id, &payment, &req.allocations, entry_date).await?;
+    let allocations = fetch_allocations(&mut tx, payment.id).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit payment allocation: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to apply payment" })),
+        )
+    })?;
+
+    Ok(Json(PaymentWithAllocations {
+        payment,
+        allocations,
+    }))
+}
+
+pub async fn delete_payment(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete payment" })),
+        )
+    })?;
+
+    let payment = lock_payment(&mut tx, user_id, id).await?;
+
+    let mut invoice_ids: Vec<Uuid> = fetch_allocations(&mut tx, payment.id)
+        .await?
+        .into_iter()
+        .map(|allocation| allocation.invoice_id)
+        .collect();
+    invoice_ids.sort();
+
+    for invoice_id in &invoice_ids {
+        invoices::lock_invoice(&mut tx, user_id, *invoice_id).await?;
+    }
+
+    ledger::reverse_source(&mut tx, user_id, (SourceType::Payment, payment.id)).await?;
+
+    sqlx::query("DELETE FROM payments WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete payment: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete payment" })),
+            )
+        })?;
+
+    for invoice_id in &invoice_ids {
+        invoices::refresh_balance(&mut tx, *invoice_id).await?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit payment deletion: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete payment" })),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Credit available to a client in one currency. Credit notes are in the
+/// currency of the invoice they credit.
+pub async fn get_client_credit(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(client_id): Path<Uuid>,
+    Query(query): Query<ClientCreditQuery>,
+) -> Result<Json<ClientCredit>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch client credit" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut conn, user_id, client_id).await?;
+    let currency =
+        currencies::document_currency(&mut conn, user_id, &client, query.currency).await?;
+
+    let available_credit = sqlx::query_scalar::<_, Decimal>(
+        r#"
+        SELECT
+            COALESCE((
+                SELECT SUM(unapplied_amount) FROM payments
+                WHERE user_id = $1 AND client_id = $2 AND currency = $3
+            ), 0)
+            + COALESCE((
+                SELECT SUM(cn.unapplied_amount) FROM credit_notes cn
+                JOIN invoices i ON i.id = cn.invoice_id
+                WHERE cn.user_id = $1 AND cn.client_id = $2 AND i.currency = $3
+            ), 0)
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(&currency)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch client credit: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch client credit" })),
+        )
+    })?;
+
+    Ok(Json(ClientCredit {
+        client_id,
+        currency,
+        available_credit,
+    }))
+}
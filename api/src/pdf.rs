@@ -0,0 +1,871 @@
+// PDF rendering for financial documents
+//
+// Documents are laid out on US Letter pages with the standard Helvetica fonts, which
+// every PDF reader provides, so nothing needs to be embedded and the output is plain
+// ASCII. Rendering is deterministic (no timestamps or random IDs) so the generated
+// bytes can be compared against snapshots.
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::business::BusinessProfile;
+use crate::clients::{Client, ClientType};
+use crate::credit_notes::{CreditNote, CreditNoteLineItem, CreditNoteTax};
+use crate::estimates::{Estimate, EstimateLineItem, EstimateTax};
+use crate::invoices::{Invoice, InvoiceLineItem, InvoiceTax};
+
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 50.0;
+const CONTENT_BOTTOM: f32 = MARGIN + 30.0;
+
+// Table column anchors (amount columns are right aligned)
+const DESCRIPTION_WIDTH: f32 = 290.0;
+const QUANTITY_RIGHT: f32 = 400.0;
+const UNIT_PRICE_RIGHT: f32 = 480.0;
+const AMOUNT_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+const TOTAL_LABEL_RIGHT: f32 = 480.0;
+
+/// Helvetica advance widths for ASCII 32..=126, in 1/1000 em.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Helvetica-Bold advance widths for ASCII 32..=126, in 1/1000 em.
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+
+    fn char_width(&self, c: char) -> u16 {
+        let widths = match self {
+            Font::Regular => &HELVETICA_WIDTHS,
+            Font::Bold => &HELVETICA_BOLD_WIDTHS,
+        };
+
+        match c as u32 {
+            code @ 32..=126 => widths[(code - 32) as usize],
+            _ => 556,
+        }
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        text.chars().map(|c| self.char_width(c) as f32).sum::<f32>() * size / 1000.0
+    }
+}
+
+/// Maps a character to its WinAnsiEncoding byte, or `?` when it has none.
+fn win_ansi_byte(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        _ => b'?',
+    }
+}
+
+/// Encodes text as a PDF hex string, which keeps the file ASCII only.
+fn encode_text(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len() * 2 + 2);
+    encoded.push('<');
+    for c in text.chars() {
+        encoded.push_str(&format!("{:02X}", win_ansi_byte(c)));
+    }
+    encoded.push('>');
+    encoded
+}
+
+#[derive(Debug, Default)]
+struct Page {
+    content: String,
+}
+
+impl Page {
+    fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        self.content.push_str(&format!(
+            "BT /{} {:.1} Tf {:.2} {:.2} Td {} Tj ET\n",
+            font.resource(),
+            size,
+            x,
+            y,
+            encode_text(text)
+        ));
+    }
+
+    fn text_right(&mut self, right: f32, y: f32, font: Font, size: f32, text: &str) {
+        self.text(right - font.text_width(text, size), y, font, size, text);
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.content.push_str(&format!(
+            "0.5 w {:.2} {:.2} m {:.2} {:.2} l S\n",
+            x1, y1, x2, y2
+        ));
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, gray: f32) {
+        self.content.push_str(&format!(
+            "q {:.2} g {:.2} {:.2} {:.2} {:.2} re f Q\n",
+            gray, x, y, width, height
+        ));
+    }
+}
+
+/// Serializes pages into a complete PDF file.
+fn write_pdf(pages: &[Page]) -> Vec<u8> {
+    let mut objects: Vec<String> = Vec::with_capacity(4 + pages.len() * 2);
+
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + i * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids,
+        pages.len()
+    ));
+    objects.push(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    );
+    objects.push(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    );
+
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            6 + i * 2
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.content.len(),
+            page.content
+        ));
+    }
+
+    let mut output = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(output.len());
+        output.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref_offset = output.len();
+    output.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        output.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    output.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    ));
+
+    output.into_bytes()
+}
+
+/// Splits text into lines no wider than `max_width`, breaking overlong words.
+fn wrap_text(text: &str, font: Font, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut current = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", current, word)
+            };
+
+            if font.text_width(&candidate, size) <= max_width {
+                current = candidate;
+                continue;
+            }
+
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+
+            for c in word.chars() {
+                if !current.is_empty()
+                    && font.text_width(&format!("{}{}", current, c), size) > max_width
+                {
+                    lines.push(std::mem::take(&mut current));
+                }
+                current.push(c);
+            }
+        }
+
+        lines.push(current);
+    }
+
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    lines
+}
+
+/// Formats an amount with two decimals and thousands separators, e.g. `-1,234.50`.
+pub fn format_money(amount: Decimal) -> String {
+    let rounded = amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    let formatted = format!("{:.2}", rounded.abs());
+    let (whole, cents) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+
+    let mut grouped = String::with_capacity(whole.len() + whole.len() / 3);
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() {
+        "-"
+    } else {
+        ""
+    };
+
+    format!("{}{}.{}", sign, grouped, cents)
+}
+
+fn format_quantity(quantity: Decimal) -> String {
+    quantity.normalize().to_string()
+}
+
+/// Formats address fields into display lines, skipping empty parts.
+fn address_lines(
+    address_line1: Option<&str>,
+    address_line2: Option<&str>,
+    city: Option<&str>,
+    province: Option<&str>,
+    postal_code: Option<&str>,
+    country: Option<&str>,
+) -> Vec<String> {
+    fn present(value: Option<&str>) -> Option<&str> {
+        value.map(str::trim).filter(|value| !value.is_empty())
+    }
+
+    let mut lines = Vec::new();
+
+    lines.extend(present(address_line1).map(str::to_string));
+    lines.extend(present(address_line2).map(str::to_string));
+
+    let locality = [present(city), present(province)]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(", ");
+    let locality = [Some(locality.as_str()), present(postal_code)]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("  ");
+    if !locality.is_empty() {
+        lines.push(locality);
+    }
+
+    lines.extend(present(country).map(str::to_string));
+    lines
+}
+
+/// A business or client block on a document.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentParty {
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+impl DocumentParty {
+    pub fn from_business(profile: &BusinessProfile) -> Self {
+        let mut lines = address_lines(
+            profile.address_line1.as_deref(),
+            profile.address_line2.as_deref(),
+            profile.city.as_deref(),
+            profile.province.as_deref(),
+            profile.postal_code.as_deref(),
+            profile.country.as_deref(),
+        );
+        lines.extend(profile.email.clone());
+        lines.extend(profile.phone.clone());
+        lines.extend(profile.website.clone());
+        lines.extend(
+            profile
+                .tax_number
+                .as_ref()
+                .map(|number| format!("Tax number: {}", number)),
+        );
+
+        Self {
+            name: profile.business_name.clone(),
+            lines,
+        }
+    }
+
+    pub fn from_client(client: &Client) -> Self {
+        let person_name = [client.first_name.as_deref(), client.last_name.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let (name, attention) = match client.client_type {
+            ClientType::Company => (
+                client.company_name.clone().unwrap_or_default(),
+                Some(person_name).filter(|name| !name.is_empty()),
+            ),
+            ClientType::Person => (person_name, None),
+        };
+
+        let mut lines = Vec::new();
+        lines.extend(attention.map(|name| format!("Attn: {}", name)));
+        lines.extend(address_lines(
+            client.address_line1.as_deref(),
+            client.address_line2.as_deref(),
+            client.city.as_deref(),
+            client.province.as_deref(),
+            client.postal_code.as_deref(),
+            client.country.as_deref(),
+        ));
+        lines.extend(client.email.clone());
+
+        Self { name, lines }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentLine {
+    pub description: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocumentTotal {
+    pub label: String,
+    pub amount: Decimal,
+    pub emphasized: bool,
+}
+
+/// Everything needed to lay out an invoice-like document.
+#[derive(Debug, Clone)]
+pub struct DocumentModel {
+    pub title: String,
+    pub details: Vec<(String, String)>,
+    pub from: Option<DocumentParty>,
+    pub bill_to: DocumentParty,
+    pub line_items: Vec<DocumentLine>,
+    pub totals: Vec<DocumentTotal>,
+    pub notes: Option<String>,
+}
+
+impl DocumentModel {
+    pub fn for_invoice(
+        invoice: &Invoice,
+        line_items: &[InvoiceLineItem],
+        taxes: &[InvoiceTax],
+        client: &Client,
+        business: Option<&BusinessProfile>,
+    ) -> Self {
+        let mut details = Vec::new();
+        if let Some(number) = &invoice.invoice_number {
+            details.push(("Invoice #".to_string(), number.clone()));
+        }
+        details.push(("Issue date".to_string(), invoice.issue_date.to_string()));
+        if let Some(due_date) = invoice.due_date {
+            details.push(("Due date".to_string(), due_date.to_string()));
+        }
+        details.push(("Currency".to_string(), invoice.currency.clone()));
+
+        let mut totals = vec![DocumentTotal {
+            label: "Subtotal".to_string(),
+            amount: invoice.subtotal,
+            emphasized: false,
+        }];
+        totals.extend(taxes.iter().map(|tax| DocumentTotal {
+            label: format!("{} ({}%)", tax.name, tax.rate.normalize()),
+            amount: tax.amount,
+            emphasized: false,
+        }));
+        totals.push(DocumentTotal {
+            label: "Total".to_string(),
+            amount: invoice.total,
+            emphasized: true,
+        });
+        if !invoice.amount_paid.is_zero() {
+            totals.push(DocumentTotal {
+                label: "Amount paid".to_string(),
+                amount: -invoice.amount_paid,
+                emphasized: false,
+            });
+        }
+        if !invoice.amount_credited.is_zero() {
+            totals.push(DocumentTotal {
+                label: "Credits".to_string(),
+                amount: -invoice.amount_credited,
+                emphasized: false,
+            });
+        }
+        totals.push(DocumentTotal {
+            label: "Balance due".to_string(),
+            amount: invoice.balance_due,
+            emphasized: true,
+        });
+
+        Self {
+            title: "INVOICE".to_string(),
+            details,
+            from: business.map(DocumentParty::from_business),
+            bill_to: DocumentParty::from_client(client),
+            line_items: line_items
+                .iter()
+                .map(|item| DocumentLine {
+                    description: item.description.clone(),
+                    quantity: item.quantity,
+                    unit_price: item.unit_price,
+                    amount: item.amount,
+                })
+                .collect(),
+            totals,
+            notes: invoice.notes.clone(),
+        }
+    }
+
+    pub fn for_credit_note(
+        credit_note: &CreditNote,
+        invoice_number: Option<&str>,
+        line_items: &[CreditNoteLineItem],
+        taxes: &[CreditNoteTax],
+        client: &Client,
+        business: Option<&BusinessProfile>,
+    ) -> Self {
+        let mut details = vec![(
+            "Credit note #".to_string(),
+            credit_note.credit_note_number.clone(),
+        )];
+        if let Some(number) = invoice_number {
+            details.push(("Invoice #".to_string(), number.to_string()));
+        }
+        details.push(("Issue date".to_string(), credit_note.issue_date.to_string()));
+
+        let mut totals = vec![DocumentTotal {
+            label: "Subtotal".to_string(),
+            amount: credit_note.subtotal,
+            emphasized: false,
+        }];
+        totals.extend(taxes.iter().map(|tax| DocumentTotal {
+            label: format!("{} ({}%)", tax.name, tax.rate.normalize()),
+            amount: tax.amount,
+            emphasized: false,
+        }));
+        totals.push(DocumentTotal {
+            label: "Total credit".to_string(),
+            amount: credit_note.total,
+            emphasized: true,
+        });
+
+        Self {
+            title: "CREDIT NOTE".to_string(),
+            details,
+            from: business.map(DocumentParty::from_business),
+            bill_to: DocumentParty::from_client(client),
+            line_items: line_items
+                .iter()
+                .map(|item| DocumentLine {
+                    description: item.description.clone(),
+                    quantity: item.quantity,
+                    unit_price: item.unit_price,
+                    amount: item.amount,
+                })
+                .collect(),
+            totals,
+            notes: credit_note.reason.clone(),
+        }
+    }
+
+    pub fn for_estimate(
+        estimate: &Estimate,
+        line_items: &[EstimateLineItem],
+        taxes: &[EstimateTax],
+        client: &Client,
+        business: Option<&BusinessProfile>,
+    ) -> Self {
+        let mut details = vec![
+            ("Estimate #".to_string(), estimate.estimate_number.clone()),
+            ("Issue date".to_string(), estimate.issue_date.to_string()),
+        ];
+        if let Some(expiry_date) = estimate.expiry_date {
+            details.push(("Valid until".to_string(), expiry_date.to_string()));
+        }
+        details.push(("Currency".to_string(), estimate.currency.clone()));
+
+        let mut totals = vec![DocumentTotal {
+            label: "Subtotal".to_string(),
+            amount: estimate.subtotal,
+            emphasized: false,
+        }];
+        totals.extend(taxes.iter().map(|tax| DocumentTotal {
+            label: format!("{} ({}%)", tax.name, tax.rate.normalize()),
+            amount: tax.amount,
+            emphasized: false,
+        }));
+        totals.push(DocumentTotal {
+            label: "Total".to_string(),
+            amount: estimate.total,
+            emphasized: true,
+        });
+
+        Self {
+            title: "ESTIMATE".to_string(),
+            details,
+            from: business.map(DocumentParty::from_business),
+            bill_to: DocumentParty::from_client(client),
+            line_items: line_items
+                .iter()
+                .map(|item| DocumentLine {
+                    description: item.description.clone(),
+                    quantity: item.quantity,
+                    unit_price: item.unit_price,
+                    amount: item.amount,
+                })
+                .collect(),
+            totals,
+            notes: estimate.notes.clone(),
+        }
+    }
+}
+
+/// Tracks the write position and starts new pages when content runs out of room.
+struct Layout {
+    pages: Vec<Page>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: vec![Page::default()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn page(&mut self) -> &mut Page {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    /// Starts a new page unless `height` still fits; returns whether it broke.
+    fn ensure_space(&mut self, height: f32) -> bool {
+        if self.y - height >= CONTENT_BOTTOM {
+            return false;
+        }
+
+        self.pages.push(Page::default());
+        self.y = PAGE_HEIGHT - MARGIN;
+        true
+    }
+
+    fn table_header(&mut self) {
+        let y = self.y;
+        let page = self.page();
+        page.fill_rect(MARGIN, y - 18.0, PAGE_WIDTH - 2.0 * MARGIN, 18.0, 0.92);
+        page.text(MARGIN + 6.0, y - 12.5, Font::Bold, 9.0, "Description");
+        page.text_right(QUANTITY_RIGHT, y - 12.5, Font::Bold, 9.0, "Qty");
+        page.text_right(UNIT_PRICE_RIGHT, y - 12.5, Font::Bold, 9.0, "Unit price");
+        page.text_right(AMOUNT_RIGHT - 6.0, y - 12.5, Font::Bold, 9.0, "Amount");
+        self.y -= 24.0;
+    }
+}
+
+/// Renders a document model to PDF bytes.
+pub fn render(document: &DocumentModel) -> Vec<u8> {
+    let mut layout = Layout::new();
+
+    // Header: business on the left, title and details on the right
+    let top = layout.y;
+    let mut left_y = top;
+    if let Some(from) = &document.from {
+        layout
+            .page()
+            .text(MARGIN, left_y - 16.0, Font::Bold, 16.0, &from.name);
+        left_y -= 32.0;
+        for line in &from.lines {
+            layout.page().text(MARGIN, left_y, Font::Regular, 9.0, line);
+            left_y -= 12.0;
+        }
+    }
+
+    let mut right_y = top;
+    layout.page().text_right(
+        AMOUNT_RIGHT,
+        right_y - 20.0,
+        Font::Bold,
+        22.0,
+        &document.title,
+    );
+    right_y -= 40.0;
+    for (label, value) in &document.details {
+        layout
+            .page()
+            .text_right(TOTAL_LABEL_RIGHT, right_y, Font::Regular, 9.0, label);
+        layout
+            .page()
+            .text_right(AMOUNT_RIGHT, right_y, Font::Bold, 9.0, value);
+        right_y -= 13.0;
+    }
+
+    layout.y = left_y.min(right_y) - 24.0;
+
+    // Bill to
+    let y = layout.y;
+    let page = layout.page();
+    page.text(MARGIN, y, Font::Bold, 9.0, "BILL TO");
+    page.text(MARGIN, y - 15.0, Font::Bold, 11.0, &document.bill_to.name);
+    layout.y -= 29.0;
+    for line in &document.bill_to.lines {
+        let y = layout.y;
+        layout.page().text(MARGIN, y, Font::Regular, 10.0, line);
+        layout.y -= 13.0;
+    }
+    layout.y -= 20.0;
+
+    // Line items
+    layout.table_header();
+    for item in &document.line_items {
+        let lines = wrap_text(&item.description, Font::Regular, 10.0, DESCRIPTION_WIDTH);
+        let height = lines.len() as f32 * 13.0 + 6.0;
+        if layout.ensure_space(height) {
+            layout.table_header();
+        }
+
+        let y = layout.y;
+        let page = layout.page();
+        for (i, line) in lines.iter().enumerate() {
+            page.text(MARGIN + 6.0, y - i as f32 * 13.0, Font::Regular, 10.0, line);
+        }
+        page.text_right(
+            QUANTITY_RIGHT,
+            y,
+            Font::Regular,
+            10.0,
+            &format_quantity(item.quantity),
+        );
+        page.text_right(
+            UNIT_PRICE_RIGHT,
+            y,
+            Font::Regular,
+            10.0,
+            &format_money(item.unit_price),
+        );
+        page.text_right(
+            AMOUNT_RIGHT - 6.0,
+            y,
+            Font::Regular,
+            10.0,
+            &format_money(item.amount),
+        );
+        page.line(MARGIN, y - height + 9.0, AMOUNT_RIGHT, y - height + 9.0);
+        layout.y -= height;
+    }
+
+    // Totals stay together on one page
+    layout.y -= 8.0;
+    layout.ensure_space(document.totals.len() as f32 * 16.0);
+    for total in &document.totals {
+        let font = if total.emphasized {
+            Font::Bold
+        } else {
+            Font::Regular
+        };
+        let y = layout.y;
+        let page = layout.page();
+        page.text_right(TOTAL_LABEL_RIGHT, y, font, 10.0, &total.label);
+        page.text_right(
+            AMOUNT_RIGHT - 6.0,
+            y,
+            font,
+            10.0,
+            &format_money(total.amount),
+        );
+        layout.y -= 16.0;
+    }
+
+    // Notes
+    if let Some(notes) = document
+        .notes
+        .as_deref()
+        .filter(|notes| !notes.trim().is_empty())
+    {
+        layout.y -= 16.0;
+        layout.ensure_space(26.0);
+        let y = layout.y;
+        layout.page().text(MARGIN, y, Font::Bold, 9.0, "NOTES");
+        layout.y -= 14.0;
+        for line in wrap_text(notes, Font::Regular, 9.0, PAGE_WIDTH - 2.0 * MARGIN) {
+            layout.ensure_space(12.0);
+            let y = layout.y;
+            layout.page().text(MARGIN, y, Font::Regular, 9.0, &line);
+            layout.y -= 12.0;
+        }
+    }
+
+    // Page numbers
+    let page_count = layout.pages.len();
+    for (i, page) in layout.pages.iter_mut().enumerate() {
+        page.text_right(
+            AMOUNT_RIGHT,
+            MARGIN - 20.0,
+            Font::Regular,
+            8.0,
+            &format!("Page {} of {}", i + 1, page_count),
+        );
+    }
+
+    write_pdf(&layout.pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn sample_document(line_count: usize) -> DocumentModel {
+        DocumentModel {
+            title: "INVOICE".to_string(),
+            details: vec![
+                ("Invoice #".to_string(), "INV-2026-0042".to_string()),
+                ("Issue date".to_string(), "2026-03-01".to_string()),
+                ("Due date".to_string(), "2026-03-31".to_string()),
+            ],
+            from: Some(DocumentParty {
+                name: "Teftar Studio".to_string(),
+                lines: vec![
+                    "100 Queen St W".to_string(),
+                    "Toronto, ON  M5H 2N2".to_string(),
+                    "Canada".to_string(),
+                ],
+            }),
+            bill_to: DocumentParty {
+                name: "Café Montréal Inc.".to_string(),
+                lines: vec!["Attn: Émilie Roy".to_string(), "Montréal, QC  H2X 1Y4".to_string()],
+            },
+            line_items: (0..line_count)
+                .map(|i| DocumentLine {
+                    description: format!(
+                        "Design work, iteration {} including revisions requested during the review meeting",
+                        i + 1
+                    ),
+                    quantity: decimal("1.5"),
+                    unit_price: decimal("1200"),
+                    amount: decimal("1800.00"),
+                })
+                .collect(),
+            totals: vec![
+                DocumentTotal {
+                    label: "Subtotal".to_string(),
+                    amount: decimal("1800.00") * Decimal::from(line_count),
+                    emphasized: false,
+                },
+                DocumentTotal {
+                    label: "Balance due".to_string(),
+                    amount: decimal("1800.00") * Decimal::from(line_count),
+                    emphasized: true,
+                },
+            ],
+            notes: Some("Thank you for your business.".to_string()),
+        }
+    }
+
+    /// Compares against a stored snapshot; set UPDATE_SNAPSHOTS=1 to regenerate it.
+    fn assert_snapshot(name: &str, output: &[u8]) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("snapshots")
+            .join(name);
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, output).unwrap();
+        }
+
+        let expected =
+            std::fs::read(&path).unwrap_or_else(|_| panic!("missing snapshot {}", path.display()));
+        assert!(expected == output, "{} does not match its snapshot", name);
+    }
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(format_money(decimal("0")), "0.00");
+        assert_eq!(format_money(decimal("1234.5")), "1,234.50");
+        assert_eq!(format_money(decimal("-1234567.005")), "-1,234,567.01");
+        assert_eq!(format_money(decimal("999.999")), "1,000.00");
+    }
+
+    #[test]
+    fn test_encode_text() {
+        assert_eq!(encode_text("A(b)"), "<41286229>");
+        assert_eq!(encode_text("é€✓"), "<E9803F>");
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        let lines = wrap_text("one two three four", Font::Regular, 10.0, 40.0);
+        assert_eq!(lines, vec!["one two", "three", "four"]);
+    }
+
+    #[test]
+    fn test_render_invoice_snapshot() {
+        assert_snapshot("invoice.pdf", &render(&sample_document(3)));
+    }
+
+    #[test]
+    fn test_render_multi_page_invoice_snapshot() {
+        let output = render(&sample_document(40));
+        assert!(String::from_utf8_lossy(&output).contains("/Count 3"));
+        assert_snapshot("invoice_multi_page.pdf", &output);
+    }
+}
@@ -0,0 +1,699 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::clients;
+use crate::invoices::{round_money, validate_non_negative, validate_positive};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BillingMethod {
+    /// The project is invoiced for a fixed fee; time is tracked but not billed.
+    FixedFee,
+    /// All time is billed at the project's hourly rate.
+    HourlyProjectRate,
+    /// Time is billed at each entry's own rate, or the client's default.
+    HourlyStaffRate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetType {
+    Hours,
+    /// Time value plus expenses.
+    Money,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Project {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub billing_method: BillingMethod,
+    pub hourly_rate: Option<Decimal>,
+    pub fixed_fee: Option<Decimal>,
+    pub budget_type: Option<BudgetType>,
+    pub budget_amount: Option<Decimal>,
+    pub is_archived: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Project {
+    /// Hourly rate time on this project is billed at, or `None` when the time
+    /// is not billed by the hour (fixed fee, or no rate anywhere).
+    pub fn time_rate(
+        &self,
+        entry_rate: Option<Decimal>,
+        client_rate: Option<Decimal>,
+    ) -> Option<Decimal> {
+        match self.billing_method {
+            BillingMethod::FixedFee => None,
+            BillingMethod::HourlyProjectRate => self.hourly_rate,
+            BillingMethod::HourlyStaffRate => entry_rate.or(client_rate),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetUsage {
+    pub budget_type: BudgetType,
+    pub budget_amount: Decimal,
+    pub used: Decimal,
+    pub remaining: Decimal,
+    pub percent_used: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectSummary {
+    pub project_id: Uuid,
+    pub hours_logged: Decimal,
+    pub billable_hours: Decimal,
+    pub unbilled_hours: Decimal,
+    /// Logged time valued at the project's billing rates.
+    pub time_value: Decimal,
+    pub expenses_total: Decimal,
+    pub unbilled_expenses_total: Decimal,
+    /// Issued (non-draft, non-void) invoices linked to the project.
+    pub invoiced_total: Decimal,
+    pub paid_total: Decimal,
+    pub outstanding_total: Decimal,
+    pub budget: Option<BudgetUsage>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateProjectRequest {
+    pub client_id: Uuid,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub description: Option<String>,
+    pub billing_method: BillingMethod,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub hourly_rate: Option<Decimal>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub fixed_fee: Option<Decimal>,
+    pub budget_type: Option<BudgetType>,
+    #[validate(custom(function = "validate_positive"))]
+    pub budget_amount: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateProjectRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub billing_method: Option<BillingMethod>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub hourly_rate: Option<Decimal>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub fixed_fee: Option<Decimal>,
+    pub budget_type: Option<BudgetType>,
+    #[validate(custom(function = "validate_positive"))]
+    pub budget_amount: Option<Decimal>,
+    pub is_archived: Option<bool>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ProjectTime {
+    minutes: i32,
+    hourly_rate: Option<Decimal>,
+    billable: bool,
+    billed: bool,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ProjectTotals {
+    expenses_total: Decimal,
+    unbilled_expenses_total: Decimal,
+    invoiced_total: Decimal,
+    paid_total: Decimal,
+    outstanding_total: Decimal,
+}
+
+fn minutes_to_hours(minutes: i64) -> Decimal {
+    (Decimal::from(minutes) / Decimal::from(60))
+        .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// How much of a budget has been consumed.
+pub fn budget_usage(budget_type: BudgetType, budget_amount: Decimal, used: Decimal) -> BudgetUsage {
+    let percent_used = if budget_amount.is_zero() {
+        Decimal::ZERO
+    } else {
+        (used * Decimal::ONE_HUNDRED / budget_amount)
+            .round_dp_with_strategy(1, RoundingStrategy::MidpointAwayFromZero)
+    };
+
+    BudgetUsage {
+        budget_type,
+        budget_amount,
+        used,
+        remaining: budget_amount - used,
+        percent_used,
+    }
+}
+
+fn validate_billing(
+    billing_method: BillingMethod,
+    hourly_rate: Option<Decimal>,
+    fixed_fee: Option<Decimal>,
+    budget_type: Option<BudgetType>,
+    budget_amount: Option<Decimal>,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let error = if billing_method == BillingMethod::HourlyProjectRate && hourly_rate.is_none() {
+        Some("Projects billed at a project rate need an hourly rate")
+    } else if billing_method == BillingMethod::FixedFee && fixed_fee.is_none() {
+        Some("Fixed-fee projects need a fee")
+    } else if budget_type.is_some() != budget_amount.is_some() {
+        Some("A budget needs both a type and an amount")
+    } else {
+        None
+    };
+
+    match error {
+        Some(error) => Err((StatusCode::BAD_REQUEST, Json(json!({ "error": error })))),
+        None => Ok(()),
+    }
+}
+
+/// Loads a project owned by `user_id`, for records that reference one.
+pub(crate) async fn fetch_owned_project(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    project_id: Uuid,
+) -> Result<Project, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1 AND user_id = $2")
+        .bind(project_id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch project: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to fetch project" })),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Project not found" })),
+            )
+        })
+}
+
+/// Client of a record linked to `project_id`: the project's client, which must
+/// match `client_id` when both are given. Without a project, `client_id` as is.
+pub(crate) async fn project_client(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    project_id: Option<Uuid>,
+    client_id: Option<Uuid>,
+) -> Result<Option<Uuid>, (StatusCode, Json<Value>)> {
+    let Some(project_id) = project_id else {
+        return Ok(client_id);
+    };
+
+    let project = fetch_owned_project(conn, user_id, project_id).await?;
+    if client_id.is_some_and(|client_id| client_id != project.client_id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Project belongs to a different client" })),
+        ));
+    }
+
+    Ok(Some(project.client_id))
+}
+
+/// The project shared by all of `project_ids`, if there is exactly one.
+pub(crate) fn common_project(project_ids: impl IntoIterator<Item = Option<Uuid>>) -> Option<Uuid> {
+    let mut project_ids = project_ids.into_iter();
+    let first = project_ids.next()??;
+    project_ids
+        .all(|project_id| project_id == Some(first))
+        .then_some(first)
+}
+
+pub async fn list_projects(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<Project>>, (StatusCode, Json<Value>)> {
+    let projects = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE user_id = $1 ORDER BY is_archived, name",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch projects: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch projects" })),
+        )
+    })?;
+
+    Ok(Json(projects))
+}
+
+pub async fn create_project(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateProjectRequest>,
+) -> Result<(StatusCode, Json<Project>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+    validate_billing(
+        req.billing_method,
+        req.hourly_rate,
+        req.fixed_fee,
+        req.budget_type,
+        req.budget_amount,
+    )?;
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create project" })),
+        )
+    })?;
+
+    clients::fetch_owned_client(&mut conn, user_id, req.client_id).await?;
+
+    let project = sqlx::query_as::<_, Project>(
+        r#"
+        INSERT INTO projects (
+            user_id, client_id, name, description, billing_method, hourly_rate,
+            fixed_fee, budget_type, budget_amount
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.client_id)
+    .bind(req.name)
+    .bind(req.description)
+    .bind(req.billing_method)
+    .bind(req.hourly_rate)
+    .bind(req.fixed_fee)
+    .bind(req.budget_type)
+    .bind(req.budget_amount)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create project: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create project" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(project)))
+}
+
+pub async fn get_project(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Project>, (StatusCode, Json<Value>)> {
+    let project =
+        sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch project: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch project" })),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": "Project not found" })),
+                )
+            })?;
+
+    Ok(Json(project))
+}
+
+pub async fn update_project(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateProjectRequest>,
+) -> Result<Json<Project>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update project" })),
+        )
+    })?;
+
+    let existing = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch project: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update project" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Project not found" })),
+        )
+    })?;
+
+    let billing_method = req.billing_method.unwrap_or(existing.billing_method);
+    let hourly_rate = req.hourly_rate.or(existing.hourly_rate);
+    let fixed_fee = req.fixed_fee.or(existing.fixed_fee);
+    let budget_type = req.budget_type.or(existing.budget_type);
+    let budget_amount = req.budget_amount.or(existing.budget_amount);
+    validate_billing(
+        billing_method,
+        hourly_rate,
+        fixed_fee,
+        budget_type,
+        budget_amount,
+    )?;
+
+    let project = sqlx::query_as::<_, Project>(
+        r#"
+        UPDATE projects
+        SET
+            name = COALESCE($1, name),
+            description = COALESCE($2, description),
+            billing_method = $3,
+            hourly_rate = $4,
+            fixed_fee = $5,
+            budget_type = $6,
+            budget_amount = $7,
+            is_archived = COALESCE($8, is_archived),
+            updated_at = NOW()
+        WHERE id = $9 AND user_id = $10
+        RETURNING *
+        "#,
+    )
+    .bind(req.name)
+    .bind(req.description)
+    .bind(billing_method)
+    .bind(hourly_rate)
+    .bind(fixed_fee)
+    .bind(budget_type)
+    .bind(budget_amount)
+    .bind(req.is_archived)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update project: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update project" })),
+        )
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update project" })),
+        )
+    })?;
+
+    Ok(Json(project))
+}
+
+pub async fn delete_project(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM projects WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            // Linked time, expenses and invoices keep the project; archive it instead
+            if e.as_database_error()
+                .is_some_and(|db| db.is_foreign_key_violation())
+            {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({
+                        "error": "Project has time, expenses or invoices and cannot be deleted; archive it instead"
+                    })),
+                );
+            }
+
+            tracing::error!("Failed to delete project: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete project" })),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Project not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Budget consumption from the project's time entries, expenses and invoices.
+pub async fn get_project_summary(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProjectSummary>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to summarize project" })),
+        )
+    })?;
+
+    let project =
+        sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch project: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch project" })),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": "Project not found" })),
+                )
+            })?;
+    let client = clients::fetch_owned_client(&mut conn, user_id, project.client_id).await?;
+
+    // Running timers are left out until they stop
+    let time = sqlx::query_as::<_, ProjectTime>(
+        r#"
+        SELECT
+            duration_minutes AS minutes,
+            hourly_rate,
+            billable,
+            invoice_line_item_id IS NOT NULL AS billed
+        FROM time_entries
+        WHERE project_id = $1 AND user_id = $2 AND duration_minutes IS NOT NULL
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch project time: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to summarize project" })),
+        )
+    })?;
+
+    let totals = sqlx::query_as::<_, ProjectTotals>(
+        r#"
+        SELECT
+            COALESCE((
+                SELECT SUM(amount) FROM expenses WHERE project_id = $1 AND user_id = $2
+            ), 0) AS expenses_total,
+            COALESCE((
+                SELECT SUM(amount) FROM expenses
+                WHERE project_id = $1 AND user_id = $2
+                    AND billable AND invoice_line_item_id IS NULL
+            ), 0) AS unbilled_expenses_total,
+            COALESCE(SUM(total), 0) AS invoiced_total,
+            COALESCE(SUM(amount_paid), 0) AS paid_total,
+            COALESCE(SUM(balance_due), 0) AS outstanding_total
+        FROM invoices
+        WHERE project_id = $1 AND user_id = $2 AND status NOT IN ('draft', 'void')
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch project totals: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to summarize project" })),
+        )
+    })?;
+
+    let minutes = |filter: fn(&ProjectTime) -> bool| -> i64 {
+        time.iter()
+            .filter(|entry| filter(entry))
+            .map(|entry| i64::from(entry.minutes))
+            .sum()
+    };
+    let hours_logged = minutes_to_hours(minutes(|_| true));
+    let billable_hours = minutes_to_hours(minutes(|entry| entry.billable));
+    let unbilled_hours = minutes_to_hours(minutes(|entry| entry.billable && !entry.billed));
+
+    let time_value: Decimal = time
+        .iter()
+        .filter_map(|entry| {
+            project
+                .time_rate(entry.hourly_rate, client.default_hourly_rate)
+                .map(|rate| round_money(minutes_to_hours(i64::from(entry.minutes)) * rate))
+        })
+        .sum();
+
+    let budget =
+        project
+            .budget_type
+            .zip(project.budget_amount)
+            .map(|(budget_type, budget_amount)| {
+                let used = match budget_type {
+                    BudgetType::Hours => hours_logged,
+                    BudgetType::Money => time_value + totals.expenses_total,
+                };
+                budget_usage(budget_type, budget_amount, used)
+            });
+
+    Ok(Json(ProjectSummary {
+        project_id: project.id,
+        hours_logged,
+        billable_hours,
+        unbilled_hours,
+        time_value,
+        expenses_total: totals.expenses_total,
+        unbilled_expenses_total: totals.unbilled_expenses_total,
+        invoiced_total: totals.invoiced_total,
+        paid_total: totals.paid_total,
+        outstanding_total: totals.outstanding_total,
+        budget,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(billing_method: BillingMethod) -> Project {
+        Project {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            client_id: Uuid::new_v4(),
+            name: "Website".to_string(),
+            description: None,
+            billing_method,
+            hourly_rate: Some(Decimal::from(150)),
+            fixed_fee: Some(Decimal::from(5000)),
+            budget_type: None,
+            budget_amount: None,
+            is_archived: false,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_time_rate() {
+        let entry_rate = Some(Decimal::from(90));
+        let client_rate = Some(Decimal::from(100));
+
+        assert_eq!(
+            project(BillingMethod::HourlyProjectRate).time_rate(entry_rate, client_rate),
+            Some(Decimal::from(150))
+        );
+        assert_eq!(
+            project(BillingMethod::HourlyStaffRate).time_rate(entry_rate, client_rate),
+            entry_rate
+        );
+        assert_eq!(
+            project(BillingMethod::HourlyStaffRate).time_rate(None, client_rate),
+            client_rate
+        );
+        assert_eq!(
+            project(BillingMethod::FixedFee).time_rate(entry_rate, client_rate),
+            None
+        );
+    }
+
+    #[test]
+    fn test_budget_usage() {
+        let usage = budget_usage(BudgetType::Hours, Decimal::from(40), Decimal::new(4550, 2));
+        assert_eq!(usage.remaining, Decimal::new(-550, 2));
+        assert_eq!(usage.percent_used, Decimal::new(1138, 1));
+    }
+
+    #[test]
+    fn test_common_project() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+
+        assert_eq!(common_project([Some(a), Some(a)]), Some(a));
+        assert_eq!(common_project([Some(a), Some(b)]), None);
+        assert_eq!(common_project([Some(a), None]), None);
+        assert_eq!(common_project([]), None);
+    }
+}
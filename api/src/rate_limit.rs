@@ -0,0 +1,119 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{Json, Response},
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Number of tracked addresses above which expired windows are dropped.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Fixed window request limit per client IP address.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    /// Header holding the client address set by a trusted proxy (e.g. Fly-Client-IP).
+    client_ip_header: Option<String>,
+    windows: Arc<Mutex<HashMap<IpAddr, (Instant, u32)>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            client_ip_header: env::var("CLIENT_IP_HEADER")
+                .ok()
+                .filter(|header| !header.is_empty()),
+            windows: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Limiter allowing `default_limit` requests a minute, or `{var}` if set.
+    pub fn per_minute_from_env(var: &str, default_limit: u32) -> Self {
+        let limit = env::var(var)
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .unwrap_or(default_limit);
+
+        Self::new(limit, Duration::from_secs(60))
+    }
+
+    /// Counts a request from `ip` at `now`; false once the window's limit is used up.
+    fn check(&self, ip: IpAddr, now: Instant) -> bool {
+        let mut windows = self.windows.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        }
+
+        let (start, count) = windows.entry(ip).or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.limit {
+            return false;
+        }
+
+        *count += 1;
+        true
+    }
+
+    fn client_ip(&self, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+        self.client_ip_header
+            .as_deref()
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(peer.ip())
+    }
+}
+
+pub async fn limit_by_ip(
+    State(limiter): State<RateLimiter>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let ip = limiter.client_ip(req.headers(), peer);
+
+    if !limiter.check(ip, Instant::now()) {
+        tracing::warn!("Rate limit exceeded for {} on {}", ip, req.uri().path());
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "error": "Too many requests. Please wait a moment and try again." })),
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_each_address_per_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "198.51.100.1".parse().unwrap();
+
+        assert!(limiter.check(client, start));
+        assert!(limiter.check(client, start + Duration::from_secs(1)));
+        assert!(!limiter.check(client, start + Duration::from_secs(2)));
+        assert!(limiter.check(other, start + Duration::from_secs(2)));
+
+        // A new window starts once the previous one is over
+        assert!(limiter.check(client, start + Duration::from_secs(61)));
+    }
+}
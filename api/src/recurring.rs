@@ -0,0 +1,821 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{Days, Months, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use std::time::Duration;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::clients::{self, Client};
+use crate::currencies;
+use crate::invoices::{self, Invoice, InvoiceStatus, LineItemRequest};
+use crate::numbering::{self, DocumentType};
+use crate::taxes::{self, TaxRounding};
+
+/// How often the scheduler looks for due profiles unless overridden with
+/// `RECURRING_SCHEDULER_INTERVAL_SECS`.
+const DEFAULT_SCHEDULER_INTERVAL_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RecurringProfile {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Uuid,
+    pub name: String,
+    pub frequency: Frequency,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub max_occurrences: Option<i32>,
+    pub occurrences_generated: i32,
+    pub next_run_date: Option<NaiveDate>,
+    pub auto_send: bool,
+    pub due_days: Option<i32>,
+    pub is_active: bool,
+    pub notes: Option<String>,
+    pub tax_rounding: TaxRounding,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RecurringLineItem {
+    pub id: Uuid,
+    pub profile_id: Uuid,
+    pub position: i32,
+    pub description: String,
+    pub quantity: Decimal,
+    pub unit_price: Decimal,
+    pub tax_rate_ids: Option<Vec<Uuid>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecurringProfileWithLineItems {
+    #[serde(flatten)]
+    pub profile: RecurringProfile,
+    pub line_items: Vec<RecurringLineItem>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateRecurringProfileRequest {
+    pub client_id: Uuid,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub frequency: Frequency,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    #[validate(range(min = 1))]
+    pub max_occurrences: Option<i32>,
+    #[serde(default)]
+    pub auto_send: bool,
+    #[validate(range(min = 0, max = 365))]
+    pub due_days: Option<i32>,
+    pub notes: Option<String>,
+    pub tax_rounding: Option<TaxRounding>,
+    #[validate(
+        length(min = 1, message = "At least one line item is required"),
+        nested
+    )]
+    pub line_items: Vec<LineItemRequest>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateRecurringProfileRequest {
+    pub client_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    /// Fixed once the profile has generated invoices, like the start date.
+    pub frequency: Option<Frequency>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    #[validate(range(min = 1))]
+    pub max_occurrences: Option<i32>,
+    pub auto_send: Option<bool>,
+    #[validate(range(min = 0, max = 365))]
+    pub due_days: Option<i32>,
+    pub is_active: Option<bool>,
+    pub notes: Option<String>,
+    pub tax_rounding: Option<TaxRounding>,
+    #[validate(
+        length(min = 1, message = "At least one line item is required"),
+        nested
+    )]
+    pub line_items: Option<Vec<LineItemRequest>>,
+}
+
+/// Date of the `n`th occurrence (zero-based) of a schedule.
+///
+/// Occurrences are always counted from the start date so that month-end dates
+/// do not drift: a schedule starting Jan 31 runs on Feb 28, then Mar 31.
+pub fn occurrence_date(start_date: NaiveDate, frequency: Frequency, n: u32) -> Option<NaiveDate> {
+    match frequency {
+        Frequency::Weekly => start_date.checked_add_days(Days::new(7 * u64::from(n))),
+        Frequency::Monthly => start_date.checked_add_months(Months::new(n)),
+        Frequency::Yearly => start_date.checked_add_months(Months::new(n.checked_mul(12)?)),
+    }
+}
+
+/// The next date a profile should generate an invoice on, or `None` once its
+/// end date or occurrence limit has been reached.
+pub fn next_run_date(
+    start_date: NaiveDate,
+    frequency: Frequency,
+    end_date: Option<NaiveDate>,
+    max_occurrences: Option<i32>,
+    occurrences_generated: i32,
+) -> Option<NaiveDate> {
+    if max_occurrences.is_some_and(|max| occurrences_generated >= max) {
+        return None;
+    }
+
+    let date = occurrence_date(
+        start_date,
+        frequency,
+        u32::try_from(occurrences_generated).ok()?,
+    )?;
+
+    if end_date.is_some_and(|end_date| date > end_date) {
+        return None;
+    }
+
+    Some(date)
+}
+
+async fn lock_profile(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<RecurringProfile, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, RecurringProfile>(
+        "SELECT * FROM recurring_profiles WHERE id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch recurring profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch recurring profile" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Recurring profile not found" })),
+        )
+    })
+}
+
+async fn fetch_line_items(
+    conn: &mut PgConnection,
+    profile_id: Uuid,
+) -> Result<Vec<RecurringLineItem>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, RecurringLineItem>(
+        "SELECT * FROM recurring_profile_line_items WHERE profile_id = $1 ORDER BY position",
+    )
+    .bind(profile_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch recurring profile line items: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch recurring profile line items" })),
+        )
+    })
+}
+
+/// Rejects unknown or archived tax rates up front so the scheduler does not
+/// keep failing on them later.
+async fn validate_line_taxes(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    client: &Client,
+    line_items: &[LineItemRequest],
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let requested_taxes: Vec<Option<Vec<Uuid>>> = line_items
+        .iter()
+        .map(|item| item.tax_rate_ids.clone())
+        .collect();
+    taxes::resolve_line_taxes(conn, user_id, client, &requested_taxes).await?;
+
+    Ok(())
+}
+
+async fn replace_line_items(
+    conn: &mut PgConnection,
+    profile_id: Uuid,
+    line_items: &[LineItemRequest],
+) -> Result<Vec<RecurringLineItem>, (StatusCode, Json<Value>)> {
+    sqlx::query("DELETE FROM recurring_profile_line_items WHERE profile_id = $1")
+        .bind(profile_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete recurring profile line items: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to save recurring profile line items" })),
+            )
+        })?;
+
+    let mut inserted = Vec::with_capacity(line_items.len());
+
+    for (position, item) in line_items.iter().enumerate() {
+        let line_item = sqlx::query_as::<_, RecurringLineItem>(
+            r#"
+            INSERT INTO recurring_profile_line_items (
+                profile_id, position, description, quantity, unit_price, tax_rate_ids
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(profile_id)
+        .bind(position as i32)
+        .bind(&item.description)
+        .bind(item.quantity)
+        .bind(item.unit_price)
+        .bind(&item.tax_rate_ids)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to insert recurring profile line item: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to save recurring profile line items" })),
+            )
+        })?;
+
+        inserted.push(line_item);
+    }
+
+    Ok(inserted)
+}
+
+/// Creates the invoice for a locked profile's next occurrence and advances the
+/// schedule. Both happen in the caller's transaction, so an occurrence is
+/// either fully generated and recorded or not at all.
+async fn generate_occurrence(
+    conn: &mut PgConnection,
+    profile: &RecurringProfile,
+    recurrence_date: NaiveDate,
+) -> Result<Invoice, (StatusCode, Json<Value>)> {
+    let client: Client =
+        clients::fetch_owned_client(conn, profile.user_id, profile.client_id).await?;
+    let line_items: Vec<LineItemRequest> = fetch_line_items(conn, profile.id)
+        .await?
+        .into_iter()
+        .map(|item| LineItemRequest {
+            description: item.description,
+            quantity: item.quantity,
+            unit_price: item.unit_price,
+            tax_rate_ids: item.tax_rate_ids,
+        })
+        .collect();
+
+    let currency = currencies::document_currency(conn, profile.user_id, &client, None).await?;
+    let invoice_number =
+        numbering::next_number(conn, profile.user_id, DocumentType::Invoice).await?;
+    let due_date = profile
+        .due_days
+        .and_then(|days| recurrence_date.checked_add_days(Days::new(days as u64)));
+    let status = if profile.auto_send {
+        InvoiceStatus::Sent
+    } else {
+        InvoiceStatus::Draft
+    };
+
+    let invoice = sqlx::query_as::<_, Invoice>(
+        r#"
+        INSERT INTO invoices (
+            user_id, client_id, invoice_number, status, issue_date, due_date, notes,
+            tax_rounding, recurring_profile_id, recurrence_date, currency
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $5, $10)
+        RETURNING *
+        "#,
+    )
+    .bind(profile.user_id)
+    .bind(profile.client_id)
+    .bind(&invoice_number)
+    .bind(status)
+    .bind(recurrence_date)
+    .bind(due_date)
+    .bind(&profile.notes)
+    .bind(profile.tax_rounding)
+    .bind(profile.id)
+    .bind(currency)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create recurring invoice: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create recurring invoice" })),
+        )
+    })?;
+
+    invoices::add_line_items(conn, profile.user_id, invoice.id, &client, &line_items).await?;
+    let invoice = invoices::recalculate_totals(conn, invoice.id).await?;
+    let invoice = if invoice.status == InvoiceStatus::Sent {
+        invoices::post_issue(conn, invoice).await?
+    } else {
+        invoice
+    };
+
+    let occurrences_generated = profile.occurrences_generated + 1;
+    sqlx::query(
+        r#"
+        UPDATE recurring_profiles
+        SET occurrences_generated = $1, next_run_date = $2, updated_at = NOW()
+        WHERE id = $3
+        "#,
+    )
+    .bind(occurrences_generated)
+    .bind(next_run_date(
+        profile.start_date,
+        profile.frequency,
+        profile.end_date,
+        profile.max_occurrences,
+        occurrences_generated,
+    ))
+    .bind(profile.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to advance recurring profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create recurring invoice" })),
+        )
+    })?;
+
+    Ok(invoice)
+}
+
+/// Generates every invoice that is due on or before `today`.
+///
+/// Each occurrence runs in its own transaction that claims the profile row
+/// with `FOR UPDATE SKIP LOCKED`, so several instances can run this at once
+/// without generating the same occurrence twice. Missed occurrences (e.g.
+/// after downtime) are caught up one at a time.
+pub async fn run_due_profiles(pool: &PgPool, today: NaiveDate) -> Result<usize, sqlx::Error> {
+    let mut generated = 0;
+    let mut failed: Vec<Uuid> = Vec::new();
+
+    loop {
+        let mut tx = pool.begin().await?;
+
+        let profile = sqlx::query_as::<_, RecurringProfile>(
+            r#"
+            SELECT * FROM recurring_profiles
+            WHERE is_active
+                AND next_run_date IS NOT NULL
+                AND next_run_date <= $1
+                AND id <> ALL($2)
+            ORDER BY next_run_date
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(today)
+        .bind(&failed)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(profile) = profile else {
+            break;
+        };
+        let Some(recurrence_date) = profile.next_run_date else {
+            break;
+        };
+
+        match generate_occurrence(&mut tx, &profile, recurrence_date).await {
+            Ok(invoice) => {
+                tx.commit().await?;
+                generated += 1;
+                tracing::info!(
+                    "Generated invoice {} from recurring profile {}",
+                    invoice.id,
+                    profile.id
+                );
+            }
+            Err((_, Json(error))) => {
+                tx.rollback().await?;
+                // Leave the profile due so it is retried on the next tick
+                failed.push(profile.id);
+                tracing::error!(
+                    "Failed to generate invoice from recurring profile {}: {}",
+                    profile.id,
+                    error
+                );
+            }
+        }
+    }
+
+    Ok(generated)
+}
+
+/// Starts the background task that generates due recurring invoices.
+pub fn spawn_scheduler(pool: PgPool) {
+    let interval_secs = std::env::var("RECURRING_SCHEDULER_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_SCHEDULER_INTERVAL_SECS);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            let today = chrono::Utc::now().date_naive();
+            match run_due_profiles(&pool, today).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Generated {} recurring invoices", count),
+                Err(e) => tracing::error!("Recurring invoice scheduler failed: {}", e),
+            }
+        }
+    });
+
+    tracing::info!(
+        "Recurring invoice scheduler started (every {}s)",
+        interval_secs
+    );
+}
+
+pub async fn list_recurring_profiles(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<RecurringProfile>>, (StatusCode, Json<Value>)> {
+    let profiles = sqlx::query_as::<_, RecurringProfile>(
+        "SELECT * FROM recurring_profiles WHERE user_id = $1 ORDER BY created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch recurring profiles: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch recurring profiles" })),
+        )
+    })?;
+
+    Ok(Json(profiles))
+}
+
+pub async fn create_recurring_profile(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateRecurringProfileRequest>,
+) -> Result<(StatusCode, Json<RecurringProfileWithLineItems>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    if req
+        .end_date
+        .is_some_and(|end_date| end_date < req.start_date)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "End date cannot be before the start date" })),
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create recurring profile" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut tx, user_id, req.client_id).await?;
+    validate_line_taxes(&mut tx, user_id, &client, &req.line_items).await?;
+
+    let profile = sqlx::query_as::<_, RecurringProfile>(
+        r#"
+        INSERT INTO recurring_profiles (
+            user_id, client_id, name, frequency, start_date, end_date, max_occurrences,
+            next_run_date, auto_send, due_days, notes, tax_rounding
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.client_id)
+    .bind(&req.name)
+    .bind(req.frequency)
+    .bind(req.start_date)
+    .bind(req.end_date)
+    .bind(req.max_occurrences)
+    .bind(next_run_date(
+        req.start_date,
+        req.frequency,
+        req.end_date,
+        req.max_occurrences,
+        0,
+    ))
+    .bind(req.auto_send)
+    .bind(req.due_days)
+    .bind(&req.notes)
+    .bind(req.tax_rounding.unwrap_or_default())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create recurring profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create recurring profile" })),
+        )
+    })?;
+
+    let line_items = replace_line_items(&mut tx, profile.id, &req.line_items).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit recurring profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create recurring profile" })),
+        )
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RecurringProfileWithLineItems {
+            profile,
+            line_items,
+        }),
+    ))
+}
+
+pub async fn get_recurring_profile(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RecurringProfileWithLineItems>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch recurring profile" })),
+        )
+    })?;
+
+    let profile = sqlx::query_as::<_, RecurringProfile>(
+        "SELECT * FROM recurring_profiles WHERE id = $1 AND user_id = $2",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch recurring profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch recurring profile" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Recurring profile not found" })),
+        )
+    })?;
+
+    let line_items = fetch_line_items(&mut conn, profile.id).await?;
+
+    Ok(Json(RecurringProfileWithLineItems {
+        profile,
+        line_items,
+    }))
+}
+
+pub async fn update_recurring_profile(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateRecurringProfileRequest>,
+) -> Result<Json<RecurringProfileWithLineItems>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update recurring profile" })),
+        )
+    })?;
+
+    // Locking the profile also keeps the scheduler from generating mid-update
+    let existing = lock_profile(&mut tx, user_id, id).await?;
+
+    let client_id = req.client_id.unwrap_or(existing.client_id);
+    let client = clients::fetch_owned_client(&mut tx, user_id, client_id).await?;
+    if let Some(items) = &req.line_items {
+        validate_line_taxes(&mut tx, user_id, &client, items).await?;
+    }
+
+    let frequency = req.frequency.unwrap_or(existing.frequency);
+    let start_date = req.start_date.unwrap_or(existing.start_date);
+
+    // Occurrences are counted from the start date, so moving the schedule
+    // under already generated invoices would put the next run in the past
+    if existing.occurrences_generated > 0
+        && (frequency != existing.frequency || start_date != existing.start_date)
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "The schedule of a profile that has generated invoices cannot be changed; create a new profile instead"
+            })),
+        ));
+    }
+
+    let end_date = req.end_date.or(existing.end_date);
+    let max_occurrences = req.max_occurrences.or(existing.max_occurrences);
+
+    if end_date.is_some_and(|end_date| end_date < start_date) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "End date cannot be before the start date" })),
+        ));
+    }
+
+    let profile = sqlx::query_as::<_, RecurringProfile>(
+        r#"
+        UPDATE recurring_profiles
+        SET
+            client_id = $1,
+            name = COALESCE($2, name),
+            frequency = $3,
+            start_date = $4,
+            end_date = $5,
+            max_occurrences = $6,
+            next_run_date = $7,
+            auto_send = COALESCE($8, auto_send),
+            due_days = COALESCE($9, due_days),
+            is_active = COALESCE($10, is_active),
+            notes = COALESCE($11, notes),
+            tax_rounding = COALESCE($12, tax_rounding),
+            updated_at = NOW()
+        WHERE id = $13 AND user_id = $14
+        RETURNING *
+        "#,
+    )
+    .bind(client_id)
+    .bind(req.name)
+    .bind(frequency)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(max_occurrences)
+    .bind(next_run_date(
+        start_date,
+        frequency,
+        end_date,
+        max_occurrences,
+        existing.occurrences_generated,
+    ))
+    .bind(req.auto_send)
+    .bind(req.due_days)
+    .bind(req.is_active)
+    .bind(req.notes)
+    .bind(req.tax_rounding)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update recurring profile: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update recurring profile" })),
+        )
+    })?;
+
+    let line_items = if let Some(items) = &req.line_items {
+        replace_line_items(&mut tx, id, items).await?
+    } else {
+        fetch_line_items(&mut tx, id).await?
+    };
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit recurring profile update: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update recurring profile" })),
+        )
+    })?;
+
+    Ok(Json(RecurringProfileWithLineItems {
+        profile,
+        line_items,
+    }))
+}
+
+pub async fn delete_recurring_profile(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM recurring_profiles WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete recurring profile: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete recurring profile" })),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Recurring profile not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_monthly_occurrences_clamp_to_month_end() {
+        let start = date(2026, 1, 31);
+
+        assert_eq!(
+            occurrence_date(start, Frequency::Monthly, 1),
+            Some(date(2026, 2, 28))
+        );
+        assert_eq!(
+            occurrence_date(start, Frequency::Monthly, 2),
+            Some(date(2026, 3, 31))
+        );
+        assert_eq!(
+            occurrence_date(start, Frequency::Weekly, 2),
+            Some(date(2026, 2, 14))
+        );
+        assert_eq!(
+            occurrence_date(date(2028, 2, 29), Frequency::Yearly, 1),
+            Some(date(2029, 2, 28))
+        );
+    }
+
+    #[test]
+    fn test_next_run_date_respects_limits() {
+        let start = date(2026, 1, 1);
+
+        assert_eq!(
+            next_run_date(start, Frequency::Monthly, None, None, 3),
+            Some(date(2026, 4, 1))
+        );
+        assert_eq!(
+            next_run_date(start, Frequency::Monthly, None, Some(3), 3),
+            None
+        );
+        assert_eq!(
+            next_run_date(start, Frequency::Monthly, Some(date(2026, 3, 15)), None, 3),
+            None
+        );
+    }
+}
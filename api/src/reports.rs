@@ -0,0 +1,1838 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{Datelike, Months, NaiveDate};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::accounts::CashFlowActivity;
+use crate::currencies;
+use crate::ledger::AccountType;
+
+/// When income and tax are recognised: when invoiced (accrual) or when the
+/// client pays (cash). Expenses are recorded as paid, so they count the same
+/// on both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Basis {
+    #[default]
+    Accrual,
+    Cash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// The period of the same length immediately before.
+    PreviousPeriod,
+    /// The same period one year earlier.
+    PreviousYear,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// First and last day of a report column.
+type Period = (NaiveDate, NaiveDate);
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    pub from: NaiveDate,
+    /// Last day of the period; the balance sheet is drawn up as of this date.
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub basis: Basis,
+    /// Adds a column for an earlier period.
+    pub compare: Option<Comparison>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportColumn {
+    pub label: String,
+    pub from: Option<NaiveDate>,
+    pub to: NaiveDate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportRow {
+    pub account_id: Option<Uuid>,
+    pub code: Option<String>,
+    pub name: String,
+    /// One amount per column.
+    pub amounts: Vec<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportSection {
+    pub name: String,
+    pub rows: Vec<ReportRow>,
+    pub totals: Vec<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub title: String,
+    pub basis: Basis,
+    pub columns: Vec<ReportColumn>,
+    pub sections: Vec<ReportSection>,
+    pub summary: Vec<ReportRow>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AgingQuery {
+    /// Ages balances as they stood at the end of this day; defaults to today.
+    pub as_of: Option<NaiveDate>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// Outstanding balances by days past due.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct AgingBuckets {
+    pub current: Decimal,
+    pub days_1_30: Decimal,
+    pub days_31_60: Decimal,
+    pub days_61_90: Decimal,
+    pub days_over_90: Decimal,
+    pub total: Decimal,
+}
+
+impl AgingBuckets {
+    pub fn add(&mut self, days_overdue: i64, amount: Decimal) {
+        let bucket = match days_overdue {
+            ..=0 => &mut self.current,
+            1..=30 => &mut self.days_1_30,
+            31..=60 => &mut self.days_31_60,
+            61..=90 => &mut self.days_61_90,
+            _ => &mut self.days_over_90,
+        };
+        *bucket += amount;
+        self.total += amount;
+    }
+
+    fn amounts(&self) -> [Decimal; 6] {
+        [
+            self.current,
+            self.days_1_30,
+            self.days_31_60,
+            self.days_61_90,
+            self.days_over_90,
+            self.total,
+        ]
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AgingInvoice {
+    pub invoice_id: Uuid,
+    pub invoice_number: Option<String>,
+    pub issue_date: NaiveDate,
+    /// Invoices without a due date are due on issue.
+    pub due_date: Option<NaiveDate>,
+    #[sqlx(skip)]
+    pub days_overdue: i64,
+    pub currency: String,
+    /// Outstanding in the invoice's currency.
+    pub balance: Decimal,
+    /// Outstanding at the invoice's exchange rate, as bucketed.
+    pub home_balance: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClientAging {
+    pub client_id: Uuid,
+    pub client_name: String,
+    #[serde(flatten)]
+    pub buckets: AgingBuckets,
+    pub invoices: Vec<AgingInvoice>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArAgingReport {
+    pub as_of: NaiveDate,
+    pub clients: Vec<ClientAging>,
+    pub totals: AgingBuckets,
+}
+
+#[derive(sqlx::FromRow)]
+struct AgingRow {
+    client_id: Uuid,
+    client_name: String,
+    #[sqlx(flatten)]
+    invoice: AgingInvoice,
+}
+
+/// How often sales tax returns are filed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilingPeriod {
+    Monthly,
+    #[default]
+    Quarterly,
+    Annual,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SalesTaxQuery {
+    #[serde(default)]
+    pub period: FilingPeriod,
+    /// Any day in the filing period to report; defaults to today.
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
+    pub basis: Basis,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// Tax collected and paid at one rate. Rows without a rate hold tax entered
+/// on expenses without choosing one.
+#[derive(Debug, Serialize)]
+pub struct SalesTaxRow {
+    pub tax_rate_id: Option<Uuid>,
+    pub name: String,
+    pub rate: Option<Decimal>,
+    pub taxable_sales: Decimal,
+    pub tax_collected: Decimal,
+    pub taxable_purchases: Decimal,
+    pub tax_paid: Decimal,
+    /// Tax collected less tax paid: owing when positive, refundable when negative.
+    pub net_tax: Decimal,
+}
+
+/// Document a tax line comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+enum TaxSource {
+    Invoice,
+    CreditNote,
+    Payment,
+    Expense,
+}
+
+/// One tax line of a document, in the document currency.
+#[derive(Debug, sqlx::FromRow)]
+struct TaxEntry {
+    tax_rate_id: Option<Uuid>,
+    name: Option<String>,
+    rate: Option<Decimal>,
+    source: TaxSource,
+    /// Issue, settlement or expense date
+    date: NaiveDate,
+    voided_on: Option<NaiveDate>,
+    taxable_amount: Decimal,
+    amount: Decimal,
+    exchange_rate: Decimal,
+    /// Part of the line that counts on cash basis: the allocation's share of
+    /// the invoice, or the share of a credit note left over as client credit.
+    cash_share: Decimal,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct SalesTaxTotals {
+    pub tax_collected: Decimal,
+    pub tax_paid: Decimal,
+    pub net_tax: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalesTaxReport {
+    pub period: FilingPeriod,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub basis: Basis,
+    pub rates: Vec<SalesTaxRow>,
+    pub totals: SalesTaxTotals,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FxGainLossQuery {
+    pub from: NaiveDate,
+    /// Open foreign currency balances are revalued at rates as of this day.
+    pub to: NaiveDate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FxDocumentType {
+    /// Receivable still owed on an invoice.
+    Invoice,
+    /// Payment received but not yet applied, owed back as credit.
+    Payment,
+}
+
+/// A foreign currency balance still open at the end of the period.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct FxExposure {
+    pub document_type: FxDocumentType,
+    pub document_id: Uuid,
+    pub number: Option<String>,
+    pub currency: String,
+    pub balance: Decimal,
+    /// Rate the document was posted at.
+    pub exchange_rate: Decimal,
+    /// Latest stored rate on or before the end of the period, if any.
+    #[sqlx(skip)]
+    pub closing_rate: Option<Decimal>,
+    /// Change in home value at the closing rate; positive is a gain.
+    #[sqlx(skip)]
+    pub unrealised: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FxGainLossReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub home_currency: String,
+    /// Net gain booked to the exchange gain/loss account in the period.
+    pub realised: Decimal,
+    /// Net gain on open balances at closing rates, leaving out balances in
+    /// currencies without a stored rate.
+    pub unrealised: Decimal,
+    pub exposures: Vec<FxExposure>,
+}
+
+/// Debits and credits to one account over a report period.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub(crate) struct AccountTotal {
+    pub account_id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub account_type: AccountType,
+    pub cash_flow_activity: Option<CashFlowActivity>,
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+impl AccountTotal {
+    /// Net movement with the account's normal sign, so income, liabilities
+    /// and equity are positive when in credit.
+    pub fn balance(&self) -> Decimal {
+        if self.account_type.is_debit_normal() {
+            self.debit - self.credit
+        } else {
+            self.credit - self.debit
+        }
+    }
+
+    /// Cash brought in by the other side of cash movements on this account.
+    pub fn cash_effect(&self) -> Decimal {
+        self.credit - self.debit
+    }
+
+    pub fn activity(&self) -> CashFlowActivity {
+        self.cash_flow_activity
+            .unwrap_or_else(|| CashFlowActivity::default_for(self.account_type))
+    }
+}
+
+fn is_month_end(date: NaiveDate) -> bool {
+    date.succ_opt().is_none_or(|next| next.day() == 1)
+}
+
+fn month_end(date: NaiveDate) -> NaiveDate {
+    date.with_day(1)
+        .and_then(|first| first.checked_add_months(Months::new(1)))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(date)
+}
+
+fn months_back(date: NaiveDate, months: u32, keep_month_end: bool) -> NaiveDate {
+    let shifted = date.checked_sub_months(Months::new(months)).unwrap_or(date);
+    if keep_month_end {
+        month_end(shifted)
+    } else {
+        shifted
+    }
+}
+
+/// The earlier period compared against `from..=to`. Periods of whole
+/// calendar months move back by whole months, so a quarter compares with
+/// the previous quarter rather than the same number of days.
+pub fn comparison_period(from: NaiveDate, to: NaiveDate, comparison: Comparison) -> Period {
+    let whole_months = from.day() == 1 && is_month_end(to);
+
+    match comparison {
+        Comparison::PreviousYear => (
+            months_back(from, 12, false),
+            months_back(to, 12, is_month_end(to)),
+        ),
+        Comparison::PreviousPeriod if whole_months => {
+            let months =
+                (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32 + 1;
+            (
+                months_back(from, months as u32, false),
+                months_back(to, months as u32, true),
+            )
+        }
+        Comparison::PreviousPeriod => {
+            let length = to - from + chrono::Duration::days(1);
+            (from - length, to - length)
+        }
+    }
+}
+
+/// The report period followed by the comparison period, if any.
+fn report_periods(query: &ReportQuery) -> Result<Vec<Period>, (StatusCode, Json<Value>)> {
+    if query.from > query.to {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "from must be on or before to" })),
+        ));
+    }
+
+    let mut periods = vec![(query.from, query.to)];
+    if let Some(comparison) = query.compare {
+        periods.push(comparison_period(query.from, query.to, comparison));
+    }
+
+    Ok(periods)
+}
+
+/// One row per account matching `include`, with an amount per column.
+/// Accounts without any amount are left out.
+fn section(
+    name: &str,
+    columns: &[Vec<AccountTotal>],
+    include: impl Fn(&AccountTotal) -> bool,
+    amount: impl Fn(&AccountTotal) -> Decimal,
+) -> ReportSection {
+    let mut rows: BTreeMap<(String, Uuid), ReportRow> = BTreeMap::new();
+
+    for (index, totals) in columns.iter().enumerate() {
+        for total in totals.iter().filter(|total| include(total)) {
+            let row = rows
+                .entry((total.code.clone(), total.account_id))
+                .or_insert_with(|| ReportRow {
+                    account_id: Some(total.account_id),
+                    code: Some(total.code.clone()),
+                    name: total.name.clone(),
+                    amounts: vec![Decimal::ZERO; columns.len()],
+                });
+            row.amounts[index] += amount(total);
+        }
+    }
+
+    let rows: Vec<ReportRow> = rows
+        .into_values()
+        .filter(|row| row.amounts.iter().any(|amount| !amount.is_zero()))
+        .collect();
+    let totals = (0..columns.len())
+        .map(|index| rows.iter().map(|row| row.amounts[index]).sum())
+        .collect();
+
+    ReportSection {
+        name: name.to_string(),
+        rows,
+        totals,
+    }
+}
+
+fn summary_row(name: &str, amounts: Vec<Decimal>) -> ReportRow {
+    ReportRow {
+        account_id: None,
+        code: None,
+        name: name.to_string(),
+        amounts,
+    }
+}
+
+fn combine(a: &[Decimal], b: &[Decimal], op: impl Fn(Decimal, Decimal) -> Decimal) -> Vec<Decimal> {
+    a.iter().zip(b).map(|(a, b)| op(*a, *b)).collect()
+}
+
+fn of_type(account_type: AccountType) -> impl Fn(&AccountTotal) -> bool {
+    move |total| total.account_type == account_type
+}
+
+pub(crate) fn profit_and_loss(
+    columns: &[Vec<AccountTotal>],
+) -> (Vec<ReportSection>, Vec<ReportRow>) {
+    let income = section(
+        "Income",
+        columns,
+        of_type(AccountType::Income),
+        AccountTotal::balance,
+    );
+    let expenses = section(
+        "Expenses",
+        columns,
+        of_type(AccountType::Expense),
+        AccountTotal::balance,
+    );
+    let net_income = combine(&income.totals, &expenses.totals, |a, b| a - b);
+
+    (
+        vec![income, expenses],
+        vec![summary_row("Net income", net_income)],
+    )
+}
+
+/// Balances as of each column's date. Income less expenses to date is shown
+/// as current earnings under equity, so assets equal liabilities plus equity.
+pub(crate) fn balance_sheet(columns: &[Vec<AccountTotal>]) -> (Vec<ReportSection>, Vec<ReportRow>) {
+    let assets = section(
+        "Assets",
+        columns,
+        of_type(AccountType::Asset),
+        AccountTotal::balance,
+    );
+    let liabilities = section(
+        "Liabilities",
+        columns,
+        of_type(AccountType::Liability),
+        AccountTotal::balance,
+    );
+    let mut equity = section(
+        "Equity",
+        columns,
+        of_type(AccountType::Equity),
+        AccountTotal::balance,
+    );
+
+    let (_, earnings) = profit_and_loss(columns);
+    let current_earnings = earnings
+        .into_iter()
+        .next()
+        .map(|row| row.amounts)
+        .unwrap_or_default();
+    equity.totals = combine(&equity.totals, &current_earnings, |a, b| a + b);
+    equity
+        .rows
+        .push(summary_row("Current earnings", current_earnings));
+
+    let liabilities_and_equity = combine(&liabilities.totals, &equity.totals, |a, b| a + b);
+
+    (
+        vec![assets, liabilities, equity],
+        vec![summary_row(
+            "Total liabilities and equity",
+            liabilities_and_equity,
+        )],
+    )
+}
+
+/// Cash movements grouped by activity, from the other side of each cash
+/// entry. `cash_balances` holds the opening and closing cash of each column.
+pub(crate) fn cash_flow(
+    columns: &[Vec<AccountTotal>],
+    cash_balances: &[(Decimal, Decimal)],
+) -> (Vec<ReportSection>, Vec<ReportRow>) {
+    let activity =
+        |activity: CashFlowActivity| move |total: &AccountTotal| total.activity() == activity;
+
+    let sections = vec![
+        section(
+            "Operating activities",
+            columns,
+            activity(CashFlowActivity::Operating),
+            AccountTotal::cash_effect,
+        ),
+        section(
+            "Investing activities",
+            columns,
+            activity(CashFlowActivity::Investing),
+            AccountTotal::cash_effect,
+        ),
+        section(
+            "Financing activities",
+            columns,
+            activity(CashFlowActivity::Financing),
+            AccountTotal::cash_effect,
+        ),
+    ];
+
+    let net_change = (0..columns.len())
+        .map(|index| sections.iter().map(|section| section.totals[index]).sum())
+        .collect();
+
+    (
+        sections,
+        vec![
+            summary_row("Net change in cash", net_change),
+            summary_row(
+                "Opening cash",
+                cash_balances.iter().map(|(opening, _)| *opening).collect(),
+            ),
+            summary_row(
+                "Closing cash",
+                cash_balances.iter().map(|(_, closing)| *closing).collect(),
+            ),
+        ],
+    )
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(fields: impl IntoIterator<Item = String>) -> String {
+    let fields: Vec<String> = fields.into_iter().map(|field| csv_field(&field)).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Splits CSV text into records of fields. Quoted fields may contain commas,
+/// doubled quotes and line breaks; blank lines are skipped.
+pub(crate) fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            _ if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|field| !field.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+
+    record.push(field);
+    if record.iter().any(|field| !field.is_empty()) {
+        records.push(record);
+    }
+
+    records
+}
+
+/// Flat CSV with one line per account, section total and summary row.
+pub fn to_csv(report: &Report) -> String {
+    let amounts = |amounts: &[Decimal]| {
+        amounts
+            .iter()
+            .map(|amount| amount.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let mut csv = csv_line(
+        ["Section", "Code", "Account"]
+            .into_iter()
+            .map(str::to_string)
+            .chain(report.columns.iter().map(|column| column.label.clone())),
+    );
+
+    for section in &report.sections {
+        for row in &section.rows {
+            csv.push_str(&csv_line(
+                [
+                    section.name.clone(),
+                    row.code.clone().unwrap_or_default(),
+                    row.name.clone(),
+                ]
+                .into_iter()
+                .chain(amounts(&row.amounts)),
+            ));
+        }
+        csv.push_str(&csv_line(
+            [
+                section.name.clone(),
+                String::new(),
+                format!("Total {}", section.name.to_lowercase()),
+            ]
+            .into_iter()
+            .chain(amounts(&section.totals)),
+        ));
+    }
+
+    for row in &report.summary {
+        csv.push_str(&csv_line(
+            [String::new(), String::new(), row.name.clone()]
+                .into_iter()
+                .chain(amounts(&row.amounts)),
+        ));
+    }
+
+    csv
+}
+
+fn render(report: Report, format: ReportFormat, filename: &str) -> Response {
+    match format {
+        ReportFormat::Json => Json(report).into_response(),
+        ReportFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.csv\"", filename),
+                ),
+            ],
+            to_csv(&report),
+        )
+            .into_response(),
+    }
+}
+
+fn report_error(e: sqlx::Error) -> (StatusCode, Json<Value>) {
+    tracing::error!("Failed to build report: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": "Failed to build report" })),
+    )
+}
+
+/// Debits and credits per account for entries dated `from..=to` (from the
+/// start of the ledger when `from` is `None`).
+///
+/// On cash basis invoices and credit notes are left out; instead each payment
+/// allocation recognises its share of the invoice's sales and tax at the
+/// invoice's exchange rate, dated like the ledger entry that settled the
+/// receivable.
+pub(crate) async fn account_totals(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    from: Option<NaiveDate>,
+    to: NaiveDate,
+    basis: Basis,
+) -> Result<Vec<AccountTotal>, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, AccountTotal>(
+        r#"
+        WITH recognised AS (
+            SELECT
+                ROUND(pa.amount * i.exchange_rate, 2) AS amount,
+                ROUND(pa.amount * i.subtotal / i.total * i.exchange_rate, 2) AS sales
+            FROM payment_allocation_dates pa
+            JOIN invoices i ON i.id = pa.invoice_id
+            WHERE $4
+                AND pa.user_id = $1
+                AND i.total > 0
+                AND pa.settled_on BETWEEN COALESCE($2, '-infinity'::date) AND $3
+        ),
+        movements AS (
+            SELECT l.account_id, l.debit, l.credit
+            FROM journal_lines l
+            JOIN journal_entries e ON e.id = l.journal_entry_id
+            WHERE e.user_id = $1
+                AND ($2::date IS NULL OR e.entry_date >= $2)
+                AND e.entry_date <= $3
+                AND NOT ($4 AND e.source_type IN ('invoice', 'credit_note'))
+            UNION ALL
+            SELECT a.id, c.debit, c.credit
+            FROM (
+                SELECT 'accounts_receivable' AS system_key, SUM(amount) AS debit, 0 AS credit
+                FROM recognised
+                UNION ALL
+                SELECT 'sales', 0, SUM(sales) FROM recognised
+                UNION ALL
+                SELECT 'sales_tax_payable', 0, SUM(amount - sales) FROM recognised
+            ) c
+            JOIN accounts a ON a.user_id = $1 AND a.system_key = c.system_key
+            WHERE c.debit IS NOT NULL AND c.credit IS NOT NULL
+        )
+        SELECT
+            a.id AS account_id, a.code, a.name, a.account_type, a.cash_flow_activity,
+            SUM(m.debit) AS debit, SUM(m.credit) AS credit
+        FROM movements m
+        JOIN accounts a ON a.id = m.account_id
+        GROUP BY a.id
+        ORDER BY a.code
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .bind(basis == Basis::Cash)
+    .fetch_all(conn)
+    .await
+    .map_err(report_error)
+}
+
+/// Movements on the accounts on the other side of cash entries dated
+/// `from..=to`, with opening and closing cash. Cash is the Cash account and
+/// its sub-accounts.
+async fn cash_movements(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<(Vec<AccountTotal>, (Decimal, Decimal)), (StatusCode, Json<Value>)> {
+    let totals = sqlx::query_as::<_, AccountTotal>(
+        r#"
+        WITH RECURSIVE cash_accounts AS (
+            SELECT id FROM accounts WHERE user_id = $1 AND system_key = 'cash'
+            UNION
+            SELECT a.id FROM accounts a JOIN cash_accounts c ON a.parent_id = c.id
+        ),
+        cash_entries AS (
+            SELECT DISTINCT l.journal_entry_id AS id
+            FROM journal_lines l
+            JOIN journal_entries e ON e.id = l.journal_entry_id
+            WHERE e.user_id = $1
+                AND e.entry_date BETWEEN $2 AND $3
+                AND l.account_id IN (SELECT id FROM cash_accounts)
+        )
+        SELECT
+            a.id AS account_id, a.code, a.name, a.account_type, a.cash_flow_activity,
+            SUM(l.debit) AS debit, SUM(l.credit) AS credit
+        FROM journal_lines l
+        JOIN accounts a ON a.id = l.account_id
+        WHERE l.journal_entry_id IN (SELECT id FROM cash_entries)
+            AND l.account_id NOT IN (SELECT id FROM cash_accounts)
+        GROUP BY a.id
+        ORDER BY a.code
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(report_error)?;
+
+    let balances = sqlx::query_as::<_, (Decimal, Decimal)>(
+        r#"
+        WITH RECURSIVE cash_accounts AS (
+            SELECT id FROM accounts WHERE user_id = $1 AND system_key = 'cash'
+            UNION
+            SELECT a.id FROM accounts a JOIN cash_accounts c ON a.parent_id = c.id
+        )
+        SELECT
+            COALESCE(SUM(l.debit - l.credit) FILTER (WHERE e.entry_date < $2), 0),
+            COALESCE(SUM(l.debit - l.credit), 0)
+        FROM journal_lines l
+        JOIN journal_entries e ON e.id = l.journal_entry_id
+        WHERE e.user_id = $1
+            AND e.entry_date <= $3
+            AND l.account_id IN (SELECT id FROM cash_accounts)
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_one(conn)
+    .await
+    .map_err(report_error)?;
+
+    Ok((totals, balances))
+}
+
+async fn acquire(
+    pool: &PgPool,
+) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>, (StatusCode, Json<Value>)> {
+    pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to build report" })),
+        )
+    })
+}
+
+fn period_columns(periods: &[Period]) -> Vec<ReportColumn> {
+    periods
+        .iter()
+        .map(|(from, to)| ReportColumn {
+            label: format!("{} to {}", from, to),
+            from: Some(*from),
+            to: *to,
+        })
+        .collect()
+}
+
+pub async fn get_profit_and_loss(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<ReportQuery>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let periods = report_periods(&query)?;
+    let mut conn = acquire(&pool).await?;
+
+    let mut columns = Vec::new();
+    for (from, to) in &periods {
+        columns.push(account_totals(&mut conn, user_id, Some(*from), *to, query.basis).await?);
+    }
+
+    let (sections, summary) = profit_and_loss(&columns);
+    let report = Report {
+        title: "Profit and loss".to_string(),
+        basis: query.basis,
+        columns: period_columns(&periods),
+        sections,
+        summary,
+    };
+
+    Ok(render(
+        report,
+        query.format,
+        &format!("profit-and-loss-{}-{}", query.from, query.to),
+    ))
+}
+
+pub async fn get_balance_sheet(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<ReportQuery>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let periods = report_periods(&query)?;
+    let mut conn = acquire(&pool).await?;
+
+    let mut columns = Vec::new();
+    for (_, to) in &periods {
+        columns.push(account_totals(&mut conn, user_id, None, *to, query.basis).await?);
+    }
+
+    let (sections, summary) = balance_sheet(&columns);
+    let report = Report {
+        title: "Balance sheet".to_string(),
+        basis: query.basis,
+        columns: periods
+            .iter()
+            .map(|(_, to)| ReportColumn {
+                label: format!("As of {}", to),
+                from: None,
+                to: *to,
+            })
+            .collect(),
+        sections,
+        summary,
+    };
+
+    Ok(render(
+        report,
+        query.format,
+        &format!("balance-sheet-{}", query.to),
+    ))
+}
+
+/// Cash flow statement. Cash moves the same way on either basis, so `basis`
+/// only labels the report.
+pub async fn get_cash_flow(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<ReportQuery>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let periods = report_periods(&query)?;
+    let mut conn = acquire(&pool).await?;
+
+    let mut columns = Vec::new();
+    let mut cash_balances = Vec::new();
+    for (from, to) in &periods {
+        let (totals, balances) = cash_movements(&mut conn, user_id, *from, *to).await?;
+        columns.push(totals);
+        cash_balances.push(balances);
+    }
+
+    let (sections, summary) = cash_flow(&columns, &cash_balances);
+    let report = Report {
+        title: "Cash flow".to_string(),
+        basis: query.basis,
+        columns: period_columns(&periods),
+        sections,
+        summary,
+    };
+
+    Ok(render(
+        report,
+        query.format,
+        &format!("cash-flow-{}-{}", query.from, query.to),
+    ))
+}
+
+/// Groups outstanding invoices by client, ageing each from its due date.
+/// Rows must be ordered by client.
+fn ar_aging(as_of: NaiveDate, rows: Vec<AgingRow>) -> ArAgingReport {
+    let mut clients: Vec<ClientAging> = Vec::new();
+    let mut totals = AgingBuckets::default();
+
+    for AgingRow {
+        client_id,
+        client_name,
+        mut invoice,
+    } in rows
+    {
+        invoice.days_overdue = (as_of - invoice.due_date.unwrap_or(invoice.issue_date)).num_days();
+        totals.add(invoice.days_overdue, invoice.home_balance);
+
+        if clients
+            .last()
+            .is_none_or(|client| client.client_id != client_id)
+        {
+            clients.push(ClientAging {
+                client_id,
+                client_name,
+                buckets: AgingBuckets::default(),
+                invoices: Vec::new(),
+            });
+        }
+        let client = clients.last_mut().expect("client was just pushed");
+        client
+            .buckets
+            .add(invoice.days_overdue, invoice.home_balance);
+        client.invoices.push(invoice);
+    }
+
+    ArAgingReport {
+        as_of,
+        clients,
+        totals,
+    }
+}
+
+fn aging_csv(report: &ArAgingReport) -> String {
+    let line = |name: &str, buckets: &AgingBuckets| {
+        csv_line(
+            std::iter::once(name.to_string())
+                .chain(buckets.amounts().iter().map(|amount| amount.to_string())),
+        )
+    };
+
+    let mut csv = csv_line(
+        [
+            "Client", "Current", "1-30", "31-60", "61-90", "90+", "Total",
+        ]
+        .into_iter()
+        .map(str::to_string),
+    );
+    for client in &report.clients {
+        csv.push_str(&line(&client.client_name, &client.buckets));
+    }
+    csv.push_str(&line("Total", &report.totals));
+
+    csv
+}
+
+/// Accounts receivable aging. Balances are rebuilt as of the given day from
+/// payments and credit notes dated up to it, so past reports can be
+/// reproduced; invoices voided later still count as outstanding then.
+/// Buckets are in the home currency at each invoice's exchange rate.
+pub async fn get_ar_aging(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<AgingQuery>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let as_of = query
+        .as_of
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+    let rows = sqlx::query_as::<_, AgingRow>(
+        r#"
+        SELECT *, ROUND(balance * exchange_rate, 2) AS home_balance FROM (
+            SELECT
+                i.client_id,
+                CASE
+                    WHEN c.client_type = 'company' THEN COALESCE(c.company_name, '')
+                    ELSE CONCAT_WS(' ', c.first_name, c.last_name)
+                END AS client_name,
+                i.id AS invoice_id,
+                i.invoice_number,
+                i.issue_date,
+                i.due_date,
+                i.currency,
+                i.exchange_rate,
+                i.total
+                    - COALESCE((
+                        SELECT SUM(a.amount) FROM payment_allocation_dates a
+                        WHERE a.invoice_id = i.id AND a.settled_on <= $2
+                    ), 0)
+                    - COALESCE((
+                        SELECT SUM(cn.amount_applied) FROM credit_notes cn
+                        WHERE cn.invoice_id = i.id AND cn.issue_date <= $2
+                    ), 0)
+                    - COALESCE((
+                        SELECT SUM(ca.amount) FROM credit_note_allocations ca
+                        WHERE ca.invoice_id = i.id AND ca.applied_on <= $2
+                    ), 0) AS balance
+            FROM invoices i
+            JOIN clients c ON c.id = i.client_id
+            WHERE i.user_id = $1
+                AND i.status <> 'draft'
+                AND i.issue_date <= $2
+                AND (i.voided_at IS NULL OR i.voided_at::date > $2)
+        ) outstanding
+        WHERE balance > 0
+        ORDER BY client_name, client_id, COALESCE(due_date, issue_date), invoice_number
+        "#,
+    )
+    .bind(user_id)
+    .bind(as_of)
+    .fetch_all(&pool)
+    .await
+    .map_err(report_error)?;
+
+    let report = ar_aging(as_of, rows);
+
+    Ok(match query.format {
+        ReportFormat::Json => Json(report).into_response(),
+        ReportFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"ar-aging-{}.csv\"", as_of),
+                ),
+            ],
+            aging_csv(&report),
+        )
+            .into_response(),
+    })
+}
+
+/// The calendar month, quarter or year containing `date`.
+pub fn filing_period(period: FilingPeriod, date: NaiveDate) -> Period {
+    let (first_month, months) = match period {
+        FilingPeriod::Monthly => (date.month(), 1),
+        FilingPeriod::Quarterly => ((date.month() - 1) / 3 * 3 + 1, 3),
+        FilingPeriod::Annual => (1, 12),
+    };
+    let from = NaiveDate::from_ymd_opt(date.year(), first_month, 1).unwrap_or(date);
+    let to = month_end(
+        from.checked_add_months(Months::new(months - 1))
+            .unwrap_or(from),
+    );
+
+    (from, to)
+}
+
+/// Sums the tax entries counting in `from..=to` on `basis` per tax rate, in
+/// the home currency.
+fn sales_tax_rows(
+    entries: &[TaxEntry],
+    basis: Basis,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<SalesTaxRow> {
+    let in_period = |date: NaiveDate| date >= from && date <= to;
+    let home = |entry: &TaxEntry, value: Decimal, share: Decimal| {
+        (value * share * entry.exchange_rate)
+            .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+    };
+
+    let mut rows: Vec<SalesTaxRow> = Vec::new();
+    for entry in entries {
+        // (sign, share) of each movement of the line inside the period
+        let movements: Vec<(Decimal, Decimal)> = match (entry.source, basis) {
+            (TaxSource::Invoice, Basis::Accrual) => [
+                Some(entry.date)
+                    .filter(|&date| in_period(date))
+                    .map(|_| Decimal::ONE),
+                entry
+                    .voided_on
+                    .filter(|&date| in_period(date))
+                    .map(|_| Decimal::NEGATIVE_ONE),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|sign| (sign, Decimal::ONE))
+            .collect(),
+            (TaxSource::CreditNote, Basis::Accrual) if in_period(entry.date) => {
+                vec![(Decimal::NEGATIVE_ONE, Decimal::ONE)]
+            }
+            (TaxSource::CreditNote, Basis::Cash)
+                if in_period(entry.date) && !entry.cash_share.is_zero() =>
+            {
+                vec![(Decimal::NEGATIVE_ONE, entry.cash_share)]
+            }
+            (TaxSource::Payment, Basis::Cash) | (TaxSource::Expense, _)
+                if in_period(entry.date) =>
+            {
+                vec![(Decimal::ONE, entry.cash_share)]
+            }
+            _ => Vec::new(),
+        };
+        if movements.is_empty() {
+            continue;
+        }
+
+        let index = match rows
+            .iter()
+            .position(|row| row.tax_rate_id == entry.tax_rate_id)
+        {
+            Some(index) => index,
+            None => {
+                rows.push(SalesTaxRow {
+                    tax_rate_id: entry.tax_rate_id,
+                    name: entry
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| "No tax rate".to_string()),
+                    rate: entry.rate,
+                    taxable_sales: Decimal::ZERO,
+                    tax_collected: Decimal::ZERO,
+                    taxable_purchases: Decimal::ZERO,
+                    tax_paid: Decimal::ZERO,
+                    net_tax: Decimal::ZERO,
+                });
+                rows.len() - 1
+            }
+        };
+        let row = &mut rows[index];
+
+        for (sign, share) in movements {
+            let taxable = sign * home(entry, entry.taxable_amount, share);
+            let tax = sign * home(entry, entry.amount, share);
+            if entry.source == TaxSource::Expense {
+                row.taxable_purchases += taxable;
+                row.tax_paid += tax;
+            } else {
+                row.taxable_sales += taxable;
+                row.tax_collected += tax;
+            }
+            row.net_tax = row.tax_collected - row.tax_paid;
+        }
+    }
+
+    rows.sort_by(|a, b| {
+        (a.tax_rate_id.is_none(), &a.name, a.rate).cmp(&(b.tax_rate_id.is_none(), &b.name, b.rate))
+    });
+    rows
+}
+
+fn sales_tax_totals(rates: &[SalesTaxRow]) -> SalesTaxTotals {
+    rates
+        .iter()
+        .fold(SalesTaxTotals::default(), |mut totals, row| {
+            totals.tax_collected += row.tax_collected;
+            totals.tax_paid += row.tax_paid;
+            totals.net_tax += row.net_tax;
+            totals
+        })
+}
+
+fn sales_tax_csv(report: &SalesTaxReport) -> String {
+    let mut csv = csv_line(
+        [
+            "Tax",
+            "Rate",
+            "Taxable sales",
+            "Tax collected",
+            "Taxable purchases",
+            "Tax paid",
+            "Net tax",
+        ]
+        .into_iter()
+        .map(str::to_string),
+    );
+    for row in &report.rates {
+        csv.push_str(&csv_line([
+            row.name.clone(),
+            row.rate.map(|rate| rate.to_string()).unwrap_or_default(),
+            row.taxable_sales.to_string(),
+            row.tax_collected.to_string(),
+            row.taxable_purchases.to_string(),
+            row.tax_paid.to_string(),
+            row.net_tax.to_string(),
+        ]));
+    }
+    csv.push_str(&csv_line([
+        "Total".to_string(),
+        String::new(),
+        String::new(),
+        report.totals.tax_collected.to_string(),
+        String::new(),
+        report.totals.tax_paid.to_string(),
+        report.totals.net_tax.to_string(),
+    ]));
+
+    csv
+}
+
+/// Sales tax return for a filing period, per tax rate, in the home currency.
+///
+/// On accrual basis tax is collected when invoices are issued and given back
+/// when credit notes are issued or invoices voided, so a filed period never
+/// changes afterwards. On cash basis each payment allocation collects its
+/// share of the invoice's taxes when it settles, so credited amounts that are
+/// never paid never count; credit notes only give back the tax on the part
+/// that was already paid (left over as client credit), and that credit
+/// collects tax again when applied to another invoice. Tax paid on expenses
+/// counts on the expense date either way.
+pub async fn get_sales_tax(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<SalesTaxQuery>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let (from, to) = filing_period(
+        query.period,
+        query
+            .date
+            .unwrap_or_else(|| chrono::Utc::now().date_naive()),
+    );
+
+    let entries = sqlx::query_as::<_, TaxEntry>(
+        r#"
+        WITH entries AS (
+            SELECT it.tax_rate_id, 'invoice'::varchar AS source, i.issue_date AS date,
+                i.voided_at::date AS voided_on, it.taxable_amount, it.amount,
+                i.exchange_rate, 1::numeric AS cash_share
+            FROM invoice_taxes it
+            JOIN invoices i ON i.id = it.invoice_id
+            WHERE NOT $4
+                AND i.user_id = $1
+                AND i.status <> 'draft'
+                AND (i.issue_date BETWEEN $2 AND $3 OR i.voided_at::date BETWEEN $2 AND $3)
+            UNION ALL
+            SELECT ct.tax_rate_id, 'credit_note'::varchar, cn.issue_date, NULL,
+                ct.taxable_amount, ct.amount, i.exchange_rate,
+                (cn.total - cn.amount_applied) / cn.total
+            FROM credit_note_taxes ct
+            JOIN credit_notes cn ON cn.id = ct.credit_note_id
+            JOIN invoices i ON i.id = cn.invoice_id
+            WHERE cn.user_id = $1
+                AND cn.issue_date BETWEEN $2 AND $3
+            UNION ALL
+            SELECT it.tax_rate_id, 'payment'::varchar, pa.settled_on, NULL,
+                it.taxable_amount, it.amount, i.exchange_rate, pa.amount / i.total
+            FROM payment_allocation_dates pa
+            JOIN invoices i ON i.id = pa.invoice_id
+            JOIN invoice_taxes it ON it.invoice_id = i.id
+            WHERE $4
+                AND pa.user_id = $1
+                AND i.total > 0
+                AND pa.settled_on BETWEEN $2 AND $3
+            UNION ALL
+            SELECT it.tax_rate_id, 'payment'::varchar, ca.applied_on, NULL,
+                it.taxable_amount, it.amount, i.exchange_rate, ca.amount / i.total
+            FROM credit_note_allocations ca
+            JOIN invoices i ON i.id = ca.invoice_id
+            JOIN invoice_taxes it ON it.invoice_id = i.id
+            WHERE $4
+                AND i.user_id = $1
+                AND i.total > 0
+                AND ca.applied_on BETWEEN $2 AND $3
+            UNION ALL
+            SELECT e.tax_rate_id, 'expense'::varchar, e.expense_date, NULL,
+                e.amount, e.tax_amount, e.exchange_rate, 1
+            FROM expenses e
+            WHERE e.user_id = $1
+                AND e.expense_date BETWEEN $2 AND $3
+                AND (e.tax_rate_id IS NOT NULL OR e.tax_amount > 0)
+        )
+        SELECT e.*, r.name, r.rate
+        FROM entries e
+        LEFT JOIN tax_rates r ON r.id = e.tax_rate_id
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .bind(query.basis == Basis::Cash)
+    .fetch_all(&pool)
+    .await
+    .map_err(report_error)?;
+
+    let rates = sales_tax_rows(&entries, query.basis, from, to);
+
+    let report = SalesTaxReport {
+        period: query.period,
+        from,
+        to,
+        basis: query.basis,
+        totals: sales_tax_totals(&rates),
+        rates,
+    };
+
+    Ok(match query.format {
+        ReportFormat::Json => Json(report).into_response(),
+        ReportFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"sales-tax-{}-{}.csv\"", from, to),
+                ),
+            ],
+            sales_tax_csv(&report),
+        )
+            .into_response(),
+    })
+}
+
+/// Home currency gain on an open balance if it were settled at
+/// `closing_rate`. Receivables gain when the currency strengthens; unapplied
+/// payments are owed to the client, so they lose.
+fn unrealised_gain(exposure: &FxExposure, closing_rate: Decimal) -> Decimal {
+    let change = currencies::to_home(exposure.balance, closing_rate)
+        - currencies::to_home(exposure.balance, exposure.exchange_rate);
+    match exposure.document_type {
+        FxDocumentType::Invoice => change,
+        FxDocumentType::Payment => -change,
+    }
+}
+
+/// Realised exchange gains and losses booked in the period, and unrealised
+/// ones on foreign currency receivables and unapplied payments still open at
+/// its end, revalued at the latest stored rates.
+pub async fn get_fx_gain_loss(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<FxGainLossQuery>,
+) -> Result<Json<FxGainLossReport>, (StatusCode, Json<Value>)> {
+    if query.from > query.to {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Report period must not end before it starts" })),
+        ));
+    }
+
+    let mut conn = acquire(&pool).await?;
+    let home_currency = currencies::home_currency(&mut conn, user_id).await?;
+
+    let realised = sqlx::query_scalar::<_, Decimal>(
+        r#"
+        SELECT COALESCE(SUM(l.credit - l.debit), 0)
+        FROM journal_lines l
+        JOIN journal_entries e ON e.id = l.journal_entry_id
+        JOIN accounts a ON a.id = l.account_id
+        WHERE e.user_id = $1
+            AND a.system_key = 'exchange_gain_loss'
+            AND e.entry_date BETWEEN $2 AND $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(report_error)?;
+
+    let mut exposures = sqlx::query_as::<_, FxExposure>(
+        r#"
+        SELECT * FROM (
+            SELECT
+                'invoice'::varchar AS document_type,
+                i.id AS document_id,
+                i.invoice_number AS number,
+                i.currency,
+                i.total
+                    - COALESCE((
+                        SELECT SUM(a.amount) FROM payment_allocation_dates a
+                        WHERE a.invoice_id = i.id AND a.settled_on <= $3
+                    ), 0)
+                    - COALESCE((
+                        SELECT SUM(cn.amount_applied) FROM credit_notes cn
+                        WHERE cn.invoice_id = i.id AND cn.issue_date <= $3
+                    ), 0)
+                    - COALESCE((
+                        SELECT SUM(ca.amount) FROM credit_note_allocations ca
+                        WHERE ca.invoice_id = i.id AND ca.applied_on <= $3
+                    ), 0) AS balance,
+                i.exchange_rate
+            FROM invoices i
+            WHERE i.user_id = $1
+                AND i.currency <> $2
+                AND i.status <> 'draft'
+                AND i.issue_date <= $3
+                AND (i.voided_at IS NULL OR i.voided_at::date > $3)
+            UNION ALL
+            SELECT
+                'payment',
+                p.id,
+                p.reference,
+                p.currency,
+                p.amount
+                    - COALESCE((
+                        SELECT SUM(a.amount) FROM payment_allocation_dates a
+                        WHERE a.payment_id = p.id AND a.settled_on <= $3
+                    ), 0),
+                p.exchange_rate
+            FROM payments p
+            WHERE p.user_id = $1
+                AND p.currency <> $2
+                AND p.payment_date <= $3
+        ) open_balances
+        WHERE balance > 0
+        ORDER BY currency, document_type, number
+        "#,
+    )
+    .bind(user_id)
+    .bind(&home_currency)
+    .bind(query.to)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(report_error)?;
+
+    let mut closing_rates: BTreeMap<String, Option<Decimal>> = BTreeMap::new();
+    let mut unrealised = Decimal::ZERO;
+    for exposure in &mut exposures {
+        let closing_rate = match closing_rates.get(&exposure.currency) {
+            Some(rate) => *rate,
+            None => {
+                let rate = currencies::find_rate(
+                    &mut conn,
+                    user_id,
+                    &exposure.currency,
+                    &home_currency,
+                    query.to,
+                )
+                .await?;
+                closing_rates.insert(exposure.currency.clone(), rate);
+                rate
+            }
+        };
+
+        exposure.closing_rate = closing_rate;
+        exposure.unrealised = closing_rate.map(|rate| unrealised_gain(exposure, rate));
+        unrealised += exposure.unrealised.unwrap_or_default();
+    }
+
+    Ok(Json(FxGainLossReport {
+        from: query.from,
+        to: query.to,
+        home_currency,
+        realised,
+        unrealised,
+        exposures,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::{self, AccountRef, SystemAccount};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_comparison_periods() {
+        let q1 = (date("2026-01-01"), date("2026-03-31"));
+
+        assert_eq!(
+            comparison_period(q1.0, q1.1, Comparison::PreviousPeriod),
+            (date("2025-10-01"), date("2025-12-31"))
+        );
+        assert_eq!(
+            comparison_period(q1.0, q1.1, Comparison::PreviousYear),
+            (date("2025-01-01"), date("2025-03-31"))
+        );
+        assert_eq!(
+            comparison_period(
+                date("2026-03-01"),
+                date("2026-03-31"),
+                Comparison::PreviousPeriod
+            ),
+            (date("2026-02-01"), date("2026-02-28"))
+        );
+        assert_eq!(
+            comparison_period(
+                date("2028-02-01"),
+                date("2028-02-29"),
+                Comparison::PreviousYear
+            ),
+            (date("2027-02-01"), date("2027-02-28"))
+        );
+        assert_eq!(
+            comparison_period(
+                date("2026-03-10"),
+                date("2026-03-16"),
+                Comparison::PreviousPeriod
+            ),
+            (date("2026-03-03"), date("2026-03-09"))
+        );
+    }
+
+    #[test]
+    fn test_balance_sheet_balances_for_random_postings() {
+        let mut rng = StdRng::seed_from_u64(15);
+        let mut amount = || Decimal::new(rng.gen_range(0..10_000_000), 2);
+        let rent = AccountRef::Id(Uuid::new_v4());
+        let mut postings = Vec::new();
+
+        for _ in 0..200 {
+            postings.extend(ledger::invoice_postings(amount(), amount()));
+            postings.extend(ledger::payment_postings(amount()));
+            postings.extend(ledger::allocation_postings(amount(), amount()));
+            postings.extend(ledger::expense_postings(rent, amount(), amount()));
+        }
+
+        let mut totals: HashMap<AccountRef, AccountTotal> = HashMap::new();
+        for posting in postings {
+            let (code, account_type) = match posting.account {
+                AccountRef::System(SystemAccount::Cash) => ("1010", AccountType::Asset),
+                AccountRef::System(SystemAccount::AccountsReceivable) => {
+                    ("1100", AccountType::Asset)
+                }
+                AccountRef::System(SystemAccount::SalesTaxPayable) => {
+                    ("2200", AccountType::Liability)
+                }
+                AccountRef::System(SystemAccount::CustomerCredits) => {
+                    ("2300", AccountType::Liability)
+                }
+                AccountRef::System(SystemAccount::Sales) => ("4100", AccountType::Income),
+                _ => ("5600", AccountType::Expense),
+            };
+            let total = totals
+                .entry(posting.account)
+                .or_insert_with(|| AccountTotal {
+                    account_id: Uuid::new_v4(),
+                    code: code.to_string(),
+                    name: code.to_string(),
+                    account_type,
+                    cash_flow_activity: None,
+                    debit: Decimal::ZERO,
+                    credit: Decimal::ZERO,
+                });
+            total.debit += posting.debit;
+            total.credit += posting.credit;
+        }
+
+        let (sections, summary) = balance_sheet(&[totals.into_values().collect()]);
+        assert_eq!(sections[0].totals, summary[0].amounts);
+    }
+
+    #[test]
+    fn test_ar_aging_buckets_by_days_past_due_per_client() {
+        let as_of = date("2026-06-30");
+        let row = |client_id: Uuid, due_date: &str, balance: i64| AgingRow {
+            client_id,
+            client_name: client_id.to_string(),
+            invoice: AgingInvoice {
+                invoice_id: Uuid::new_v4(),
+                invoice_number: None,
+                issue_date: date("2026-01-01"),
+                due_date: Some(date(due_date)),
+                days_overdue: 0,
+                currency: "USD".to_string(),
+                balance: Decimal::from(balance),
+                home_balance: Decimal::from(balance),
+            },
+        };
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let report = ar_aging(
+            as_of,
+            vec![
+                row(a, "2026-06-30", 1),
+                row(a, "2026-06-29", 10),
+                row(a, "2026-05-31", 100),
+                row(b, "2026-05-01", 1000),
+                row(b, "2026-04-01", 10000),
+                row(b, "2026-03-31", 100000),
+            ],
+        );
+
+        assert_eq!(report.clients.len(), 2);
+        assert_eq!(report.clients[0].buckets.current, Decimal::from(1));
+        assert_eq!(report.clients[0].buckets.days_1_30, Decimal::from(110));
+        assert_eq!(report.clients[1].buckets.days_31_60, Decimal::from(1000));
+        assert_eq!(report.clients[1].buckets.days_61_90, Decimal::from(10000));
+        assert_eq!(
+            report.clients[1].buckets.days_over_90,
+            Decimal::from(100000)
+        );
+        assert_eq!(report.totals.total, Decimal::from(111111));
+        assert_eq!(report.clients[1].invoices[2].days_overdue, 91);
+    }
+
+    #[test]
+    fn test_filing_period_covers_calendar_month_quarter_and_year() {
+        assert_eq!(
+            filing_period(FilingPeriod::Monthly, date("2028-02-15")),
+            (date("2028-02-01"), date("2028-02-29"))
+        );
+        assert_eq!(
+            filing_period(FilingPeriod::Quarterly, date("2026-03-31")),
+            (date("2026-01-01"), date("2026-03-31"))
+        );
+        assert_eq!(
+            filing_period(FilingPeriod::Quarterly, date("2026-11-02")),
+            (date("2026-10-01"), date("2026-12-31"))
+        );
+        assert_eq!(
+            filing_period(FilingPeriod::Annual, date("2026-07-04")),
+            (date("2026-01-01"), date("2026-12-31"))
+        );
+    }
+
+    fn tax_entry(
+        source: TaxSource,
+        date_value: &str,
+        amount: i64,
+        cash_share: Decimal,
+    ) -> TaxEntry {
+        TaxEntry {
+            tax_rate_id: Some(Uuid::nil()),
+            name: Some("VAT".to_string()),
+            rate: Some(Decimal::from(20)),
+            source,
+            date: date(date_value),
+            voided_on: None,
+            taxable_amount: Decimal::from(amount * 5),
+            amount: Decimal::from(amount),
+            exchange_rate: Decimal::ONE,
+            cash_share,
+        }
+    }
+
+    #[test]
+    fn test_accrual_sales_tax_reverses_voided_invoices_when_voided() {
+        let voided = TaxEntry {
+            voided_on: Some(date("2026-04-10")),
+            ..tax_entry(TaxSource::Invoice, "2026-03-20", 200, Decimal::ONE)
+        };
+        let entries = vec![
+            voided,
+            tax_entry(TaxSource::Invoice, "2026-03-05", 100, Decimal::ONE),
+            tax_entry(TaxSource::CreditNote, "2026-04-02", 30, Decimal::ZERO),
+            tax_entry(TaxSource::Expense, "2026-04-15", 10, Decimal::ONE),
+        ];
+        let collected = |from, to| {
+            sales_tax_rows(&entries, Basis::Accrual, date(from), date(to))
+                .first()
+                .map(|row| (row.tax_collected, row.net_tax))
+        };
+
+        // The filed quarter keeps the invoice voided afterwards
+        assert_eq!(
+            collected("2026-01-01", "2026-03-31"),
+            Some((Decimal::from(300), Decimal::from(300)))
+        );
+        // The void and the credit note give tax back when they happen
+        assert_eq!(
+            collected("2026-04-01", "2026-06-30"),
+            Some((Decimal::from(-230), Decimal::from(-240)))
+        );
+        // Issued and voided in the same period cancel out
+        assert_eq!(
+            collected("2026-01-01", "2026-12-31"),
+            Some((Decimal::from(70), Decimal::from(60)))
+        );
+    }
+
+    #[test]
+    fn test_cash_sales_tax_gives_back_tax_on_credit_for_paid_amounts() {
+        let half = Decimal::new(5, 1);
+        let entries = vec![
+            tax_entry(TaxSource::Payment, "2026-03-10", 100, half),
+            tax_entry(TaxSource::Payment, "2026-04-10", 100, half),
+            // Credit note after the invoice was paid in full: all client credit
+            tax_entry(TaxSource::CreditNote, "2026-05-01", 40, Decimal::ONE),
+            // Credit note that only reduced an unpaid balance
+            tax_entry(TaxSource::CreditNote, "2026-05-02", 25, Decimal::ZERO),
+            // Invoices count when paid, not when issued
+            tax_entry(TaxSource::Invoice, "2026-04-01", 100, Decimal::ONE),
+        ];
+        let collected = |from, to| {
+            sales_tax_rows(&entries, Basis::Cash, date(from), date(to))
+                .first()
+                .map(|row| row.tax_collected)
+        };
+
+        assert_eq!(
+            collected("2026-03-01", "2026-03-31"),
+            Some(Decimal::from(50))
+        );
+        assert_eq!(
+            collected("2026-04-01", "2026-04-30"),
+            Some(Decimal::from(50))
+        );
+        assert_eq!(
+            collected("2026-05-01", "2026-05-31"),
+            Some(Decimal::from(-40))
+        );
+        assert_eq!(collected("2026-06-01", "2026-06-30"), None);
+    }
+
+    #[test]
+    fn test_unrealised_gain_on_receivables_and_unapplied_payments() {
+        let exposure = |document_type| FxExposure {
+            document_type,
+            document_id: Uuid::new_v4(),
+            number: None,
+            currency: "EUR".to_string(),
+            balance: Decimal::new(10000, 2),
+            exchange_rate: Decimal::new(110, 2),
+            closing_rate: None,
+            unrealised: None,
+        };
+        let stronger = Decimal::new(115, 2);
+
+        assert_eq!(
+            unrealised_gain(&exposure(FxDocumentType::Invoice), stronger),
+            Decimal::new(500, 2)
+        );
+        assert_eq!(
+            unrealised_gain(&exposure(FxDocumentType::Payment), stronger),
+            Decimal::new(-500, 2)
+        );
+        assert_eq!(
+            unrealised_gain(&exposure(FxDocumentType::Invoice), Decimal::new(110, 2)),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_reads_back_written_lines() {
+        let text = csv_line(["a,b".to_string(), "say \"hi\"".to_string(), "".to_string()])
+            + "\r\n"
+            + &csv_line(["line\nbreak".to_string(), "x".to_string(), "y".to_string()]);
+
+        assert_eq!(
+            parse_csv(&text, ','),
+            vec![
+                vec!["a,b".to_string(), "say \"hi\"".to_string(), String::new()],
+                vec!["line\nbreak".to_string(), "x".to_string(), "y".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_escapes_fields() {
+        let report = Report {
+            title: "Profit and loss".to_string(),
+            basis: Basis::Accrual,
+            columns: vec![ReportColumn {
+                label: "2026-01-01 to 2026-01-31".to_string(),
+                from: Some(date("2026-01-01")),
+                to: date("2026-01-31"),
+            }],
+            sections: vec![ReportSection {
+                name: "Income".to_string(),
+                rows: vec![ReportRow {
+                    account_id: None,
+                    code: Some("4100".to_string()),
+                    name: "Sales, \"consulting\"".to_string(),
+                    amounts: vec![Decimal::new(150050, 2)],
+                }],
+                totals: vec![Decimal::new(150050, 2)],
+            }],
+            summary: vec![],
+        };
+
+        assert_eq!(
+            to_csv(&report),
+            "Section,Code,Account,2026-01-01 to 2026-01-31\r\n\
+             Income,4100,\"Sales, \"\"consulting\"\"\",1500.50\r\n\
+             Income,,Total income,1500.50\r\n"
+        );
+    }
+}
@@ -22,7 +22,87 @@ struct SupabaseAuthResponse {
     user: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+/// The signed-in user and when their access token expires.
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub user: User,
+    pub expires_at: usize,
+}
+
+/// What a one-time token sent by email was issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationType {
+    Signup,
+    Invite,
+    Magiclink,
+    Recovery,
+    EmailChange,
+    /// Code or link sent by a passwordless sign-in request
+    Email,
+}
+
+impl VerificationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationType::Signup => "signup",
+            VerificationType::Invite => "invite",
+            VerificationType::Magiclink => "magiclink",
+            VerificationType::Recovery => "recovery",
+            VerificationType::EmailChange => "email_change",
+            VerificationType::Email => "email",
+        }
+    }
+}
+
+/// Identity providers offered for social login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OAuthProvider {
+    Google,
+    Github,
+    Microsoft,
+}
+
+impl OAuthProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "google",
+            OAuthProvider::Github => "github",
+            OAuthProvider::Microsoft => "microsoft",
+        }
+    }
+
+    /// Provider name GoTrue knows the provider by
+    fn gotrue_name(&self) -> &'static str {
+        match self {
+            OAuthProvider::Microsoft => "azure",
+            _ => self.as_str(),
+        }
+    }
+}
+
+/// Which sessions to end when signing out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignOutScope {
+    /// Every session of the user, on all devices
+    #[default]
+    Global,
+    /// Only the session the access token belongs to
+    Local,
+}
+
+impl SignOutScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignOutScope::Global => "global",
+            SignOutScope::Local => "local",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct SupabaseErrorResponse {
     #[serde(default)]
     error_code: Option<String>,
@@ -39,6 +119,10 @@ pub enum AuthError {
     InvalidCredentials,
     InvalidToken,
     ExpiredToken,
+    SessionExpired,
+    InvalidAuthCode,
+    WeakPassword(String),
+    RateLimited,
     MissingData(String),
     NetworkError(String),
     UnknownError(String),
@@ -63,6 +147,19 @@ impl std::fmt::Display for AuthError {
                     "The confirmation link has expired. Please sign up again to receive a new link."
                 )
             }
+            AuthError::SessionExpired => {
+                write!(f, "Your session has expired. Please sign in again.")
+            }
+            AuthError::InvalidAuthCode => {
+                write!(
+                    f,
+                    "The sign-in attempt is invalid or has expired. Please try again."
+                )
+            }
+            AuthError::WeakPassword(msg) => write!(f, "{}", msg),
+            AuthError::RateLimited => {
+                write!(f, "Too many requests. Please wait a moment and try again.")
+            }
             AuthError::MissingData(msg) => write!(f, "Missing data: {}", msg),
             AuthError::NetworkError(msg) => write!(f, "Network error: {}", msg),
             AuthError::UnknownError(msg) => write!(f, "{}", msg),
@@ -108,6 +205,10 @@ impl SupabaseClient {
 
             tracing::error!("Signup failed for {}: {:?}", email, error_response);
 
+            if status.as_u16() == 429 {
+                return Err(AuthError::RateLimited);
+            }
+
             let error_msg = error_response
                 .msg
                 .or(error_response.error_description)
@@ -195,54 +296,21 @@ impl SupabaseClient {
             .await
             .map_err(|e| AuthError::UnknownError(format!("Failed to parse response: {}", e)))?;
 
-        let access_token = auth_response
-            .access_token
-            .ok_or_else(|| AuthError::MissingData("access_token".to_string()))?;
-
-        let refresh_token = auth_response
-            .refresh_token
-            .ok_or_else(|| AuthError::MissingData("refresh_token".to_string()))?;
-
-        let user_data = auth_response
-            .user
-            .ok_or_else(|| AuthError::MissingData("user".to_string()))?;
-
-        let user_id = user_data["id"]
-            .as_str()
-            .ok_or_else(|| AuthError::MissingData("user id".to_string()))?
-            .to_string();
-
-        let user_email = user_data["email"]
-            .as_str()
-            .ok_or_else(|| AuthError::MissingData("user email".to_string()))?
-            .to_string();
-
-        Ok(AuthResponse {
-            access_token,
-            refresh_token,
-            user: User {
-                id: user_id,
-                email: user_email,
-            },
-        })
+        auth_response.try_into()
     }
 
     pub async fn verify_otp(
         &self,
-        token_hash: &str,
-        verification_type: &str,
+        token: &str,
+        verification_type: VerificationType,
         email: Option<&str>,
     ) -> Result<AuthResponse, AuthError> {
-        tracing::info!("POST /auth/verify-email - verifying OTP");
+        tracing::info!(
+            "POST /auth/verify-email - verifying OTP ({})",
+            verification_type.as_str()
+        );
 
-        let mut body = serde_json::json!({
-            "token_hash": token_hash,
-            "type": verification_type,
-        });
-
-        if let Some(email) = email {
-            body["email"] = serde_json::json!(email);
-        }
+        let body = verify_body(token, verification_type, email);
 
         let response = self
             .client()
@@ -278,6 +346,301 @@ impl SupabaseClient {
             .await
             .map_err(|e| AuthError::UnknownError(format!("Failed to parse response: {}", e)))?;
 
+        auth_response.try_into()
+    }
+
+    pub async fn refresh_session(&self, refresh_token: &str) -> Result<AuthResponse, AuthError> {
+        let response = self
+            .client()
+            .post(self.auth_url("/token?grant_type=refresh_token"))
+            .header("apikey", self.anon_key())
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "refresh_token": refresh_token,
+            }))
+            .send()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_response: SupabaseErrorResponse = response.json().await.unwrap_or_default();
+
+            tracing::warn!("Token refresh failed: {:?}", error_response);
+
+            return Err(session_error(status, "Unable to refresh session"));
+        }
+
+        let auth_response: SupabaseAuthResponse = response
+            .json()
+            .await
+            .map_err(|e| AuthError::UnknownError(format!("Failed to parse response: {}", e)))?;
+
+        auth_response.try_into()
+    }
+
+    /// Ends the session of the client's access token, or all of the user's sessions.
+    pub async fn sign_out(&self, scope: SignOutScope) -> Result<(), AuthError> {
+        let response = self
+            .client()
+            .post(self.auth_url(&format!("/logout?scope={}", scope.as_str())))
+            .header("apikey", self.anon_key())
+            .bearer_auth(self.bearer_token())
+            .send()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        // The session is already gone: nothing left to sign out of
+        if status.is_success() || status.as_u16() == 404 {
+            return Ok(());
+        }
+
+        let error_response: SupabaseErrorResponse = response.json().await.unwrap_or_default();
+
+        tracing::warn!("Sign out failed: {:?}", error_response);
+
+        Err(session_error(status, "Unable to sign out"))
+    }
+
+    /// Fetches the user the client's access token belongs to.
+    pub async fn get_user(&self) -> Result<User, AuthError> {
+        let response = self
+            .client()
+            .get(self.auth_url("/user"))
+            .header("apikey", self.anon_key())
+            .bearer_auth(self.bearer_token())
+            .send()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_response: SupabaseErrorResponse = response.json().await.unwrap_or_default();
+
+            tracing::warn!("Fetching user failed: {:?}", error_response);
+
+            return Err(session_error(status, "Unable to load session"));
+        }
+
+        let user_data: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| AuthError::UnknownError(format!("Failed to parse response: {}", e)))?;
+
+        parse_user(&user_data)
+    }
+
+    /// Sends the password reset email; the link leads back to the app's auth callback.
+    pub async fn recover(&self, email: &str) -> Result<(), AuthError> {
+        let site_url = env::var("SITE_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
+
+        tracing::info!("POST /auth/recover - email: {}", email);
+
+        let response = self
+            .client()
+            .post(self.auth_url("/recover"))
+            .query(&[("redirect_to", format!("{}/auth/callback", site_url))])
+            .header("apikey", self.anon_key())
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "email": email,
+            }))
+            .send()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_response: SupabaseErrorResponse = response.json().await.unwrap_or_default();
+
+            tracing::error!(
+                "Password recovery failed for {}: {:?}",
+                email,
+                error_response
+            );
+
+            if status.as_u16() == 429 {
+                return Err(AuthError::RateLimited);
+            }
+
+            // Do not reveal whether an account exists for the address
+            if error_response.error_code.as_deref() == Some("user_not_found") {
+                return Ok(());
+            }
+
+            return Err(AuthError::UnknownError(
+                "Unable to send the password reset email. Please try again later.".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Sets a new password for the user the client's access token belongs to.
+    pub async fn update_password(&self, password: &str) -> Result<User, AuthError> {
+        let response = self
+            .client()
+            .put(self.auth_url("/user"))
+            .header("apikey", self.anon_key())
+            .bearer_auth(self.bearer_token())
+            .json(&serde_json::json!({
+                "password": password,
+            }))
+            .send()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_response: SupabaseErrorResponse = response.json().await.unwrap_or_default();
+
+            tracing::warn!("Password update failed: {:?}", error_response);
+
+            return Err(match error_response.error_code.as_deref() {
+                Some("weak_password") | Some("same_password") => AuthError::WeakPassword(
+                    error_response
+                        .msg
+                        .or(error_response.message)
+                        .unwrap_or_else(|| "Please choose a stronger password.".to_string()),
+                ),
+                _ if status.as_u16() == 429 => AuthError::RateLimited,
+                _ => session_error(status, "Unable to update password"),
+            });
+        }
+
+        let user_data: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| AuthError::UnknownError(format!("Failed to parse response: {}", e)))?;
+
+        parse_user(&user_data)
+    }
+
+    /// Sends a magic link and one-time code for passwordless sign-in.
+    pub async fn send_otp(&self, email: &str, create_user: bool) -> Result<(), AuthError> {
+        let site_url = env::var("SITE_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
+
+        tracing::info!("POST /auth/otp - email: {}", email);
+
+        let response = self
+            .client()
+            .post(self.auth_url("/otp"))
+            .query(&[("redirect_to", format!("{}/auth/callback", site_url))])
+            .header("apikey", self.anon_key())
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "email": email,
+                "create_user": create_user,
+            }))
+            .send()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_response: SupabaseErrorResponse = response.json().await.unwrap_or_default();
+
+            tracing::error!("Sending OTP failed for {}: {:?}", email, error_response);
+
+            if status.as_u16() == 429 {
+                return Err(AuthError::RateLimited);
+            }
+
+            // Do not reveal whether an account exists for the address
+            if matches!(
+                error_response.error_code.as_deref(),
+                Some("otp_disabled") | Some("user_not_found")
+            ) && !create_user
+            {
+                return Ok(());
+            }
+
+            return Err(AuthError::UnknownError(
+                "Unable to send the sign-in email. Please try again later.".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// URL of GoTrue's `/authorize` endpoint starting a PKCE login with the provider.
+    pub fn authorize_url(
+        &self,
+        provider: OAuthProvider,
+        redirect_to: &str,
+        code_challenge: &str,
+    ) -> Result<String, AuthError> {
+        let mut params = vec![
+            ("provider", provider.gotrue_name()),
+            ("redirect_to", redirect_to),
+            ("code_challenge", code_challenge),
+            ("code_challenge_method", "s256"),
+        ];
+        // Azure only returns the address when asked for it
+        if provider == OAuthProvider::Microsoft {
+            params.push(("scopes", "email"));
+        }
+
+        reqwest::Url::parse_with_params(&self.auth_url("/authorize"), &params)
+            .map(String::from)
+            .map_err(|e| AuthError::UnknownError(format!("Invalid authorize URL: {}", e)))
+    }
+
+    /// Exchanges the code GoTrue redirected back with for a session.
+    pub async fn exchange_code(
+        &self,
+        auth_code: &str,
+        code_verifier: &str,
+    ) -> Result<AuthResponse, AuthError> {
+        let response = self
+            .client()
+            .post(self.auth_url("/token?grant_type=pkce"))
+            .header("apikey", self.anon_key())
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({
+                "auth_code": auth_code,
+                "code_verifier": code_verifier,
+            }))
+            .send()
+            .await
+            .map_err(|e| AuthError::NetworkError(e.to_string()))?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_response: SupabaseErrorResponse = response.json().await.unwrap_or_default();
+
+            tracing::warn!("OAuth code exchange failed: {:?}", error_response);
+
+            return Err(match status.as_u16() {
+                400 | 401 | 403 | 404 => AuthError::InvalidAuthCode,
+                429 => AuthError::RateLimited,
+                _ => AuthError::UnknownError(
+                    "Unable to complete sign in. Please try again later.".to_string(),
+                ),
+            });
+        }
+
+        let auth_response: SupabaseAuthResponse = response
+            .json()
+            .await
+            .map_err(|e| AuthError::UnknownError(format!("Failed to parse response: {}", e)))?;
+
+        auth_response.try_into()
+    }
+}
+
+impl TryFrom<SupabaseAuthResponse> for AuthResponse {
+    type Error = AuthError;
+
+    fn try_from(auth_response: SupabaseAuthResponse) -> Result<Self, Self::Error> {
         let access_token = auth_response
             .access_token
             .ok_or_else(|| AuthError::MissingData("access_token".to_string()))?;
@@ -290,23 +653,340 @@ impl SupabaseClient {
             .user
             .ok_or_else(|| AuthError::MissingData("user".to_string()))?;
 
-        let user_id = user_data["id"]
-            .as_str()
-            .ok_or_else(|| AuthError::MissingData("user id".to_string()))?
-            .to_string();
-
-        let user_email = user_data["email"]
-            .as_str()
-            .ok_or_else(|| AuthError::MissingData("user email".to_string()))?
-            .to_string();
-
         Ok(AuthResponse {
             access_token,
             refresh_token,
-            user: User {
-                id: user_id,
-                email: user_email,
-            },
+            user: parse_user(&user_data)?,
+        })
+    }
+}
+
+/// Builds the `/verify` request: codes typed in by the user are checked
+/// against the email address, links carry a token hash.
+fn verify_body(
+    token: &str,
+    verification_type: VerificationType,
+    email: Option<&str>,
+) -> serde_json::Value {
+    let is_code = token.len() <= 10 && token.chars().all(|c| c.is_ascii_digit());
+
+    match email {
+        Some(email) if is_code => serde_json::json!({
+            "token": token,
+            "type": verification_type,
+            "email": email,
+        }),
+        Some(email) => serde_json::json!({
+            "token_hash": token,
+            "type": verification_type,
+            "email": email,
+        }),
+        None => serde_json::json!({
+            "token_hash": token,
+            "type": verification_type,
+        }),
+    }
+}
+
+fn parse_user(user_data: &serde_json::Value) -> Result<User, AuthError> {
+    let id = user_data["id"]
+        .as_str()
+        .ok_or_else(|| AuthError::MissingData("user id".to_string()))?
+        .to_string();
+
+    let email = user_data["email"]
+        .as_str()
+        .ok_or_else(|| AuthError::MissingData("user email".to_string()))?
+        .to_string();
+
+    Ok(User { id, email })
+}
+
+/// Maps a failed request made with the user's tokens: rejected tokens mean the
+/// session is over and the user has to sign in again.
+fn session_error(status: reqwest::StatusCode, fallback: &str) -> AuthError {
+    match status.as_u16() {
+        400 | 401 | 403 => AuthError::SessionExpired,
+        _ => AuthError::UnknownError(format!("{}. Please try again later.", fallback)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{bearer_token, body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn session_json() -> serde_json::Value {
+        serde_json::json!({
+            "access_token": "access",
+            "refresh_token": "refresh",
+            "token_type": "bearer",
+            "user": { "id": "user-1", "email": "user@example.com" },
         })
     }
+
+    #[tokio::test]
+    async fn test_refresh_session() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/token"))
+            .and(query_param("grant_type", "refresh_token"))
+            .and(body_json(serde_json::json!({ "refresh_token": "valid" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(session_json()))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error_code": "refresh_token_not_found",
+                "msg": "Invalid Refresh Token: Refresh Token Not Found",
+            })))
+            .mount(&server)
+            .await;
+        let client = SupabaseClient::with_url(server.uri(), "anon");
+
+        let auth = client
+            .refresh_session("valid")
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(auth.refresh_token, "refresh");
+        assert_eq!(auth.user.email, "user@example.com");
+
+        assert!(matches!(
+            client.refresh_session("revoked").await,
+            Err(AuthError::SessionExpired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sign_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/logout"))
+            .and(query_param("scope", "local"))
+            .and(bearer_token("user-token"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/logout"))
+            .and(query_param("scope", "global"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let client = SupabaseClient::with_url(server.uri(), "anon").with_access_token("user-token");
+
+        let scope: SignOutScope = serde_json::from_str("\"local\"").unwrap();
+        assert!(client.sign_out(scope).await.is_ok());
+        // A session that is already gone counts as signed out
+        assert!(client.sign_out(SignOutScope::default()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_get_user() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/auth/v1/user"))
+            .and(bearer_token("user-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(session_json()["user"].clone()))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/auth/v1/user"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        let client = SupabaseClient::with_url(server.uri(), "anon");
+
+        let user = client
+            .clone()
+            .with_access_token("user-token")
+            .get_user()
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(user.id, "user-1");
+
+        assert!(matches!(
+            client.with_access_token("expired").get_user().await,
+            Err(AuthError::SessionExpired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recover() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/recover"))
+            .and(body_json(
+                serde_json::json!({ "email": "user@example.com" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/recover"))
+            .and(body_json(
+                serde_json::json!({ "email": "unknown@example.com" }),
+            ))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error_code": "user_not_found",
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/recover"))
+            .respond_with(ResponseTemplate::new(429).set_body_json(serde_json::json!({
+                "error_code": "over_email_send_rate_limit",
+            })))
+            .mount(&server)
+            .await;
+        let client = SupabaseClient::with_url(server.uri(), "anon");
+
+        assert!(client.recover("user@example.com").await.is_ok());
+        // Unknown addresses look the same as known ones
+        assert!(client.recover("unknown@example.com").await.is_ok());
+        assert!(matches!(
+            client.recover("again@example.com").await,
+            Err(AuthError::RateLimited)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_password() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/auth/v1/user"))
+            .and(body_json(serde_json::json!({ "password": "123" })))
+            .respond_with(ResponseTemplate::new(422).set_body_json(serde_json::json!({
+                "error_code": "weak_password",
+                "msg": "Password should be at least 8 characters.",
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/auth/v1/user"))
+            .and(bearer_token("user-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(session_json()["user"].clone()))
+            .mount(&server)
+            .await;
+        let client = SupabaseClient::with_url(server.uri(), "anon").with_access_token("user-token");
+
+        assert!(matches!(
+            client.update_password("123").await,
+            Err(AuthError::WeakPassword(msg)) if msg.contains("8 characters")
+        ));
+        let user = client
+            .update_password("correct horse battery staple")
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(user.email, "user@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_send_otp() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/otp"))
+            .and(body_json(serde_json::json!({
+                "email": "user@example.com",
+                "create_user": false,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/otp"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+        let client = SupabaseClient::with_url(server.uri(), "anon");
+
+        assert!(client.send_otp("user@example.com", false).await.is_ok());
+        assert!(matches!(
+            client.send_otp("user@example.com", true).await,
+            Err(AuthError::RateLimited)
+        ));
+    }
+
+    #[test]
+    fn test_verify_body_for_codes_and_links() {
+        let code = verify_body("123456", VerificationType::Email, Some("user@example.com"));
+        assert_eq!(code["token"], "123456");
+        assert_eq!(code["type"], "email");
+        assert!(code.get("token_hash").is_none());
+
+        let link = verify_body("pkce_8c6a2b", VerificationType::Magiclink, None);
+        assert_eq!(link["token_hash"], "pkce_8c6a2b");
+        assert_eq!(link["type"], "magiclink");
+    }
+
+    #[test]
+    fn test_authorize_url_uses_s256_challenge() {
+        // RFC 7636, appendix B
+        let challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+        let client = SupabaseClient::with_url("https://project.supabase.co", "anon");
+        let url = client
+            .authorize_url(
+                OAuthProvider::Microsoft,
+                "http://localhost:5173/auth/callback",
+                challenge,
+            )
+            .unwrap_or_else(|e| panic!("{}", e));
+        let url = reqwest::Url::parse(&url).unwrap();
+        let params: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+
+        assert_eq!(url.path(), "/auth/v1/authorize");
+        assert_eq!(params["provider"], "azure");
+        assert_eq!(params["redirect_to"], "http://localhost:5173/auth/callback");
+        assert_eq!(params["code_challenge"], challenge);
+        assert_eq!(params["code_challenge_method"], "s256");
+    }
+
+    #[tokio::test]
+    async fn test_exchange_code_against_mock_gotrue() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/token"))
+            .and(query_param("grant_type", "pkce"))
+            .and(header("apikey", "anon"))
+            .and(body_json(serde_json::json!({
+                "auth_code": "good-code",
+                "code_verifier": "verifier",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "access",
+                "refresh_token": "refresh",
+                "token_type": "bearer",
+                "user": { "id": "user-1", "email": "user@example.com" },
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v1/token"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "code": 404,
+                "error_code": "flow_state_not_found",
+                "msg": "invalid flow state, no valid flow state found",
+            })))
+            .mount(&server)
+            .await;
+
+        let client = SupabaseClient::with_url(server.uri(), "anon");
+
+        let auth = client
+            .exchange_code("good-code", "verifier")
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(auth.access_token, "access");
+        assert_eq!(auth.user.id, "user-1");
+
+        assert!(matches!(
+            client.exchange_code("stale-code", "verifier").await,
+            Err(AuthError::InvalidAuthCode)
+        ));
+    }
 }
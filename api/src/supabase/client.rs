@@ -6,6 +6,7 @@ pub struct SupabaseClient {
     client: Client,
     pub(crate) url: String,
     anon_key: String,
+    access_token: Option<String>,
 }
 
 impl SupabaseClient {
@@ -15,11 +16,17 @@ impl SupabaseClient {
         let anon_key = env::var("SUPABASE_ANON_KEY")
             .map_err(|_| "Missing SUPABASE_ANON_KEY environment variable")?;
 
-        Ok(Self {
+        Ok(Self::with_url(url, anon_key))
+    }
+
+    /// Client for an explicit project URL, e.g. a local mock server
+    pub fn with_url(url: impl Into<String>, anon_key: impl Into<String>) -> Self {
+        Self {
             client: Client::new(),
-            url,
-            anon_key,
-        })
+            url: url.into(),
+            anon_key: anon_key.into(),
+            access_token: None,
+        }
     }
 
     pub fn auth_url(&self, path: &str) -> String {
@@ -33,4 +40,15 @@ impl SupabaseClient {
     pub fn anon_key(&self) -> &str {
         &self.anon_key
     }
+
+    /// Act on behalf of a signed-in user so requests are subject to their RLS policies
+    pub fn with_access_token(mut self, access_token: &str) -> Self {
+        self.access_token = Some(access_token.to_string());
+        self
+    }
+
+    /// Bearer token for authenticated requests: the user's token if set, otherwise the anon key
+    pub fn bearer_token(&self) -> &str {
+        self.access_token.as_deref().unwrap_or(&self.anon_key)
+    }
 }
@@ -96,7 +96,8 @@ impl SupabaseClient {
         };
 
         let response = request
-            .header("Authorization", format!("Bearer {}", self.anon_key()))
+            .header("apikey", self.anon_key())
+            .header("Authorization", format!("Bearer {}", self.bearer_token()))
             .multipart(form)
             .send()
             .await
@@ -126,7 +127,8 @@ impl SupabaseClient {
         let response = self
             .client()
             .get(&url)
-            .header("Authorization", format!("Bearer {}", self.anon_key()))
+            .header("apikey", self.anon_key())
+            .header("Authorization", format!("Bearer {}", self.bearer_token()))
             .send()
             .await
             .map_err(|e| StorageError::NetworkError(e.to_string()))?;
@@ -167,7 +169,8 @@ impl SupabaseClient {
         let response = self
             .client()
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.anon_key()))
+            .header("apikey", self.anon_key())
+            .header("Authorization", format!("Bearer {}", self.bearer_token()))
             .header("Content-Type", "application/json")
             .json(&serde_json::json!({
                 "expiresIn": expires_in
@@ -195,7 +198,8 @@ impl SupabaseClient {
             .await
             .map_err(|e| StorageError::NetworkError(format!("Failed to parse response: {}", e)))?;
 
-        Ok(signed_response.signed_url)
+        // Storage returns a path relative to /storage/v1
+        Ok(self.storage_url(&signed_response.signed_url))
     }
 
     /// Delete one or more files
@@ -209,7 +213,8 @@ impl SupabaseClient {
         let response = self
             .client()
             .delete(&url)
-            .header("Authorization", format!("Bearer {}", self.anon_key()))
+            .header("apikey", self.anon_key())
+            .header("Authorization", format!("Bearer {}", self.bearer_token()))
             .header("Content-Type", "application/json")
             .json(&serde_json::json!({
                 "prefixes": paths
@@ -256,7 +261,8 @@ impl SupabaseClient {
         let response = self
             .client()
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.anon_key()))
+            .header("apikey", self.anon_key())
+            .header("Authorization", format!("Bearer {}", self.bearer_token()))
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
@@ -291,7 +297,8 @@ impl SupabaseClient {
         let response = self
             .client()
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.anon_key()))
+            .header("apikey", self.anon_key())
+            .header("Authorization", format!("Bearer {}", self.bearer_token()))
             .header("Content-Type", "application/json")
             .json(&serde_json::json!({
                 "bucketId": bucket,
@@ -335,7 +342,8 @@ impl SupabaseClient {
         let response = self
             .client()
             .post(&url)
-            .header("Authorization", format!("Bearer {}", self.anon_key()))
+            .header("apikey", self.anon_key())
+            .header("Authorization", format!("Bearer {}", self.bearer_token()))
             .header("Content-Type", "application/json")
             .json(&serde_json::json!({
                 "bucketId": bucket,
@@ -0,0 +1,640 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::clients::{self, Client};
+use crate::invoices::round_money;
+
+/// Whether tax amounts are rounded on every line or once per document and rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "VARCHAR", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TaxRounding {
+    #[default]
+    PerLine,
+    PerInvoice,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TaxRate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub rate: Decimal,
+    pub is_compound: bool,
+    pub is_archived: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TaxRule {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub tax_rate_id: Uuid,
+    pub country: String,
+    pub province: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A tax as applied to a document line, snapshotted from its tax rate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AppliedTax {
+    pub tax_rate_id: Uuid,
+    pub name: String,
+    pub rate: Decimal,
+    pub is_compound: bool,
+}
+
+impl From<&TaxRate> for AppliedTax {
+    fn from(tax_rate: &TaxRate) -> Self {
+        Self {
+            tax_rate_id: tax_rate.id,
+            name: tax_rate.name.clone(),
+            rate: tax_rate.rate,
+            is_compound: tax_rate.is_compound,
+        }
+    }
+}
+
+/// Tax totals for one rate across a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaxSummary {
+    pub tax: AppliedTax,
+    pub taxable_amount: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTaxRateRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_rate"))]
+    pub rate: Decimal,
+    #[serde(default)]
+    pub is_compound: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateTaxRateRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_rate"))]
+    pub rate: Option<Decimal>,
+    pub is_compound: Option<bool>,
+    pub is_archived: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTaxRuleRequest {
+    pub tax_rate_id: Uuid,
+    #[validate(length(min = 1, max = 100))]
+    pub country: String,
+    #[validate(length(min = 1, max = 100))]
+    pub province: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DefaultTaxesQuery {
+    pub client_id: Uuid,
+}
+
+fn validate_rate(rate: &Decimal) -> Result<(), ValidationError> {
+    if *rate < Decimal::ZERO || *rate > Decimal::ONE_HUNDRED {
+        return Err(ValidationError::new("rate_out_of_range"));
+    }
+
+    Ok(())
+}
+
+/// Computes `(taxable base, tax amount)` for each tax on a line.
+///
+/// Non-compound taxes apply to the line amount; compound taxes apply to the line
+/// amount plus all of the line's non-compound taxes. With per-line rounding each
+/// amount is rounded to cents here, otherwise amounts are left exact for
+/// [`summarize_taxes`] to round once per rate.
+pub fn line_tax_amounts(
+    amount: Decimal,
+    taxes: &[AppliedTax],
+    rounding: TaxRounding,
+) -> Vec<(Decimal, Decimal)> {
+    let round = |value: Decimal| match rounding {
+        TaxRounding::PerLine => round_money(value),
+        TaxRounding::PerInvoice => value,
+    };
+
+    let non_compound_total: Decimal = taxes
+        .iter()
+        .filter(|tax| !tax.is_compound)
+        .map(|tax| round(amount * tax.rate / Decimal::ONE_HUNDRED))
+        .sum();
+
+    taxes
+        .iter()
+        .map(|tax| {
+            let base = if tax.is_compound {
+                amount + non_compound_total
+            } else {
+                amount
+            };
+            (base, round(base * tax.rate / Decimal::ONE_HUNDRED))
+        })
+        .collect()
+}
+
+/// Totals taxes per rate across all lines of a document, in first-seen order.
+pub fn summarize_taxes(
+    lines: &[(Decimal, Vec<AppliedTax>)],
+    rounding: TaxRounding,
+) -> Vec<TaxSummary> {
+    let mut summaries: Vec<TaxSummary> = Vec::new();
+
+    for (amount, taxes) in lines {
+        for (tax, (base, tax_amount)) in
+            taxes.iter().zip(line_tax_amounts(*amount, taxes, rounding))
+        {
+            match summaries.iter_mut().find(|summary| &summary.tax == tax) {
+                Some(summary) => {
+                    summary.taxable_amount += base;
+                    summary.amount += tax_amount;
+                }
+                None => summaries.push(TaxSummary {
+                    tax: tax.clone(),
+                    taxable_amount: base,
+                    amount: tax_amount,
+                }),
+            }
+        }
+    }
+
+    for summary in &mut summaries {
+        summary.taxable_amount = round_money(summary.taxable_amount);
+        summary.amount = round_money(summary.amount);
+    }
+
+    summaries
+}
+
+/// Tax rates that apply by default to a client in `country`/`province`.
+///
+/// Rules for the exact province win; otherwise the country-wide rules apply.
+pub(crate) async fn default_tax_rates(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    country: Option<&str>,
+    province: Option<&str>,
+) -> Result<Vec<TaxRate>, (StatusCode, Json<Value>)> {
+    let Some(country) = country.map(str::trim).filter(|country| !country.is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let province = province.map(str::trim).unwrap_or_default();
+
+    sqlx::query_as::<_, TaxRate>(
+        r#"
+        SELECT DISTINCT tax_rates.*
+        FROM tax_rates
+        JOIN tax_rules ON tax_rules.tax_rate_id = tax_rates.id
+        WHERE tax_rules.user_id = $1
+            AND NOT tax_rates.is_archived
+            AND LOWER(tax_rules.country) = LOWER($2)
+            AND (
+                LOWER(tax_rules.province) = LOWER($3)
+                OR (
+                    tax_rules.province IS NULL
+                    AND NOT EXISTS (
+                        SELECT 1 FROM tax_rules provincial
+                        WHERE provincial.user_id = $1
+                            AND LOWER(provincial.country) = LOWER($2)
+                            AND LOWER(provincial.province) = LOWER($3)
+                    )
+                )
+            )
+        ORDER BY tax_rates.is_compound, tax_rates.name
+        "#,
+    )
+    .bind(user_id)
+    .bind(country)
+    .bind(province)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch default tax rates: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch default tax rates" })),
+        )
+    })
+}
+
+/// Resolves the taxes for each line: explicit tax rate IDs when given, otherwise
+/// the client's regional defaults.
+pub(crate) async fn resolve_line_taxes(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    client: &Client,
+    requested: &[Option<Vec<Uuid>>],
+) -> Result<Vec<Vec<AppliedTax>>, (StatusCode, Json<Value>)> {
+    let defaults: Vec<AppliedTax> = if requested.iter().any(Option::is_none) {
+        default_tax_rates(
+            conn,
+            user_id,
+            client.country.as_deref(),
+            client.province.as_deref(),
+        )
+        .await?
+        .iter()
+        .map(AppliedTax::from)
+        .collect()
+    } else {
+        Vec::new()
+    };
+
+    let explicit_ids: Vec<Uuid> = requested.iter().flatten().flatten().copied().collect();
+    let explicit = sqlx::query_as::<_, TaxRate>(
+        "SELECT * FROM tax_rates WHERE user_id = $1 AND id = ANY($2) AND NOT is_archived",
+    )
+    .bind(user_id)
+    .bind(&explicit_ids)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch tax rates: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch tax rates" })),
+        )
+    })?;
+
+    requested
+        .iter()
+        .map(|ids| match ids {
+            None => Ok(defaults.clone()),
+            Some(ids) => {
+                let mut taxes: Vec<AppliedTax> = Vec::with_capacity(ids.len());
+                for id in ids {
+                    let tax_rate =
+                        explicit.iter().find(|rate| rate.id == *id).ok_or_else(|| {
+                            (
+                                StatusCode::BAD_REQUEST,
+                                Json(json!({ "error": format!("Tax rate {} not found", id) })),
+                            )
+                        })?;
+                    if !taxes.iter().any(|tax| tax.tax_rate_id == *id) {
+                        taxes.push(AppliedTax::from(tax_rate));
+                    }
+                }
+                Ok(taxes)
+            }
+        })
+        .collect()
+}
+
+pub async fn list_tax_rates(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<TaxRate>>, (StatusCode, Json<Value>)> {
+    let tax_rates =
+        sqlx::query_as::<_, TaxRate>("SELECT * FROM tax_rates WHERE user_id = $1 ORDER BY name")
+            .bind(user_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch tax rates: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch tax rates" })),
+                )
+            })?;
+
+    Ok(Json(tax_rates))
+}
+
+pub async fn create_tax_rate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateTaxRateRequest>,
+) -> Result<(StatusCode, Json<TaxRate>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let tax_rate = sqlx::query_as::<_, TaxRate>(
+        r#"
+        INSERT INTO tax_rates (user_id, name, description, rate, is_compound)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.name)
+    .bind(req.description)
+    .bind(req.rate)
+    .bind(req.is_compound)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create tax rate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create tax rate" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(tax_rate)))
+}
+
+pub async fn update_tax_rate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateTaxRateRequest>,
+) -> Result<Json<TaxRate>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let tax_rate = sqlx::query_as::<_, TaxRate>(
+        r#"
+        UPDATE tax_rates
+        SET
+            name = COALESCE($1, name),
+            description = COALESCE($2, description),
+            rate = COALESCE($3, rate),
+            is_compound = COALESCE($4, is_compound),
+            is_archived = COALESCE($5, is_archived),
+            updated_at = NOW()
+        WHERE id = $6 AND user_id = $7
+        RETURNING *
+        "#,
+    )
+    .bind(req.name)
+    .bind(req.description)
+    .bind(req.rate)
+    .bind(req.is_compound)
+    .bind(req.is_archived)
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update tax rate: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update tax rate" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Tax rate not found" })),
+        )
+    })?;
+
+    Ok(Json(tax_rate))
+}
+
+pub async fn delete_tax_rate(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM tax_rates WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            // Rates used on documents are kept so those documents stay intact
+            if e.as_database_error()
+                .is_some_and(|db| db.is_foreign_key_violation())
+            {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({ "error": "Tax rate is in use; archive it instead" })),
+                );
+            }
+
+            tracing::error!("Failed to delete tax rate: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete tax rate" })),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Tax rate not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_tax_rules(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<TaxRule>>, (StatusCode, Json<Value>)> {
+    let rules = sqlx::query_as::<_, TaxRule>(
+        "SELECT * FROM tax_rules WHERE user_id = $1 ORDER BY country, province NULLS FIRST",
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch tax rules: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch tax rules" })),
+        )
+    })?;
+
+    Ok(Json(rules))
+}
+
+pub async fn create_tax_rule(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateTaxRuleRequest>,
+) -> Result<(StatusCode, Json<TaxRule>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let rule = sqlx::query_as::<_, TaxRule>(
+        r#"
+        INSERT INTO tax_rules (user_id, tax_rate_id, country, province)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(req.tax_rate_id)
+    .bind(req.country.trim())
+    .bind(req.province.as_deref().map(str::trim))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        if let Some(db) = e.as_database_error() {
+            if db.is_foreign_key_violation() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "Tax rate not found" })),
+                );
+            }
+            if db.is_unique_violation() {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({ "error": "This tax rate already applies to that region" })),
+                );
+            }
+        }
+
+        tracing::error!("Failed to create tax rule: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create tax rule" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn delete_tax_rule(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM tax_rules WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete tax rule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to delete tax rule" })),
+            )
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Tax rule not found" })),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_default_taxes(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<DefaultTaxesQuery>,
+) -> Result<Json<Vec<TaxRate>>, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch default tax rates" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut conn, user_id, query.client_id).await?;
+    let tax_rates = default_tax_rates(
+        &mut conn,
+        user_id,
+        client.country.as_deref(),
+        client.province.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(tax_rates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn tax(name: &str, rate: &str, is_compound: bool) -> AppliedTax {
+        AppliedTax {
+            tax_rate_id: Uuid::new_v4(),
+            name: name.to_string(),
+            rate: decimal(rate),
+            is_compound,
+        }
+    }
+
+    #[test]
+    fn test_compound_tax_applies_on_top_of_other_taxes() {
+        let gst = tax("GST", "5", false);
+        let qst = tax("QST", "9.975", true);
+
+        let amounts = line_tax_amounts(decimal("100.00"), &[qst, gst], TaxRounding::PerLine);
+
+        assert_eq!(amounts[0], (decimal("105.00"), decimal("10.47")));
+        assert_eq!(amounts[1], (decimal("100.00"), decimal("5.00")));
+    }
+
+    #[test]
+    fn test_per_line_and_per_invoice_rounding_differ() {
+        let gst = tax("GST", "5", false);
+        let lines: Vec<_> = (0..3)
+            .map(|_| (decimal("0.10"), vec![gst.clone()]))
+            .collect();
+
+        let per_line = summarize_taxes(&lines, TaxRounding::PerLine);
+        let per_invoice = summarize_taxes(&lines, TaxRounding::PerInvoice);
+
+        assert_eq!(per_line[0].amount, decimal("0.03"));
+        assert_eq!(per_invoice[0].amount, decimal("0.02"));
+        assert_eq!(per_invoice[0].taxable_amount, decimal("0.30"));
+    }
+
+    #[test]
+    fn test_summarize_taxes_groups_by_rate() {
+        let hst = tax("HST", "13", false);
+        let gst = tax("GST", "5", false);
+        let lines = vec![
+            (decimal("200.00"), vec![hst.clone()]),
+            (decimal("50.00"), vec![gst.clone()]),
+            (decimal("19.99"), vec![hst.clone()]),
+            (decimal("10.00"), vec![]),
+        ];
+
+        let summaries = summarize_taxes(&lines, TaxRounding::PerLine);
+
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].tax, hst);
+        assert_eq!(summaries[0].taxable_amount, decimal("219.99"));
+        assert_eq!(summaries[0].amount, decimal("28.60"));
+        assert_eq!(summaries[1].tax, gst);
+        assert_eq!(summaries[1].amount, decimal("2.50"));
+    }
+}
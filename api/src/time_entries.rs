@@ -0,0 +1,970 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::clients::{self, Client};
+use crate::invoices::{self, InvoiceWithLineItems, NewLineItem, validate_non_negative};
+use crate::projects::{self, BillingMethod, Project};
+use crate::taxes;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TimeEntry {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub client_id: Option<Uuid>,
+    pub task: Option<String>,
+    pub description: Option<String>,
+    pub entry_date: NaiveDate,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// `None` while the timer is running.
+    pub duration_minutes: Option<i32>,
+    pub hourly_rate: Option<Decimal>,
+    pub billable: bool,
+    pub invoice_line_item_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TimeEntry {
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some() && self.ended_at.is_none()
+    }
+}
+
+/// How selected entries are combined into invoice lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    #[default]
+    Day,
+    Task,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTimeEntryRequest {
+    pub client_id: Option<Uuid>,
+    /// Sets the client when `client_id` is omitted.
+    pub project_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 255))]
+    pub task: Option<String>,
+    pub description: Option<String>,
+    pub entry_date: Option<NaiveDate>,
+    #[validate(range(min = 1, max = 1440))]
+    pub duration_minutes: i32,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub hourly_rate: Option<Decimal>,
+    /// Defaults to billable when a client is given.
+    pub billable: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateTimeEntryRequest {
+    pub client_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 255))]
+    pub task: Option<String>,
+    pub description: Option<String>,
+    pub entry_date: Option<NaiveDate>,
+    #[validate(range(min = 1, max = 1440))]
+    pub duration_minutes: Option<i32>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub hourly_rate: Option<Decimal>,
+    pub billable: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct StartTimerRequest {
+    pub client_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 255))]
+    pub task: Option<String>,
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_non_negative"))]
+    pub hourly_rate: Option<Decimal>,
+    pub billable: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnbilledTimeQuery {
+    pub client_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct InvoiceTimeEntriesRequest {
+    pub client_id: Uuid,
+    #[validate(length(min = 1, message = "At least one time entry is required"))]
+    pub entry_ids: Vec<Uuid>,
+    /// Draft invoice to add the time to; a new draft is created when omitted.
+    pub invoice_id: Option<Uuid>,
+    #[serde(default)]
+    pub group_by: GroupBy,
+}
+
+/// A stopped entry ready to bill, with its hourly rate resolved.
+#[derive(Debug, Clone)]
+pub struct BillableTime {
+    pub entry_id: Uuid,
+    pub entry_date: NaiveDate,
+    pub task: Option<String>,
+    pub minutes: i32,
+    pub rate: Decimal,
+}
+
+/// One invoice line made of one or more time entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeLine {
+    pub description: String,
+    pub hours: Decimal,
+    pub rate: Decimal,
+    pub entry_ids: Vec<Uuid>,
+}
+
+/// Billed length of a timer run: whole minutes, rounded up, at least one.
+pub fn timer_minutes(started_at: DateTime<Utc>, ended_at: DateTime<Utc>) -> i32 {
+    let seconds = (ended_at - started_at).num_seconds().max(0);
+    let minutes = (seconds + 59) / 60;
+    minutes.clamp(1, i32::MAX as i64) as i32
+}
+
+/// Hourly rate for an entry: its own rate, otherwise the client's default.
+pub fn resolve_hourly_rate(
+    entry_rate: Option<Decimal>,
+    client_rate: Option<Decimal>,
+) -> Option<Decimal> {
+    entry_rate.or(client_rate)
+}
+
+fn hours(minutes: i64) -> Decimal {
+    (Decimal::from(minutes) / Decimal::from(60))
+        .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Groups entries into invoice lines by day or by task, in date order.
+///
+/// Entries only share a line when they also share a rate, since a line has a
+/// single unit price.
+pub fn group_time(entries: &[BillableTime], group_by: GroupBy) -> Vec<TimeLine> {
+    let mut sorted: Vec<&BillableTime> = entries.iter().collect();
+    sorted.sort_by_key(|entry| entry.entry_date);
+
+    let key = |entry: &BillableTime| match group_by {
+        GroupBy::Day => entry.entry_date.to_string(),
+        GroupBy::Task => entry.task.clone().unwrap_or_default(),
+    };
+
+    let mut groups: Vec<(String, Decimal, Vec<&BillableTime>)> = Vec::new();
+    for entry in sorted {
+        let entry_key = key(entry);
+        match groups
+            .iter_mut()
+            .find(|(group_key, rate, _)| *group_key == entry_key && *rate == entry.rate)
+        {
+            Some((_, _, members)) => members.push(entry),
+            None => groups.push((entry_key, entry.rate, vec![entry])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(_, rate, members)| {
+            let minutes: i64 = members.iter().map(|entry| i64::from(entry.minutes)).sum();
+            let first = members[0].entry_date;
+            let last = members[members.len() - 1].entry_date;
+
+            let description = match group_by {
+                GroupBy::Day => {
+                    let mut tasks: Vec<&str> = Vec::new();
+                    for task in members.iter().filter_map(|entry| entry.task.as_deref()) {
+                        if !tasks.contains(&task) {
+                            tasks.push(task);
+                        }
+                    }
+                    if tasks.is_empty() {
+                        format!("Time on {}", first)
+                    } else {
+                        format!("{}: {}", first, tasks.join(", "))
+                    }
+                }
+                GroupBy::Task => {
+                    let task = members[0].task.as_deref().unwrap_or("General");
+                    if first == last {
+                        format!("{} ({})", task, first)
+                    } else {
+                        format!("{} ({} to {})", task, first, last)
+                    }
+                }
+            };
+
+            TimeLine {
+                description,
+                hours: hours(minutes),
+                rate,
+                entry_ids: members.iter().map(|entry| entry.entry_id).collect(),
+            }
+        })
+        .collect()
+}
+
+async fn lock_time_entry(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<TimeEntry, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, TimeEntry>(
+        "SELECT * FROM time_entries WHERE id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch time entry: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch time entry" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Time entry not found" })),
+        )
+    })
+}
+
+fn billable_or_default(
+    billable: Option<bool>,
+    client_id: Option<Uuid>,
+) -> Result<bool, (StatusCode, Json<Value>)> {
+    let billable = billable.unwrap_or(client_id.is_some());
+    if billable && client_id.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Billable time needs a client" })),
+        ));
+    }
+    Ok(billable)
+}
+
+pub async fn list_time_entries(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Vec<TimeEntry>>, (StatusCode, Json<Value>)> {
+    let entries = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT * FROM time_entries
+        WHERE user_id = $1
+        ORDER BY entry_date DESC, started_at DESC NULLS LAST, created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch time entries: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch time entries" })),
+        )
+    })?;
+
+    Ok(Json(entries))
+}
+
+pub async fn create_time_entry(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<CreateTimeEntryRequest>,
+) -> Result<(StatusCode, Json<TimeEntry>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create time entry" })),
+        )
+    })?;
+
+    let client_id =
+        projects::project_client(&mut conn, user_id, req.project_id, req.client_id).await?;
+    let billable = billable_or_default(req.billable, client_id)?;
+
+    if let Some(client_id) = client_id {
+        clients::fetch_owned_client(&mut conn, user_id, client_id).await?;
+    }
+
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        INSERT INTO time_entries (
+            user_id, client_id, task, description, entry_date, duration_minutes,
+            hourly_rate, billable, project_id
+        )
+        VALUES ($1, $2, $3, $4, COALESCE($5, CURRENT_DATE), $6, $7, $8, $9)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(req.task)
+    .bind(req.description)
+    .bind(req.entry_date)
+    .bind(req.duration_minutes)
+    .bind(req.hourly_rate)
+    .bind(billable)
+    .bind(req.project_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create time entry: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to create time entry" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+pub async fn get_time_entry(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TimeEntry>, (StatusCode, Json<Value>)> {
+    let entry =
+        sqlx::query_as::<_, TimeEntry>("SELECT * FROM time_entries WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch time entry: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to fetch time entry" })),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({ "error": "Time entry not found" })),
+                )
+            })?;
+
+    Ok(Json(entry))
+}
+
+pub async fn update_time_entry(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateTimeEntryRequest>,
+) -> Result<Json<TimeEntry>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update time entry" })),
+        )
+    })?;
+
+    let existing = lock_time_entry(&mut tx, user_id, id).await?;
+
+    if existing.invoice_line_item_id.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Time entry has been billed; remove it from the invoice before editing it"
+            })),
+        ));
+    }
+    if existing.is_running() && req.duration_minutes.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Stop the timer before setting its duration" })),
+        ));
+    }
+
+    let project_id = req.project_id.or(existing.project_id);
+    let client_id = projects::project_client(
+        &mut tx,
+        user_id,
+        project_id,
+        req.client_id.or(existing.client_id),
+    )
+    .await?;
+    let billable = billable_or_default(Some(req.billable.unwrap_or(existing.billable)), client_id)?;
+
+    if let Some(client_id) = req.client_id {
+        clients::fetch_owned_client(&mut tx, user_id, client_id).await?;
+    }
+
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        UPDATE time_entries
+        SET
+            client_id = $1,
+            task = COALESCE($2, task),
+            description = COALESCE($3, description),
+            entry_date = COALESCE($4, entry_date),
+            duration_minutes = COALESCE($5, duration_minutes),
+            hourly_rate = COALESCE($6, hourly_rate),
+            billable = $7,
+            project_id = $8,
+            updated_at = NOW()
+        WHERE id = $9 AND user_id = $10
+        RETURNING *
+        "#,
+    )
+    .bind(client_id)
+    .bind(req.task)
+    .bind(req.description)
+    .bind(req.entry_date)
+    .bind(req.duration_minutes)
+    .bind(req.hourly_rate)
+    .bind(billable)
+    .bind(project_id)
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update time entry: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update time entry" })),
+        )
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to update time entry" })),
+        )
+    })?;
+
+    Ok(Json(entry))
+}
+
+pub async fn delete_time_entry(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete time entry" })),
+        )
+    })?;
+
+    let existing = lock_time_entry(&mut conn, user_id, id).await?;
+    if existing.invoice_line_item_id.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Billed time entries cannot be deleted" })),
+        ));
+    }
+
+    sqlx::query(
+        "DELETE FROM time_entries WHERE id = $1 AND user_id = $2 AND invoice_line_item_id IS NULL",
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to delete time entry: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to delete time entry" })),
+        )
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_running_timer(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<Option<TimeEntry>>, (StatusCode, Json<Value>)> {
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT * FROM time_entries
+        WHERE user_id = $1 AND started_at IS NOT NULL AND ended_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch running timer: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch timer" })),
+        )
+    })?;
+
+    Ok(Json(entry))
+}
+
+pub async fn start_timer(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<StartTimerRequest>,
+) -> Result<(StatusCode, Json<TimeEntry>), (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to start timer" })),
+        )
+    })?;
+
+    let client_id =
+        projects::project_client(&mut conn, user_id, req.project_id, req.client_id).await?;
+    let billable = billable_or_default(req.billable, client_id)?;
+
+    if let Some(client_id) = client_id {
+        clients::fetch_owned_client(&mut conn, user_id, client_id).await?;
+    }
+
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        INSERT INTO time_entries (
+            user_id, client_id, task, description, entry_date, started_at, hourly_rate,
+            billable, project_id
+        )
+        VALUES ($1, $2, $3, $4, CURRENT_DATE, NOW(), $5, $6, $7)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(req.task)
+    .bind(req.description)
+    .bind(req.hourly_rate)
+    .bind(billable)
+    .bind(req.project_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        // The running-timer index allows a single timer per user
+        if e.as_database_error()
+            .is_some_and(|db| db.is_unique_violation())
+        {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "A timer is already running; stop it first" })),
+            );
+        }
+
+        tracing::error!("Failed to start timer: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to start timer" })),
+        )
+    })?;
+
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+pub async fn stop_timer(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+) -> Result<Json<TimeEntry>, (StatusCode, Json<Value>)> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to stop timer" })),
+        )
+    })?;
+
+    let running = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT * FROM time_entries
+        WHERE user_id = $1 AND started_at IS NOT NULL AND ended_at IS NULL
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch running timer: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to stop timer" })),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "No timer is running" })),
+        )
+    })?;
+
+    let ended_at = Utc::now();
+    let started_at = running.started_at.unwrap_or(ended_at);
+
+    let entry = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        UPDATE time_entries
+        SET ended_at = $1, duration_minutes = $2, updated_at = NOW()
+        WHERE id = $3
+        RETURNING *
+        "#,
+    )
+    .bind(ended_at)
+    .bind(timer_minutes(started_at, ended_at))
+    .bind(running.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to stop timer: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to stop timer" })),
+        )
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to stop timer" })),
+        )
+    })?;
+
+    Ok(Json(entry))
+}
+
+pub async fn list_unbilled_time_entries(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Query(query): Query<UnbilledTimeQuery>,
+) -> Result<Json<Vec<TimeEntry>>, (StatusCode, Json<Value>)> {
+    let entries = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT * FROM time_entries
+        WHERE user_id = $1
+            AND billable
+            AND invoice_line_item_id IS NULL
+            AND duration_minutes IS NOT NULL
+            AND ($2::UUID IS NULL OR client_id = $2)
+        ORDER BY entry_date, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.client_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch unbilled time entries: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to fetch time entries" })),
+        )
+    })?;
+
+    Ok(Json(entries))
+}
+
+/// Checks that the selected entries can be billed to `client` and resolves
+/// their hourly rates, from the entry's project when it has one.
+fn billable_time(
+    entries: &[TimeEntry],
+    requested: &[Uuid],
+    client: &Client,
+    projects: &[Project],
+) -> Result<Vec<BillableTime>, (StatusCode, Json<Value>)> {
+    let mut selected: Vec<BillableTime> = Vec::with_capacity(requested.len());
+
+    for id in requested {
+        if selected.iter().any(|time| time.entry_id == *id) {
+            continue;
+        }
+        let entry = entries
+            .iter()
+            .find(|entry| entry.id == *id)
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("Time entry {} not found", id) })),
+                )
+            })?;
+
+        if !entry.billable || entry.client_id != Some(client.id) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("Time entry {} is not billable to this client", id)
+                })),
+            ));
+        }
+        if entry.invoice_line_item_id.is_some() {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({ "error": format!("Time entry {} has already been billed", id) })),
+            ));
+        }
+        let minutes = entry.duration_minutes.ok_or_else(|| {
+            (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": format!("Time entry {} is still running", id) })),
+            )
+        })?;
+        let project = projects
+            .iter()
+            .find(|project| Some(project.id) == entry.project_id);
+        if project.is_some_and(|project| project.billing_method == BillingMethod::FixedFee) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!(
+                        "Time entry {} belongs to a fixed-fee project and is not billed by the hour",
+                        id
+                    )
+                })),
+            ));
+        }
+        let rate = match project {
+            Some(project) => project.time_rate(entry.hourly_rate, client.default_hourly_rate),
+            None => resolve_hourly_rate(entry.hourly_rate, client.default_hourly_rate),
+        }
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!(
+                        "Time entry {} has no hourly rate and the client has no default rate",
+                        id
+                    )
+                })),
+            )
+        })?;
+
+        selected.push(BillableTime {
+            entry_id: entry.id,
+            entry_date: entry.entry_date,
+            task: entry.task.clone(),
+            minutes,
+            rate,
+        });
+    }
+
+    Ok(selected)
+}
+
+/// Turns unbilled time into lines on a new or existing draft invoice.
+pub async fn invoice_time_entries(
+    State(pool): State<PgPool>,
+    user_id: Uuid,
+    Json(req): Json<InvoiceTimeEntriesRequest>,
+) -> Result<Json<InvoiceWithLineItems>, (StatusCode, Json<Value>)> {
+    req.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Validation error: {}", e) })),
+        )
+    })?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to start transaction: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to bill time" })),
+        )
+    })?;
+
+    let client = clients::fetch_owned_client(&mut tx, user_id, req.client_id).await?;
+
+    // Locked so concurrent requests cannot bill the same entry twice
+    let entries = sqlx::query_as::<_, TimeEntry>(
+        r#"
+        SELECT * FROM time_entries
+        WHERE user_id = $1 AND id = ANY($2)
+        ORDER BY id
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .bind(&req.entry_ids)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch time entries: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to bill time" })),
+        )
+    })?;
+
+    let project_ids: Vec<Uuid> = entries
+        .iter()
+        .filter_map(|entry| entry.project_id)
+        .collect();
+    let entry_projects =
+        sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE user_id = $1 AND id = ANY($2)")
+            .bind(user_id)
+            .bind(&project_ids)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch projects: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Failed to bill time" })),
+                )
+            })?;
+
+    let selected = billable_time(&entries, &req.entry_ids, &client, &entry_projects)?;
+    let lines = group_time(&selected, req.group_by);
+
+    let project_id = projects::common_project(
+        entries
+            .iter()
+            .filter(|entry| selected.iter().any(|time| time.entry_id == entry.id))
+            .map(|entry| entry.project_id),
+    );
+    let invoice =
+        invoices::open_billing_invoice(&mut tx, user_id, client.id, project_id, req.invoice_id)
+            .await?;
+
+    let line_taxes = taxes::resolve_line_taxes(&mut tx, user_id, &client, &[None]).await?;
+    let default_taxes = line_taxes.into_iter().next().unwrap_or_default();
+
+    let new_items: Vec<NewLineItem> = lines
+        .iter()
+        .map(|line| NewLineItem {
+            description: line.description.clone(),
+            quantity: line.hours,
+            unit_price: line.rate,
+            taxes: default_taxes.clone(),
+        })
+        .collect();
+    let line_items = invoices::insert_line_items(&mut tx, invoice.id, &new_items).await?;
+
+    for (line, line_item) in lines.iter().zip(&line_items) {
+        sqlx::query(
+            "UPDATE time_entries SET invoice_line_item_id = $1, updated_at = NOW() WHERE id = ANY($2)",
+        )
+        .bind(line_item.id)
+        .bind(&line.entry_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark time entries as billed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to bill time" })),
+            )
+        })?;
+    }
+
+    let invoice = invoices::recalculate_totals(&mut tx, invoice.id).await?;
+    let details = invoices::fetch_invoice_details(&mut tx, invoice).await?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit time billing: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to bill time" })),
+        )
+    })?;
+
+    Ok(Json(details))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(day: u32, task: Option<&str>, minutes: i32, rate: i64) -> BillableTime {
+        BillableTime {
+            entry_id: Uuid::new_v4(),
+            entry_date: NaiveDate::from_ymd_opt(2026, 3, day).unwrap(),
+            task: task.map(str::to_string),
+            minutes,
+            rate: Decimal::from(rate),
+        }
+    }
+
+    #[test]
+    fn test_timer_minutes() {
+        let start = DateTime::parse_from_rfc3339("2026-03-02T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(timer_minutes(start, start), 1);
+        assert_eq!(
+            timer_minutes(start, start + chrono::Duration::seconds(60)),
+            1
+        );
+        assert_eq!(
+            timer_minutes(start, start + chrono::Duration::seconds(61)),
+            2
+        );
+        assert_eq!(
+            timer_minutes(start, start + chrono::Duration::minutes(90)),
+            90
+        );
+    }
+
+    #[test]
+    fn test_group_time() {
+        let entries = vec![
+            time(3, Some("Design"), 30, 100),
+            time(2, Some("Design"), 90, 100),
+            time(2, Some("Build"), 20, 100),
+            time(2, Some("Build"), 60, 120),
+        ];
+
+        let by_day = group_time(&entries, GroupBy::Day);
+        assert_eq!(by_day.len(), 3);
+        assert_eq!(by_day[0].description, "2026-03-02: Design, Build");
+        assert_eq!(by_day[0].hours, Decimal::new(183, 2)); // 110 minutes
+        assert_eq!(by_day[1].rate, Decimal::from(120));
+        assert_eq!(by_day[2].description, "2026-03-03: Design");
+
+        let by_task = group_time(&entries, GroupBy::Task);
+        assert_eq!(by_task.len(), 3);
+        assert_eq!(by_task[0].description, "Design (2026-03-02 to 2026-03-03)");
+        assert_eq!(by_task[0].hours, Decimal::from(2));
+        assert_eq!(by_task[0].entry_ids.len(), 2);
+        assert_eq!(by_task[1].description, "Build (2026-03-02)");
+    }
+}
@@ -0,0 +1,89 @@
+-- Allow child tables to reference a client together with its owner
+ALTER TABLE clients ADD CONSTRAINT clients_id_user_id_key UNIQUE (id, user_id);
+
+-- Create invoices table
+CREATE TABLE invoices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    client_id UUID NOT NULL,
+
+    invoice_number VARCHAR(50),
+
+    -- Status lifecycle: draft -> sent -> partially_paid -> paid, or void
+    status VARCHAR(20) NOT NULL DEFAULT 'draft'
+        CHECK (status IN ('draft', 'sent', 'partially_paid', 'paid', 'void')),
+
+    issue_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    due_date DATE,
+    notes TEXT,
+
+    -- Totals (computed server-side from line items)
+    subtotal NUMERIC(14, 2) NOT NULL DEFAULT 0,
+    tax_total NUMERIC(14, 2) NOT NULL DEFAULT 0,
+    total NUMERIC(14, 2) NOT NULL DEFAULT 0,
+    amount_paid NUMERIC(14, 2) NOT NULL DEFAULT 0,
+    balance_due NUMERIC(14, 2) NOT NULL DEFAULT 0,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- The client must belong to the same user as the invoice
+    CONSTRAINT invoices_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT invoices_due_date_check CHECK (due_date IS NULL OR due_date >= issue_date)
+);
+
+CREATE INDEX idx_invoices_user_id ON invoices(user_id);
+CREATE INDEX idx_invoices_client_id ON invoices(client_id);
+
+CREATE TRIGGER update_invoices_updated_at
+    BEFORE UPDATE ON invoices
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create invoice line items table
+CREATE TABLE invoice_line_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    quantity NUMERIC(14, 4) NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(14, 4) NOT NULL CHECK (unit_price >= 0),
+    amount NUMERIC(14, 2) NOT NULL
+);
+
+CREATE INDEX idx_invoice_line_items_invoice_id ON invoice_line_items(invoice_id);
+
+-- Enable Row Level Security
+ALTER TABLE invoices ENABLE ROW LEVEL SECURITY;
+ALTER TABLE invoice_line_items ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own invoices
+CREATE POLICY "Users can view their own invoices"
+    ON invoices FOR SELECT
+    USING (auth.uid() = user_id);
+
+CREATE POLICY "Users can create their own invoices"
+    ON invoices FOR INSERT
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can update their own invoices"
+    ON invoices FOR UPDATE
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can delete their own invoices"
+    ON invoices FOR DELETE
+    USING (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage line items of their own invoices"
+    ON invoice_line_items FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM invoices
+        WHERE invoices.id = invoice_line_items.invoice_id AND invoices.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM invoices
+        WHERE invoices.id = invoice_line_items.invoice_id AND invoices.user_id = auth.uid()
+    ));
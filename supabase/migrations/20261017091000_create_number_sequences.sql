@@ -0,0 +1,51 @@
+-- Create per-user document numbering sequences
+-- Numbers are allocated inside the transaction that creates the document, with
+-- the sequence row locked, so a rolled back document never leaves a gap.
+CREATE TABLE number_sequences (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+
+    document_type VARCHAR(20) NOT NULL
+        CHECK (document_type IN ('invoice', 'estimate', 'credit_note')),
+
+    -- Format: {prefix}-{year}-{zero padded value}, e.g. INV-2026-0042
+    prefix VARCHAR(20) NOT NULL,
+    padding SMALLINT NOT NULL DEFAULT 4 CHECK (padding BETWEEN 1 AND 10),
+    include_year BOOLEAN NOT NULL DEFAULT TRUE,
+    yearly_reset BOOLEAN NOT NULL DEFAULT TRUE,
+
+    -- Last allocated value and the year it was allocated in
+    current_year INTEGER NOT NULL,
+    last_value BIGINT NOT NULL DEFAULT 0 CHECK (last_value >= 0),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT number_sequences_user_document_key UNIQUE (user_id, document_type)
+);
+
+CREATE TRIGGER update_number_sequences_updated_at
+    BEFORE UPDATE ON number_sequences
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Invoice numbers are now generated by the numbering service and never repeat
+CREATE UNIQUE INDEX idx_invoices_user_invoice_number ON invoices(user_id, invoice_number);
+
+-- Enable Row Level Security
+ALTER TABLE number_sequences ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own sequences
+CREATE POLICY "Users can view their own number sequences"
+    ON number_sequences FOR SELECT
+    USING (auth.uid() = user_id);
+
+CREATE POLICY "Users can create their own number sequences"
+    ON number_sequences FOR INSERT
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can update their own number sequences"
+    ON number_sequences FOR UPDATE
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
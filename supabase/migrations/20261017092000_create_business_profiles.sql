@@ -0,0 +1,46 @@
+-- Create business profiles table (one per user, shown on issued documents)
+CREATE TABLE business_profiles (
+    user_id UUID PRIMARY KEY REFERENCES auth.users(id) ON DELETE CASCADE,
+
+    business_name VARCHAR(255) NOT NULL,
+    email VARCHAR(255),
+    phone VARCHAR(50),
+    website VARCHAR(255),
+
+    -- Sales tax registration number (e.g. GST/HST or VAT number)
+    tax_number VARCHAR(50),
+
+    -- Address
+    country VARCHAR(100),
+    address_line1 VARCHAR(255),
+    address_line2 VARCHAR(255),
+    city VARCHAR(100),
+    province VARCHAR(100),
+    postal_code VARCHAR(20),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_business_profiles_updated_at
+    BEFORE UPDATE ON business_profiles
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Enable Row Level Security
+ALTER TABLE business_profiles ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own business profile
+CREATE POLICY "Users can view their own business profile"
+    ON business_profiles FOR SELECT
+    USING (auth.uid() = user_id);
+
+CREATE POLICY "Users can create their own business profile"
+    ON business_profiles FOR INSERT
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can update their own business profile"
+    ON business_profiles FOR UPDATE
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
@@ -0,0 +1,124 @@
+-- Create tax rates table (user-defined, e.g. GST 5%, QST 9.975% compound)
+CREATE TABLE tax_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+
+    name VARCHAR(50) NOT NULL,
+    description VARCHAR(255),
+
+    -- Percentage, e.g. 9.975 for 9.975%
+    rate NUMERIC(7, 4) NOT NULL CHECK (rate >= 0 AND rate <= 100),
+
+    -- Compound taxes apply on top of the line amount plus its non-compound taxes
+    is_compound BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- Archived rates stay on existing documents but cannot be newly assigned
+    is_archived BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT tax_rates_id_user_id_key UNIQUE (id, user_id)
+);
+
+CREATE INDEX idx_tax_rates_user_id ON tax_rates(user_id);
+
+CREATE TRIGGER update_tax_rates_updated_at
+    BEFORE UPDATE ON tax_rates
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create tax rules table: default tax rates by client country/province
+-- A rule without a province applies to every province that has no rules of its own.
+CREATE TABLE tax_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    tax_rate_id UUID NOT NULL,
+    country VARCHAR(100) NOT NULL,
+    province VARCHAR(100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT tax_rules_tax_rate_fkey FOREIGN KEY (tax_rate_id, user_id)
+        REFERENCES tax_rates(id, user_id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_tax_rules_region_rate
+    ON tax_rules(user_id, LOWER(country), LOWER(COALESCE(province, '')), tax_rate_id);
+
+-- Invoices choose whether taxes are rounded per line or once per invoice
+ALTER TABLE invoices
+ADD COLUMN tax_rounding VARCHAR(20) NOT NULL DEFAULT 'per_line'
+    CHECK (tax_rounding IN ('per_line', 'per_invoice'));
+
+-- Taxes assigned to each invoice line, snapshotted so later rate edits do not
+-- change issued documents
+CREATE TABLE invoice_line_item_taxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    line_item_id UUID NOT NULL REFERENCES invoice_line_items(id) ON DELETE CASCADE,
+    tax_rate_id UUID NOT NULL REFERENCES tax_rates(id) ON DELETE RESTRICT,
+    position INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    rate NUMERIC(7, 4) NOT NULL,
+    is_compound BOOLEAN NOT NULL
+);
+
+CREATE INDEX idx_invoice_line_item_taxes_line_item_id ON invoice_line_item_taxes(line_item_id);
+
+-- Per-invoice tax summary, one row per tax rate
+CREATE TABLE invoice_taxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    tax_rate_id UUID NOT NULL REFERENCES tax_rates(id) ON DELETE RESTRICT,
+    position INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    rate NUMERIC(7, 4) NOT NULL,
+    is_compound BOOLEAN NOT NULL,
+    taxable_amount NUMERIC(14, 2) NOT NULL,
+    amount NUMERIC(14, 2) NOT NULL
+);
+
+CREATE INDEX idx_invoice_taxes_invoice_id ON invoice_taxes(invoice_id);
+
+-- Enable Row Level Security
+ALTER TABLE tax_rates ENABLE ROW LEVEL SECURITY;
+ALTER TABLE tax_rules ENABLE ROW LEVEL SECURITY;
+ALTER TABLE invoice_line_item_taxes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE invoice_taxes ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own taxes
+CREATE POLICY "Users can manage their own tax rates"
+    ON tax_rates FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage their own tax rules"
+    ON tax_rules FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage taxes of their own invoice lines"
+    ON invoice_line_item_taxes FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM invoice_line_items
+        JOIN invoices ON invoices.id = invoice_line_items.invoice_id
+        WHERE invoice_line_items.id = invoice_line_item_taxes.line_item_id
+            AND invoices.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM invoice_line_items
+        JOIN invoices ON invoices.id = invoice_line_items.invoice_id
+        WHERE invoice_line_items.id = invoice_line_item_taxes.line_item_id
+            AND invoices.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can manage taxes of their own invoices"
+    ON invoice_taxes FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM invoices
+        WHERE invoices.id = invoice_taxes.invoice_id AND invoices.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM invoices
+        WHERE invoices.id = invoice_taxes.invoice_id AND invoices.user_id = auth.uid()
+    ));
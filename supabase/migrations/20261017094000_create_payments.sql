@@ -0,0 +1,71 @@
+-- Create payments table (money received from a client)
+CREATE TABLE payments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    client_id UUID NOT NULL,
+
+    amount NUMERIC(14, 2) NOT NULL CHECK (amount > 0),
+    payment_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    method VARCHAR(20) NOT NULL DEFAULT 'bank_transfer'
+        CHECK (method IN ('cash', 'check', 'bank_transfer', 'credit_card', 'other')),
+    reference VARCHAR(100),
+    note TEXT,
+
+    -- Portion not allocated to any invoice, available to the client as credit
+    unapplied_amount NUMERIC(14, 2) NOT NULL,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT payments_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT payments_unapplied_amount_check
+        CHECK (unapplied_amount >= 0 AND unapplied_amount <= amount)
+);
+
+CREATE INDEX idx_payments_user_id ON payments(user_id);
+CREATE INDEX idx_payments_client_id ON payments(client_id);
+
+CREATE TRIGGER update_payments_updated_at
+    BEFORE UPDATE ON payments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create payment allocations table (how much of a payment settles each invoice)
+CREATE TABLE payment_allocations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payment_id UUID NOT NULL REFERENCES payments(id) ON DELETE CASCADE,
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE RESTRICT,
+    amount NUMERIC(14, 2) NOT NULL CHECK (amount > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT payment_allocations_payment_invoice_key UNIQUE (payment_id, invoice_id)
+);
+
+CREATE INDEX idx_payment_allocations_invoice_id ON payment_allocations(invoice_id);
+
+-- An invoice can never be paid beyond its total
+ALTER TABLE invoices
+ADD CONSTRAINT invoices_balance_due_check CHECK (balance_due >= 0);
+
+-- Enable Row Level Security
+ALTER TABLE payments ENABLE ROW LEVEL SECURITY;
+ALTER TABLE payment_allocations ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own payments
+CREATE POLICY "Users can manage their own payments"
+    ON payments FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage allocations of their own payments"
+    ON payment_allocations FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM payments
+        WHERE payments.id = payment_allocations.payment_id AND payments.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM payments
+        WHERE payments.id = payment_allocations.payment_id AND payments.user_id = auth.uid()
+    ));
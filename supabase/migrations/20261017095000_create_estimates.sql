@@ -0,0 +1,137 @@
+-- Create estimates table (quotes sent before work starts)
+CREATE TABLE estimates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    client_id UUID NOT NULL,
+
+    estimate_number VARCHAR(50) NOT NULL,
+
+    -- Status lifecycle: draft -> sent -> accepted or declined
+    status VARCHAR(20) NOT NULL DEFAULT 'draft'
+        CHECK (status IN ('draft', 'sent', 'accepted', 'declined')),
+
+    issue_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    expiry_date DATE,
+    notes TEXT,
+    tax_rounding VARCHAR(20) NOT NULL DEFAULT 'per_line'
+        CHECK (tax_rounding IN ('per_line', 'per_invoice')),
+
+    -- Totals (computed server-side from line items)
+    subtotal NUMERIC(14, 2) NOT NULL DEFAULT 0,
+    tax_total NUMERIC(14, 2) NOT NULL DEFAULT 0,
+    total NUMERIC(14, 2) NOT NULL DEFAULT 0,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT estimates_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT estimates_expiry_date_check CHECK (expiry_date IS NULL OR expiry_date >= issue_date)
+);
+
+CREATE INDEX idx_estimates_user_id ON estimates(user_id);
+CREATE INDEX idx_estimates_client_id ON estimates(client_id);
+CREATE UNIQUE INDEX idx_estimates_user_estimate_number ON estimates(user_id, estimate_number);
+
+CREATE TRIGGER update_estimates_updated_at
+    BEFORE UPDATE ON estimates
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create estimate line items table
+CREATE TABLE estimate_line_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    estimate_id UUID NOT NULL REFERENCES estimates(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    quantity NUMERIC(14, 4) NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(14, 4) NOT NULL CHECK (unit_price >= 0),
+    amount NUMERIC(14, 2) NOT NULL
+);
+
+CREATE INDEX idx_estimate_line_items_estimate_id ON estimate_line_items(estimate_id);
+
+-- Taxes assigned to each estimate line
+CREATE TABLE estimate_line_item_taxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    line_item_id UUID NOT NULL REFERENCES estimate_line_items(id) ON DELETE CASCADE,
+    tax_rate_id UUID NOT NULL REFERENCES tax_rates(id) ON DELETE RESTRICT,
+    position INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    rate NUMERIC(7, 4) NOT NULL,
+    is_compound BOOLEAN NOT NULL
+);
+
+CREATE INDEX idx_estimate_line_item_taxes_line_item_id ON estimate_line_item_taxes(line_item_id);
+
+-- Per-estimate tax summary, one row per tax rate
+CREATE TABLE estimate_taxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    estimate_id UUID NOT NULL REFERENCES estimates(id) ON DELETE CASCADE,
+    tax_rate_id UUID NOT NULL REFERENCES tax_rates(id) ON DELETE RESTRICT,
+    position INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    rate NUMERIC(7, 4) NOT NULL,
+    is_compound BOOLEAN NOT NULL,
+    taxable_amount NUMERIC(14, 2) NOT NULL,
+    amount NUMERIC(14, 2) NOT NULL
+);
+
+CREATE INDEX idx_estimate_taxes_estimate_id ON estimate_taxes(estimate_id);
+
+-- Invoices converted from an estimate keep a link back to it; an estimate can
+-- only be converted once
+ALTER TABLE invoices
+ADD COLUMN estimate_id UUID REFERENCES estimates(id) ON DELETE SET NULL;
+
+CREATE UNIQUE INDEX idx_invoices_estimate_id ON invoices(estimate_id) WHERE estimate_id IS NOT NULL;
+
+-- Enable Row Level Security
+ALTER TABLE estimates ENABLE ROW LEVEL SECURITY;
+ALTER TABLE estimate_line_items ENABLE ROW LEVEL SECURITY;
+ALTER TABLE estimate_line_item_taxes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE estimate_taxes ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own estimates
+CREATE POLICY "Users can manage their own estimates"
+    ON estimates FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage line items of their own estimates"
+    ON estimate_line_items FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM estimates
+        WHERE estimates.id = estimate_line_items.estimate_id AND estimates.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM estimates
+        WHERE estimates.id = estimate_line_items.estimate_id AND estimates.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can manage taxes of their own estimate lines"
+    ON estimate_line_item_taxes FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM estimate_line_items
+        JOIN estimates ON estimates.id = estimate_line_items.estimate_id
+        WHERE estimate_line_items.id = estimate_line_item_taxes.line_item_id
+            AND estimates.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM estimate_line_items
+        JOIN estimates ON estimates.id = estimate_line_items.estimate_id
+        WHERE estimate_line_items.id = estimate_line_item_taxes.line_item_id
+            AND estimates.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can manage taxes of their own estimates"
+    ON estimate_taxes FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM estimates
+        WHERE estimates.id = estimate_taxes.estimate_id AND estimates.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM estimates
+        WHERE estimates.id = estimate_taxes.estimate_id AND estimates.user_id = auth.uid()
+    ));
@@ -0,0 +1,92 @@
+-- Create recurring invoice profiles (templates the scheduler turns into invoices)
+CREATE TABLE recurring_profiles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    client_id UUID NOT NULL,
+
+    name VARCHAR(255) NOT NULL,
+    frequency VARCHAR(20) NOT NULL CHECK (frequency IN ('weekly', 'monthly', 'yearly')),
+    start_date DATE NOT NULL,
+    end_date DATE,
+
+    -- Stop after this many invoices (NULL = no limit)
+    max_occurrences INTEGER CHECK (max_occurrences > 0),
+    occurrences_generated INTEGER NOT NULL DEFAULT 0,
+
+    -- Date of the next invoice to generate; NULL once the schedule has finished
+    next_run_date DATE,
+
+    -- Generated invoices are marked as sent instead of left as drafts
+    auto_send BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Days after the issue date that generated invoices are due (NULL = no due date)
+    due_days INTEGER CHECK (due_days >= 0),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+
+    notes TEXT,
+    tax_rounding VARCHAR(20) NOT NULL DEFAULT 'per_line'
+        CHECK (tax_rounding IN ('per_line', 'per_invoice')),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT recurring_profiles_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT recurring_profiles_end_date_check CHECK (end_date IS NULL OR end_date >= start_date)
+);
+
+CREATE INDEX idx_recurring_profiles_user_id ON recurring_profiles(user_id);
+CREATE INDEX idx_recurring_profiles_next_run_date ON recurring_profiles(next_run_date)
+    WHERE is_active AND next_run_date IS NOT NULL;
+
+CREATE TRIGGER update_recurring_profiles_updated_at
+    BEFORE UPDATE ON recurring_profiles
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Template line items copied onto every generated invoice
+CREATE TABLE recurring_profile_line_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    profile_id UUID NOT NULL REFERENCES recurring_profiles(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    quantity NUMERIC(14, 4) NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(14, 4) NOT NULL CHECK (unit_price >= 0),
+    -- Explicit tax rates; NULL applies the client's regional defaults at generation time
+    tax_rate_ids UUID[]
+);
+
+CREATE INDEX idx_recurring_profile_line_items_profile_id ON recurring_profile_line_items(profile_id);
+
+-- Generated invoices remember the profile and occurrence they came from; the
+-- unique index makes generation idempotent across restarts and instances
+ALTER TABLE invoices
+ADD COLUMN recurring_profile_id UUID REFERENCES recurring_profiles(id) ON DELETE SET NULL,
+ADD COLUMN recurrence_date DATE;
+
+CREATE UNIQUE INDEX idx_invoices_recurring_occurrence
+    ON invoices(recurring_profile_id, recurrence_date)
+    WHERE recurring_profile_id IS NOT NULL;
+
+-- Enable Row Level Security
+ALTER TABLE recurring_profiles ENABLE ROW LEVEL SECURITY;
+ALTER TABLE recurring_profile_line_items ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own recurring profiles
+CREATE POLICY "Users can manage their own recurring profiles"
+    ON recurring_profiles FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage line items of their own recurring profiles"
+    ON recurring_profile_line_items FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM recurring_profiles
+        WHERE recurring_profiles.id = recurring_profile_line_items.profile_id
+            AND recurring_profiles.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM recurring_profiles
+        WHERE recurring_profiles.id = recurring_profile_line_items.profile_id
+            AND recurring_profiles.user_id = auth.uid()
+    ));
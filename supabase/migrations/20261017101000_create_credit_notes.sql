@@ -0,0 +1,173 @@
+-- Create credit notes table (full or partial reversal of an issued invoice)
+-- Credit notes are never edited or deleted once issued.
+CREATE TABLE credit_notes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    client_id UUID NOT NULL,
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE RESTRICT,
+
+    credit_note_number VARCHAR(50) NOT NULL,
+    issue_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    reason TEXT,
+
+    -- Totals (computed server-side from line items)
+    subtotal NUMERIC(14, 2) NOT NULL,
+    tax_total NUMERIC(14, 2) NOT NULL,
+    total NUMERIC(14, 2) NOT NULL CHECK (total > 0),
+
+    -- Portion that reduced the invoice balance; the rest is client credit
+    amount_applied NUMERIC(14, 2) NOT NULL,
+    unapplied_amount NUMERIC(14, 2) NOT NULL,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT credit_notes_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT credit_notes_amounts_check CHECK (
+        amount_applied >= 0 AND unapplied_amount >= 0 AND amount_applied + unapplied_amount = total
+    )
+);
+
+CREATE INDEX idx_credit_notes_user_id ON credit_notes(user_id);
+CREATE INDEX idx_credit_notes_invoice_id ON credit_notes(invoice_id);
+CREATE UNIQUE INDEX idx_credit_notes_user_number ON credit_notes(user_id, credit_note_number);
+
+-- Create credit note line items table
+CREATE TABLE credit_note_line_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    credit_note_id UUID NOT NULL REFERENCES credit_notes(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    quantity NUMERIC(14, 4) NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(14, 4) NOT NULL CHECK (unit_price >= 0),
+    amount NUMERIC(14, 2) NOT NULL
+);
+
+CREATE INDEX idx_credit_note_line_items_credit_note_id ON credit_note_line_items(credit_note_id);
+
+-- Taxes reversed on each credit note line
+CREATE TABLE credit_note_line_item_taxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    line_item_id UUID NOT NULL REFERENCES credit_note_line_items(id) ON DELETE CASCADE,
+    tax_rate_id UUID NOT NULL REFERENCES tax_rates(id) ON DELETE RESTRICT,
+    position INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    rate NUMERIC(7, 4) NOT NULL,
+    is_compound BOOLEAN NOT NULL
+);
+
+CREATE INDEX idx_credit_note_line_item_taxes_line_item_id
+    ON credit_note_line_item_taxes(line_item_id);
+
+-- Per-credit-note tax summary, one row per tax rate
+CREATE TABLE credit_note_taxes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    credit_note_id UUID NOT NULL REFERENCES credit_notes(id) ON DELETE CASCADE,
+    tax_rate_id UUID NOT NULL REFERENCES tax_rates(id) ON DELETE RESTRICT,
+    position INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    rate NUMERIC(7, 4) NOT NULL,
+    is_compound BOOLEAN NOT NULL,
+    taxable_amount NUMERIC(14, 2) NOT NULL,
+    amount NUMERIC(14, 2) NOT NULL
+);
+
+CREATE INDEX idx_credit_note_taxes_credit_note_id ON credit_note_taxes(credit_note_id);
+
+-- Invoices track credits separately from payments; voided invoices record why
+ALTER TABLE invoices
+ADD COLUMN amount_credited NUMERIC(14, 2) NOT NULL DEFAULT 0,
+ADD COLUMN voided_at TIMESTAMPTZ,
+ADD COLUMN void_reason TEXT;
+
+-- A voided invoice is kept for the record and its contents can no longer change
+-- (links such as estimate_id may still be cleared when the source is deleted)
+CREATE OR REPLACE FUNCTION prevent_voided_invoice_changes()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF OLD.status = 'void' AND (
+        NEW.status, NEW.client_id, NEW.invoice_number, NEW.issue_date, NEW.due_date,
+        NEW.subtotal, NEW.tax_total, NEW.total, NEW.amount_paid, NEW.amount_credited,
+        NEW.balance_due
+    ) IS DISTINCT FROM (
+        OLD.status, OLD.client_id, OLD.invoice_number, OLD.issue_date, OLD.due_date,
+        OLD.subtotal, OLD.tax_total, OLD.total, OLD.amount_paid, OLD.amount_credited,
+        OLD.balance_due
+    ) THEN
+        RAISE EXCEPTION 'Invoice % is void and cannot be modified', OLD.id
+            USING ERRCODE = 'check_violation';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER prevent_voided_invoice_changes
+    BEFORE UPDATE ON invoices
+    FOR EACH ROW
+    EXECUTE FUNCTION prevent_voided_invoice_changes();
+
+-- Enable Row Level Security
+ALTER TABLE credit_notes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE credit_note_line_items ENABLE ROW LEVEL SECURITY;
+ALTER TABLE credit_note_line_item_taxes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE credit_note_taxes ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can view and issue their own credit notes (no updates or deletes)
+CREATE POLICY "Users can view their own credit notes"
+    ON credit_notes FOR SELECT
+    USING (auth.uid() = user_id);
+
+CREATE POLICY "Users can create their own credit notes"
+    ON credit_notes FOR INSERT
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can view line items of their own credit notes"
+    ON credit_note_line_items FOR SELECT
+    USING (EXISTS (
+        SELECT 1 FROM credit_notes
+        WHERE credit_notes.id = credit_note_line_items.credit_note_id
+            AND credit_notes.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can create line items of their own credit notes"
+    ON credit_note_line_items FOR INSERT
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM credit_notes
+        WHERE credit_notes.id = credit_note_line_items.credit_note_id
+            AND credit_notes.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can view taxes of their own credit note lines"
+    ON credit_note_line_item_taxes FOR SELECT
+    USING (EXISTS (
+        SELECT 1 FROM credit_note_line_items
+        JOIN credit_notes ON credit_notes.id = credit_note_line_items.credit_note_id
+        WHERE credit_note_line_items.id = credit_note_line_item_taxes.line_item_id
+            AND credit_notes.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can create taxes of their own credit note lines"
+    ON credit_note_line_item_taxes FOR INSERT
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM credit_note_line_items
+        JOIN credit_notes ON credit_notes.id = credit_note_line_items.credit_note_id
+        WHERE credit_note_line_items.id = credit_note_line_item_taxes.line_item_id
+            AND credit_notes.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can view taxes of their own credit notes"
+    ON credit_note_taxes FOR SELECT
+    USING (EXISTS (
+        SELECT 1 FROM credit_notes
+        WHERE credit_notes.id = credit_note_taxes.credit_note_id
+            AND credit_notes.user_id = auth.uid()
+    ));
+
+CREATE POLICY "Users can create taxes of their own credit notes"
+    ON credit_note_taxes FOR INSERT
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM credit_notes
+        WHERE credit_notes.id = credit_note_taxes.credit_note_id
+            AND credit_notes.user_id = auth.uid()
+    ));
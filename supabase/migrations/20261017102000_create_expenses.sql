@@ -0,0 +1,83 @@
+-- Create expenses table (costs incurred by the business, optionally for a client)
+CREATE TABLE expenses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    -- Client the expense was incurred for, so it can be rebilled (NULL = overhead)
+    client_id UUID,
+
+    vendor VARCHAR(255) NOT NULL,
+    category VARCHAR(100) NOT NULL,
+    description TEXT,
+    expense_date DATE NOT NULL DEFAULT CURRENT_DATE,
+
+    -- Amounts: net cost, tax paid on it, and what left the bank account
+    amount NUMERIC(14, 2) NOT NULL CHECK (amount > 0),
+    tax_rate_id UUID REFERENCES tax_rates(id) ON DELETE RESTRICT,
+    tax_amount NUMERIC(14, 2) NOT NULL DEFAULT 0 CHECK (tax_amount >= 0),
+    total NUMERIC(14, 2) NOT NULL,
+
+    -- Receipt stored in the private "receipts" bucket under {user_id}/{expense_id}/
+    receipt_path TEXT,
+    receipt_filename VARCHAR(255),
+    receipt_content_type VARCHAR(100),
+
+    notes TEXT,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT expenses_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT expenses_total_check CHECK (total = amount + tax_amount)
+);
+
+CREATE INDEX idx_expenses_user_id ON expenses(user_id);
+CREATE INDEX idx_expenses_client_id ON expenses(client_id);
+CREATE INDEX idx_expenses_expense_date ON expenses(expense_date);
+
+CREATE TRIGGER update_expenses_updated_at
+    BEFORE UPDATE ON expenses
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Enable Row Level Security
+ALTER TABLE expenses ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own expenses
+CREATE POLICY "Users can manage their own expenses"
+    ON expenses FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+-- Private bucket for receipt files; objects are only reachable through signed URLs
+INSERT INTO storage.buckets (id, name, public, file_size_limit, allowed_mime_types)
+VALUES (
+    'receipts',
+    'receipts',
+    FALSE,
+    10485760,
+    ARRAY['image/jpeg', 'image/png', 'image/webp', 'image/heic', 'application/pdf']
+)
+ON CONFLICT (id) DO NOTHING;
+
+-- Storage policies: the first path segment of every receipt is its owner's user id
+CREATE POLICY "Users can read their own receipts"
+    ON storage.objects FOR SELECT
+    TO authenticated
+    USING (bucket_id = 'receipts' AND (storage.foldername(name))[1] = auth.uid()::text);
+
+CREATE POLICY "Users can upload their own receipts"
+    ON storage.objects FOR INSERT
+    TO authenticated
+    WITH CHECK (bucket_id = 'receipts' AND (storage.foldername(name))[1] = auth.uid()::text);
+
+CREATE POLICY "Users can replace their own receipts"
+    ON storage.objects FOR UPDATE
+    TO authenticated
+    USING (bucket_id = 'receipts' AND (storage.foldername(name))[1] = auth.uid()::text);
+
+CREATE POLICY "Users can delete their own receipts"
+    ON storage.objects FOR DELETE
+    TO authenticated
+    USING (bucket_id = 'receipts' AND (storage.foldername(name))[1] = auth.uid()::text);
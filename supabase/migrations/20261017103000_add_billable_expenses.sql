@@ -0,0 +1,15 @@
+-- Billable expenses are rebilled to their client as invoice line items
+ALTER TABLE expenses
+ADD COLUMN billable BOOLEAN NOT NULL DEFAULT FALSE,
+-- Line the expense was billed on; NULL while unbilled. Deleting the line
+-- (editing or deleting a draft invoice) makes the expense billable again.
+ADD COLUMN invoice_line_item_id UUID REFERENCES invoice_line_items(id) ON DELETE SET NULL,
+ADD CONSTRAINT expenses_billable_client_check CHECK (NOT billable OR client_id IS NOT NULL),
+ADD CONSTRAINT expenses_billed_check CHECK (invoice_line_item_id IS NULL OR billable);
+
+-- An invoice line bills at most one expense, so nothing is invoiced twice
+CREATE UNIQUE INDEX idx_expenses_invoice_line_item_id ON expenses(invoice_line_item_id)
+    WHERE invoice_line_item_id IS NOT NULL;
+
+CREATE INDEX idx_expenses_unbilled ON expenses(user_id, client_id)
+    WHERE billable AND invoice_line_item_id IS NULL;
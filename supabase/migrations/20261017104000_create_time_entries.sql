@@ -0,0 +1,63 @@
+-- Default rate for time billed to a client
+ALTER TABLE clients
+ADD COLUMN default_hourly_rate NUMERIC(14, 2) CHECK (default_hourly_rate >= 0);
+
+-- Create time entries table (timer runs and manually entered time)
+CREATE TABLE time_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    client_id UUID,
+
+    task VARCHAR(255),
+    description TEXT,
+    entry_date DATE NOT NULL DEFAULT CURRENT_DATE,
+
+    -- Timer entries have a start, and an end once stopped; manual entries only a duration
+    started_at TIMESTAMPTZ,
+    ended_at TIMESTAMPTZ,
+    duration_minutes INTEGER CHECK (duration_minutes > 0),
+
+    -- Overrides the client's default hourly rate (NULL = use the default)
+    hourly_rate NUMERIC(14, 2) CHECK (hourly_rate >= 0),
+    billable BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Invoice line the entry was billed on; several entries may share a line
+    invoice_line_item_id UUID REFERENCES invoice_line_items(id) ON DELETE SET NULL,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT time_entries_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT time_entries_duration_check CHECK (
+        (started_at IS NULL AND ended_at IS NULL AND duration_minutes IS NOT NULL)
+        OR (started_at IS NOT NULL AND (ended_at IS NULL) = (duration_minutes IS NULL))
+    ),
+    CONSTRAINT time_entries_ended_at_check CHECK (ended_at IS NULL OR ended_at >= started_at),
+    CONSTRAINT time_entries_billable_client_check CHECK (NOT billable OR client_id IS NOT NULL),
+    CONSTRAINT time_entries_billed_check CHECK (
+        invoice_line_item_id IS NULL OR (billable AND duration_minutes IS NOT NULL)
+    )
+);
+
+CREATE INDEX idx_time_entries_user_id ON time_entries(user_id);
+CREATE INDEX idx_time_entries_client_id ON time_entries(client_id);
+CREATE INDEX idx_time_entries_invoice_line_item_id ON time_entries(invoice_line_item_id);
+
+-- Only one timer can run per user at a time
+CREATE UNIQUE INDEX idx_time_entries_running_timer ON time_entries(user_id)
+    WHERE started_at IS NOT NULL AND ended_at IS NULL;
+
+CREATE TRIGGER update_time_entries_updated_at
+    BEFORE UPDATE ON time_entries
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Enable Row Level Security
+ALTER TABLE time_entries ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own time entries
+CREATE POLICY "Users can manage their own time entries"
+    ON time_entries FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
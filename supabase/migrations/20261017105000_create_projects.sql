@@ -0,0 +1,74 @@
+-- Create projects table (work for a client, tracked against an optional budget)
+CREATE TABLE projects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    client_id UUID NOT NULL,
+
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+
+    -- fixed_fee: billed for fixed_fee; hourly_project_rate: all time at hourly_rate;
+    -- hourly_staff_rate: time at each entry's own rate (or the client default)
+    billing_method VARCHAR(30) NOT NULL
+        CHECK (billing_method IN ('fixed_fee', 'hourly_project_rate', 'hourly_staff_rate')),
+    hourly_rate NUMERIC(14, 2) CHECK (hourly_rate >= 0),
+    fixed_fee NUMERIC(14, 2) CHECK (fixed_fee >= 0),
+
+    -- Budget in hours of time or in money (time value plus expenses); NULL = none
+    budget_type VARCHAR(10) CHECK (budget_type IN ('hours', 'money')),
+    budget_amount NUMERIC(14, 2) CHECK (budget_amount > 0),
+
+    is_archived BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT projects_id_user_id_key UNIQUE (id, user_id),
+    CONSTRAINT projects_client_fkey FOREIGN KEY (client_id, user_id)
+        REFERENCES clients(id, user_id) ON DELETE RESTRICT,
+    CONSTRAINT projects_budget_check CHECK ((budget_type IS NULL) = (budget_amount IS NULL)),
+    CONSTRAINT projects_hourly_rate_check CHECK (
+        billing_method <> 'hourly_project_rate' OR hourly_rate IS NOT NULL
+    ),
+    CONSTRAINT projects_fixed_fee_check CHECK (
+        billing_method <> 'fixed_fee' OR fixed_fee IS NOT NULL
+    )
+);
+
+CREATE INDEX idx_projects_user_id ON projects(user_id);
+CREATE INDEX idx_projects_client_id ON projects(client_id);
+
+CREATE TRIGGER update_projects_updated_at
+    BEFORE UPDATE ON projects
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Time, expenses and invoices can be linked to one of the user's projects
+ALTER TABLE time_entries
+ADD COLUMN project_id UUID,
+ADD CONSTRAINT time_entries_project_fkey FOREIGN KEY (project_id, user_id)
+    REFERENCES projects(id, user_id) ON DELETE RESTRICT;
+
+ALTER TABLE expenses
+ADD COLUMN project_id UUID,
+ADD CONSTRAINT expenses_project_fkey FOREIGN KEY (project_id, user_id)
+    REFERENCES projects(id, user_id) ON DELETE RESTRICT;
+
+ALTER TABLE invoices
+ADD COLUMN project_id UUID,
+ADD CONSTRAINT invoices_project_fkey FOREIGN KEY (project_id, user_id)
+    REFERENCES projects(id, user_id) ON DELETE RESTRICT;
+
+CREATE INDEX idx_time_entries_project_id ON time_entries(project_id);
+CREATE INDEX idx_expenses_project_id ON expenses(project_id);
+CREATE INDEX idx_invoices_project_id ON invoices(project_id);
+
+-- Enable Row Level Security
+ALTER TABLE projects ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own projects
+CREATE POLICY "Users can manage their own projects"
+    ON projects FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
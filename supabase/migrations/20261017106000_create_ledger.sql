@@ -0,0 +1,167 @@
+-- Create chart of accounts (one per user; system accounts are posted to automatically)
+CREATE TABLE accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+
+    code VARCHAR(20) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    account_type VARCHAR(20) NOT NULL
+        CHECK (account_type IN ('asset', 'liability', 'equity', 'income', 'expense')),
+    -- Role of the account in automatic postings (e.g. accounts_receivable); NULL = user account
+    system_key VARCHAR(50),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT accounts_id_user_id_key UNIQUE (id, user_id),
+    CONSTRAINT accounts_user_id_code_key UNIQUE (user_id, code),
+    CONSTRAINT accounts_user_id_system_key_key UNIQUE (user_id, system_key)
+);
+
+CREATE INDEX idx_accounts_user_id ON accounts(user_id);
+
+CREATE TRIGGER update_accounts_updated_at
+    BEFORE UPDATE ON accounts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create journal entries table (append-only; corrections are reversing entries)
+CREATE TABLE journal_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+
+    entry_date DATE NOT NULL,
+    description TEXT NOT NULL,
+
+    -- Document that produced the entry
+    source_type VARCHAR(20) NOT NULL
+        CHECK (source_type IN ('invoice', 'payment', 'credit_note', 'expense')),
+    source_id UUID NOT NULL,
+    -- Entry this one cancels out; each entry is reversed at most once
+    reverses_entry_id UUID UNIQUE REFERENCES journal_entries(id),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT journal_entries_id_user_id_key UNIQUE (id, user_id)
+);
+
+CREATE INDEX idx_journal_entries_user_id_entry_date ON journal_entries(user_id, entry_date);
+CREATE INDEX idx_journal_entries_source ON journal_entries(source_type, source_id);
+
+-- Create journal lines table (one debit or credit to an account)
+CREATE TABLE journal_lines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    journal_entry_id UUID NOT NULL,
+    account_id UUID NOT NULL,
+
+    debit NUMERIC(14, 2) NOT NULL DEFAULT 0 CHECK (debit >= 0),
+    credit NUMERIC(14, 2) NOT NULL DEFAULT 0 CHECK (credit >= 0),
+
+    CONSTRAINT journal_lines_entry_fkey FOREIGN KEY (journal_entry_id, user_id)
+        REFERENCES journal_entries(id, user_id) ON DELETE CASCADE,
+    -- Not RESTRICT: accounts and lines go together when the user is deleted
+    CONSTRAINT journal_lines_account_fkey FOREIGN KEY (account_id, user_id)
+        REFERENCES accounts(id, user_id),
+    CONSTRAINT journal_lines_one_side_check CHECK ((debit = 0) <> (credit = 0))
+);
+
+CREATE INDEX idx_journal_lines_journal_entry_id ON journal_lines(journal_entry_id);
+CREATE INDEX idx_journal_lines_account_id ON journal_lines(account_id);
+
+-- Every entry must have at least two lines and its debits must equal its
+-- credits. Checked at commit so the entry and its lines can be inserted in turn.
+CREATE OR REPLACE FUNCTION check_journal_entry_balanced()
+RETURNS TRIGGER AS $$
+DECLARE
+    entry_id UUID;
+    line_count INTEGER;
+    total_debit NUMERIC;
+    total_credit NUMERIC;
+BEGIN
+    IF TG_TABLE_NAME = 'journal_entries' THEN
+        entry_id := NEW.id;
+    ELSE
+        entry_id := NEW.journal_entry_id;
+    END IF;
+
+    SELECT COUNT(*), COALESCE(SUM(debit), 0), COALESCE(SUM(credit), 0)
+    INTO line_count, total_debit, total_credit
+    FROM journal_lines
+    WHERE journal_entry_id = entry_id;
+
+    IF line_count < 2 THEN
+        RAISE EXCEPTION 'Journal entry % needs at least two lines', entry_id;
+    END IF;
+
+    IF total_debit <> total_credit THEN
+        RAISE EXCEPTION 'Journal entry % does not balance: debits % <> credits %',
+            entry_id, total_debit, total_credit;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER check_journal_entries_balanced
+    AFTER INSERT ON journal_entries
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW
+    EXECUTE FUNCTION check_journal_entry_balanced();
+
+CREATE CONSTRAINT TRIGGER check_journal_lines_balanced
+    AFTER INSERT ON journal_lines
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW
+    EXECUTE FUNCTION check_journal_entry_balanced();
+
+-- The journal is append-only. Rows are only removed along with their user.
+CREATE OR REPLACE FUNCTION prevent_journal_modification()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' AND NOT EXISTS (SELECT 1 FROM auth.users WHERE id = OLD.user_id) THEN
+        RETURN OLD;
+    END IF;
+
+    RAISE EXCEPTION '% is append-only; post a reversing entry instead', TG_TABLE_NAME;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER prevent_journal_entries_modification
+    BEFORE UPDATE OR DELETE ON journal_entries
+    FOR EACH ROW
+    EXECUTE FUNCTION prevent_journal_modification();
+
+CREATE TRIGGER prevent_journal_lines_modification
+    BEFORE UPDATE OR DELETE ON journal_lines
+    FOR EACH ROW
+    EXECUTE FUNCTION prevent_journal_modification();
+
+-- Enable Row Level Security
+ALTER TABLE accounts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE journal_entries ENABLE ROW LEVEL SECURITY;
+ALTER TABLE journal_lines ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own accounts and journal
+CREATE POLICY "Users can manage their own accounts"
+    ON accounts FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can view their own journal entries"
+    ON journal_entries FOR SELECT
+    USING (auth.uid() = user_id);
+
+CREATE POLICY "Users can post their own journal entries"
+    ON journal_entries FOR INSERT
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can view their own journal lines"
+    ON journal_lines FOR SELECT
+    USING (auth.uid() = user_id);
+
+CREATE POLICY "Users can post their own journal lines"
+    ON journal_lines FOR INSERT
+    WITH CHECK (auth.uid() = user_id);
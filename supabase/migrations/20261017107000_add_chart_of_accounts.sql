@@ -0,0 +1,27 @@
+-- Accounts are grouped under parent accounts of the same type
+ALTER TABLE accounts
+ADD COLUMN parent_id UUID,
+ADD COLUMN description TEXT,
+ADD COLUMN is_archived BOOLEAN NOT NULL DEFAULT FALSE,
+-- Not RESTRICT: parents and sub-accounts go together when the user is deleted
+ADD CONSTRAINT accounts_parent_fkey FOREIGN KEY (parent_id, user_id)
+    REFERENCES accounts(id, user_id),
+ADD CONSTRAINT accounts_parent_check CHECK (parent_id <> id);
+
+CREATE INDEX idx_accounts_parent_id ON accounts(parent_id);
+
+-- Users whose default chart of accounts has been created, so that accounts
+-- they delete afterwards are not seeded again
+CREATE TABLE account_charts (
+    user_id UUID PRIMARY KEY REFERENCES auth.users(id) ON DELETE CASCADE,
+    seeded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Enable Row Level Security
+ALTER TABLE account_charts ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own chart record
+CREATE POLICY "Users can manage their own account chart"
+    ON account_charts FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
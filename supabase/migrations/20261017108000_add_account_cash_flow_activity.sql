@@ -0,0 +1,16 @@
+-- Section of the cash flow statement an account's cash movements are reported
+-- in; NULL = financing for equity accounts, operating for everything else
+ALTER TABLE accounts
+ADD COLUMN cash_flow_activity VARCHAR(20)
+    CHECK (cash_flow_activity IN ('operating', 'investing', 'financing'));
+
+-- Classify the default chart of users seeded before this column existed
+UPDATE accounts a
+SET cash_flow_activity = 'investing'
+FROM account_charts c
+WHERE a.user_id = c.user_id AND a.code = '1500' AND a.name = 'Equipment';
+
+UPDATE accounts a
+SET cash_flow_activity = 'financing'
+FROM account_charts c
+WHERE a.user_id = c.user_id AND a.code = '2700' AND a.name = 'Loans Payable';
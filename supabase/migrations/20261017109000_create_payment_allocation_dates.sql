@@ -0,0 +1,17 @@
+-- Day each payment allocation settled its invoice, matching the ledger: the
+-- payment date for allocations made with the payment, otherwise the day the
+-- client's credit was applied
+CREATE VIEW payment_allocation_dates
+WITH (security_invoker = true) AS
+SELECT
+    a.id,
+    a.payment_id,
+    a.invoice_id,
+    a.amount,
+    p.user_id,
+    CASE
+        WHEN a.created_at = p.created_at THEN p.payment_date
+        ELSE GREATEST(p.payment_date, a.created_at::date)
+    END AS settled_on
+FROM payment_allocations a
+JOIN payments p ON p.id = a.payment_id;
@@ -0,0 +1,73 @@
+-- Currency the books are kept in; the ledger and reports are in this currency
+ALTER TABLE business_profiles
+ADD COLUMN home_currency VARCHAR(3) NOT NULL DEFAULT 'USD'
+    CHECK (home_currency ~ '^[A-Z]{3}$');
+
+-- Currency new invoices and payments for the client default to (NULL = home currency)
+ALTER TABLE clients
+ADD COLUMN currency VARCHAR(3) CHECK (currency ~ '^[A-Z]{3}$');
+
+-- Documents are in their own currency. The exchange rate is the home currency
+-- value of one unit, fixed when the document is posted to the ledger.
+-- Existing documents were all in the default home currency.
+ALTER TABLE invoices
+ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'USD' CHECK (currency ~ '^[A-Z]{3}$'),
+-- NULL until the invoice is issued
+ADD COLUMN exchange_rate NUMERIC(18, 8) CHECK (exchange_rate > 0);
+
+UPDATE invoices SET exchange_rate = 1 WHERE status <> 'draft';
+
+ALTER TABLE invoices ALTER COLUMN currency DROP DEFAULT;
+
+ALTER TABLE payments
+ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'USD' CHECK (currency ~ '^[A-Z]{3}$'),
+ADD COLUMN exchange_rate NUMERIC(18, 8) NOT NULL DEFAULT 1 CHECK (exchange_rate > 0);
+
+ALTER TABLE payments
+ALTER COLUMN currency DROP DEFAULT,
+ALTER COLUMN exchange_rate DROP DEFAULT;
+
+ALTER TABLE expenses
+ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'USD' CHECK (currency ~ '^[A-Z]{3}$'),
+ADD COLUMN exchange_rate NUMERIC(18, 8) NOT NULL DEFAULT 1 CHECK (exchange_rate > 0);
+
+ALTER TABLE expenses
+ALTER COLUMN currency DROP DEFAULT,
+ALTER COLUMN exchange_rate DROP DEFAULT;
+
+-- Create exchange rates table: one base_currency is worth `rate` quote_currency
+-- on rate_date. Rates are entered by hand or imported from files.
+CREATE TABLE exchange_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+
+    base_currency VARCHAR(3) NOT NULL CHECK (base_currency ~ '^[A-Z]{3}$'),
+    quote_currency VARCHAR(3) NOT NULL CHECK (quote_currency ~ '^[A-Z]{3}$'),
+    rate NUMERIC(18, 8) NOT NULL CHECK (rate > 0),
+    rate_date DATE NOT NULL,
+    source VARCHAR(10) NOT NULL DEFAULT 'manual' CHECK (source IN ('manual', 'csv', 'ecb')),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT exchange_rates_currencies_check CHECK (base_currency <> quote_currency),
+    CONSTRAINT exchange_rates_user_pair_date_key
+        UNIQUE (user_id, base_currency, quote_currency, rate_date)
+);
+
+CREATE INDEX idx_exchange_rates_user_id_rate_date ON exchange_rates(user_id, rate_date);
+
+CREATE TRIGGER update_exchange_rates_updated_at
+    BEFORE UPDATE ON exchange_rates
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Enable Row Level Security
+ALTER TABLE exchange_rates ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own exchange rates
+CREATE POLICY "Users can manage their own exchange rates"
+    ON exchange_rates FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
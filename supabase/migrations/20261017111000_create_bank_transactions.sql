@@ -0,0 +1,76 @@
+-- Create bank accounts table (accounts statements are imported into)
+CREATE TABLE bank_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+
+    name VARCHAR(255) NOT NULL,
+    -- IBAN or account number as printed on statements
+    account_number VARCHAR(50),
+    currency VARCHAR(3) NOT NULL CHECK (currency ~ '^[A-Z]{3}$'),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT bank_accounts_id_user_id_key UNIQUE (id, user_id)
+);
+
+CREATE INDEX idx_bank_accounts_user_id ON bank_accounts(user_id);
+
+CREATE TRIGGER update_bank_accounts_updated_at
+    BEFORE UPDATE ON bank_accounts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create bank transactions table (one line of a bank statement)
+CREATE TABLE bank_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    bank_account_id UUID NOT NULL,
+
+    transaction_date DATE NOT NULL,
+    -- Positive for money in, negative for money out
+    amount NUMERIC(14, 2) NOT NULL CHECK (amount <> 0),
+    description TEXT NOT NULL DEFAULT '',
+    reference VARCHAR(255),
+    counterparty VARCHAR(255),
+    -- The bank's own id for the transaction (OFX FITID, CAMT AcctSvcrRef)
+    external_id VARCHAR(255),
+
+    -- Hash identifying the transaction across imports of overlapping statements
+    fingerprint VARCHAR(64) NOT NULL,
+    source VARCHAR(10) NOT NULL CHECK (source IN ('csv', 'ofx', 'camt053')),
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT bank_transactions_bank_account_fkey FOREIGN KEY (bank_account_id, user_id)
+        REFERENCES bank_accounts(id, user_id) ON DELETE CASCADE,
+    CONSTRAINT bank_transactions_bank_account_id_fingerprint_key
+        UNIQUE (bank_account_id, fingerprint)
+);
+
+CREATE INDEX idx_bank_transactions_user_id ON bank_transactions(user_id);
+CREATE INDEX idx_bank_transactions_bank_account_id_date
+    ON bank_transactions(bank_account_id, transaction_date);
+
+CREATE TRIGGER update_bank_transactions_updated_at
+    BEFORE UPDATE ON bank_transactions
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Enable Row Level Security
+ALTER TABLE bank_accounts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE bank_transactions ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own bank accounts and transactions
+CREATE POLICY "Users can manage their own bank accounts"
+    ON bank_accounts FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage their own bank transactions"
+    ON bank_transactions FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
@@ -0,0 +1,105 @@
+-- Balance of the account before its first recorded transaction
+ALTER TABLE bank_accounts
+ADD COLUMN opening_balance NUMERIC(14, 2) NOT NULL DEFAULT 0;
+
+-- Create bank reconciliations table: a bank statement checked against the
+-- transactions recorded for its account, one session at a time
+CREATE TABLE bank_reconciliations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    bank_account_id UUID NOT NULL,
+
+    statement_date DATE NOT NULL,
+    statement_ending_balance NUMERIC(14, 2) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'in_progress'
+        CHECK (status IN ('in_progress', 'completed')),
+    completed_at TIMESTAMPTZ,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT bank_reconciliations_bank_account_fkey FOREIGN KEY (bank_account_id, user_id)
+        REFERENCES bank_accounts(id, user_id) ON DELETE CASCADE,
+    CONSTRAINT bank_reconciliations_completed_check
+        CHECK ((status = 'completed') = (completed_at IS NOT NULL))
+);
+
+CREATE INDEX idx_bank_reconciliations_user_id ON bank_reconciliations(user_id);
+CREATE INDEX idx_bank_reconciliations_bank_account_id
+    ON bank_reconciliations(bank_account_id, statement_date);
+
+-- At most one reconciliation in progress per bank account
+CREATE UNIQUE INDEX idx_bank_reconciliations_one_in_progress
+    ON bank_reconciliations(bank_account_id)
+    WHERE status = 'in_progress';
+
+CREATE TRIGGER update_bank_reconciliations_updated_at
+    BEFORE UPDATE ON bank_reconciliations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Create bank rules table: withdrawals whose description contains the pattern
+-- are recorded as expenses in the rule's category
+CREATE TABLE bank_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    -- Account the rule applies to (NULL = all accounts)
+    bank_account_id UUID,
+
+    name VARCHAR(100) NOT NULL,
+    -- Case-insensitive text to look for in the description or counterparty
+    pattern VARCHAR(255) NOT NULL CHECK (pattern <> ''),
+    -- Vendor of created expenses (NULL = the transaction's counterparty)
+    vendor VARCHAR(255),
+    category VARCHAR(100) NOT NULL,
+    -- Tax included in the withdrawn amount
+    tax_rate_id UUID,
+    -- Rules with a higher priority are tried first
+    priority INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT bank_rules_bank_account_fkey FOREIGN KEY (bank_account_id, user_id)
+        REFERENCES bank_accounts(id, user_id) ON DELETE CASCADE,
+    CONSTRAINT bank_rules_tax_rate_fkey FOREIGN KEY (tax_rate_id, user_id)
+        REFERENCES tax_rates(id, user_id) ON DELETE RESTRICT
+);
+
+CREATE INDEX idx_bank_rules_user_id ON bank_rules(user_id);
+
+CREATE TRIGGER update_bank_rules_updated_at
+    BEFORE UPDATE ON bank_rules
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Transactions can also be entered by hand, are ticked off in a
+-- reconciliation, and may be recorded as an expense
+ALTER TABLE bank_transactions DROP CONSTRAINT bank_transactions_source_check;
+
+ALTER TABLE bank_transactions
+ADD CONSTRAINT bank_transactions_source_check
+    CHECK (source IN ('manual', 'csv', 'ofx', 'camt053')),
+ADD COLUMN reconciliation_id UUID REFERENCES bank_reconciliations(id) ON DELETE SET NULL,
+ADD COLUMN expense_id UUID REFERENCES expenses(id) ON DELETE SET NULL,
+ADD COLUMN bank_rule_id UUID REFERENCES bank_rules(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_bank_transactions_reconciliation_id ON bank_transactions(reconciliation_id);
+
+-- Enable Row Level Security
+ALTER TABLE bank_reconciliations ENABLE ROW LEVEL SECURITY;
+ALTER TABLE bank_rules ENABLE ROW LEVEL SECURITY;
+
+-- RLS Policies: Users can only access their own reconciliations and rules
+CREATE POLICY "Users can manage their own bank reconciliations"
+    ON bank_reconciliations FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
+
+CREATE POLICY "Users can manage their own bank rules"
+    ON bank_rules FOR ALL
+    USING (auth.uid() = user_id)
+    WITH CHECK (auth.uid() = user_id);
@@ -0,0 +1,18 @@
+-- Create OAuth flow states table: the PKCE code verifier of a social login
+-- started by the API, kept until the provider redirects back with a code
+CREATE TABLE oauth_flow_states (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+    provider VARCHAR(20) NOT NULL CHECK (provider IN ('google', 'github', 'microsoft')),
+    code_verifier VARCHAR(128) NOT NULL,
+
+    -- Metadata
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL DEFAULT NOW() + INTERVAL '10 minutes'
+);
+
+CREATE INDEX idx_oauth_flow_states_expires_at ON oauth_flow_states(expires_at);
+
+-- Enable Row Level Security without policies: only the API's database role,
+-- which bypasses RLS, may read verifiers
+ALTER TABLE oauth_flow_states ENABLE ROW LEVEL SECURITY;
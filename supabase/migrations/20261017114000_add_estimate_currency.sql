@@ -0,0 +1,15 @@
+-- Estimates are quoted in a currency, carried over to the invoice on conversion.
+-- The exchange rate is optional: when set, it is the rate agreed with the client.
+ALTER TABLE estimates
+ADD COLUMN currency VARCHAR(3) CHECK (currency ~ '^[A-Z]{3}$'),
+ADD COLUMN exchange_rate NUMERIC(18, 8) CHECK (exchange_rate > 0);
+
+-- Existing estimates were quoted in the client's currency at the time
+UPDATE estimates e
+SET currency = COALESCE(
+    (SELECT c.currency FROM clients c WHERE c.id = e.client_id),
+    (SELECT b.home_currency FROM business_profiles b WHERE b.user_id = e.user_id),
+    'USD'
+);
+
+ALTER TABLE estimates ALTER COLUMN currency SET NOT NULL;
@@ -0,0 +1,37 @@
+-- Create credit note allocations table (client credit left over by a credit
+-- note, applied later to settle invoices of the same client and currency)
+CREATE TABLE credit_note_allocations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    credit_note_id UUID NOT NULL REFERENCES credit_notes(id) ON DELETE CASCADE,
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE RESTRICT,
+    amount NUMERIC(14, 2) NOT NULL CHECK (amount > 0),
+    -- Day the credit settled the invoice, matching the ledger entry
+    applied_on DATE NOT NULL DEFAULT CURRENT_DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT credit_note_allocations_credit_note_invoice_key UNIQUE (credit_note_id, invoice_id)
+);
+
+CREATE INDEX idx_credit_note_allocations_invoice_id ON credit_note_allocations(invoice_id);
+
+-- The unapplied amount now also shrinks as the credit is allocated
+ALTER TABLE credit_notes DROP CONSTRAINT credit_notes_amounts_check;
+ALTER TABLE credit_notes ADD CONSTRAINT credit_notes_amounts_check CHECK (
+    amount_applied >= 0 AND unapplied_amount >= 0 AND amount_applied + unapplied_amount <= total
+);
+
+-- Enable Row Level Security
+ALTER TABLE credit_note_allocations ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY "Users can manage allocations of their own credit notes"
+    ON credit_note_allocations FOR ALL
+    USING (EXISTS (
+        SELECT 1 FROM credit_notes
+        WHERE credit_notes.id = credit_note_allocations.credit_note_id
+            AND credit_notes.user_id = auth.uid()
+    ))
+    WITH CHECK (EXISTS (
+        SELECT 1 FROM credit_notes
+        WHERE credit_notes.id = credit_note_allocations.credit_note_id
+            AND credit_notes.user_id = auth.uid()
+    ));
@@ -0,0 +1,3 @@
+-- The PKCE code verifier of a social login now stays with the browser that
+-- started it, so the API no longer keeps one per flow
+DROP TABLE oauth_flow_states;